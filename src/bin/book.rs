@@ -0,0 +1,95 @@
+/* Builds a Polyglot opening book from PGN game collections */
+
+use std::{env, fs, process::ExitCode};
+
+use rusty_engine::{
+    repr::move_gen::MoveGen,
+    utils::{book_builder::BookBuilder, zobrist::Zobrist},
+};
+
+const DEFAULT_PLIES: usize = 20;
+const USAGE: &str =
+    "usage: book <out.bin> <games.pgn>... [--plies <n>] [--min-count <n>] [--min-score <percent>] [--variations]";
+
+struct Args {
+    out_path: String,
+    pgn_paths: Vec<String>,
+    builder: BookBuilder,
+}
+
+fn main() -> ExitCode {
+    let Args { out_path, pgn_paths, mut builder } = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let move_gen: MoveGen = MoveGen::init();
+    let zobrist: Zobrist = Zobrist::default();
+
+    let mut nof_games: usize = 0;
+    for path in pgn_paths.iter() {
+        let text: String = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("Could not read {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        };
+        match builder.add_pgn(&text, &move_gen, &zobrist) {
+            Ok(added) => {
+                println!("{}: {} games", path, added);
+                nof_games += added;
+            }
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let nof_entries: usize = builder.entries().len();
+    if let Err(err) = builder.write(&out_path) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Wrote {} entries for {} positions from {} games to {}",
+        nof_entries,
+        builder.nof_positions(),
+        nof_games,
+        out_path
+    );
+    ExitCode::SUCCESS
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut paths: Vec<String> = vec![];
+    let mut builder: BookBuilder = BookBuilder::new(DEFAULT_PLIES);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plies" | "--min-count" | "--min-score" => {
+                let value: u32 = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or(format!("{} needs a number", arg))?;
+                match arg.as_str() {
+                    "--plies" => builder.max_ply = value as usize,
+                    "--min-count" => builder.min_count = value,
+                    _ if value <= 100 => builder.min_performance = value as f64 / 100.0,
+                    _ => return Err("--min-score must be within 0..=100".to_string()),
+                }
+            }
+            "--variations" => builder.include_variations = true,
+            _ if !arg.starts_with("--") => paths.push(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    if paths.len() < 2 {
+        return Err("Missing output book or PGN file".to_string());
+    }
+    let out_path: String = paths.remove(0);
+    Ok(Args { out_path, pgn_paths: paths, builder })
+}
@@ -0,0 +1,133 @@
+/* Runs an EPD test suite, e.g. WAC or STS, and reports solved/failed counts */
+
+use std::{env, fs, process::ExitCode};
+
+use rusty_engine::{
+    repr::{move_gen::MoveGen, position::Position},
+    search::{search_config::SearchMode, searcher::MAX_SEARCH_DEPTH},
+    utils::{
+        epd::{self, EpdOutcome, EpdRecord},
+        zobrist::Zobrist,
+    },
+};
+
+const DEFAULT_TIME: u64 = 1000; //ms per position
+const USAGE: &str = "usage: epd <suite.epd> [--time <ms> | --depth <plies>]";
+
+fn main() -> ExitCode {
+    let (path, search_mode) = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let text: String = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let move_gen: MoveGen = MoveGen::init();
+    let zobrist: Zobrist = Zobrist::default();
+    let records: Vec<EpdRecord> = match epd::parse_epd(&text, &move_gen, &zobrist) {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed: Vec<String> = vec![];
+    let mut solution_times: Vec<u64> = vec![];
+    let mut total_time: u64 = 0;
+    let mut total_nodes: u64 = 0;
+    for (i, record) in records.iter().enumerate() {
+        let name: String = record.id.clone().unwrap_or_else(|| format!("#{}", i + 1));
+        let outcome: EpdOutcome = epd::run_record(record, search_mode, &move_gen, &zobrist);
+        let pos: Position = Position::from(&record.fen, &move_gen, &zobrist).expect("Record FEN was validated on parse");
+        let played: String = outcome
+            .best_move
+            .map_or("none".to_string(), |mov| pos.move_to_san(mov, &move_gen, &zobrist));
+        let expected: String = record
+            .best_moves
+            .iter()
+            .map(|mov| pos.move_to_san(*mov, &move_gen, &zobrist))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        println!(
+            "[{:>4}/{}] {:<16} {:<6} {:<8} bm {:<12} depth {:>2} eval {:>6} tts {:>7} time {:>6} ms",
+            i + 1,
+            records.len(),
+            name,
+            if outcome.solved { "solved" } else { "FAILED" },
+            played,
+            if expected.is_empty() { "-" } else { &expected },
+            outcome.depth,
+            outcome.eval,
+            outcome.time_to_solution.map_or("-".to_string(), |t| format!("{} ms", t)),
+            outcome.time_ms,
+        );
+        total_time += outcome.time_ms;
+        total_nodes += outcome.nodes;
+        match outcome.time_to_solution {
+            Some(t) => solution_times.push(t),
+            None => failed.push(name),
+        }
+    }
+
+    let solved: usize = solution_times.len();
+    println!();
+    println!(
+        "Solved {}/{} ({:.1}%), failed {}",
+        solved,
+        records.len(),
+        100.0 * solved as f64 / records.len().max(1) as f64,
+        failed.len()
+    );
+    if solved > 0 {
+        println!(
+            "Time to solution: total {} ms, average {} ms",
+            solution_times.iter().sum::<u64>(),
+            solution_times.iter().sum::<u64>() / solved as u64
+        );
+    }
+    println!(
+        "Searched {} nodes in {} ms ({} nps)",
+        total_nodes,
+        total_time,
+        total_nodes * 1000 / total_time.max(1)
+    );
+    if !failed.is_empty() {
+        println!("Failed: {}", failed.join(" "));
+    }
+    ExitCode::SUCCESS
+}
+
+fn parse_args(args: Vec<String>) -> Result<(String, SearchMode), String> {
+    let mut path: Option<String> = None;
+    let mut search_mode: SearchMode = SearchMode::StaticTime(DEFAULT_TIME);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time" | "--depth" => {
+                let value: u64 = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or(format!("{} needs a number", arg))?;
+                search_mode = if arg == "--time" {
+                    SearchMode::StaticTime(value)
+                } else if (1..=MAX_SEARCH_DEPTH as u64).contains(&value) {
+                    SearchMode::StaticDepth(value as usize)
+                } else {
+                    return Err(format!("--depth must be within 1..={}", MAX_SEARCH_DEPTH));
+                };
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    Ok((path.ok_or("Missing EPD file")?, search_mode))
+}
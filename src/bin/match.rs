@@ -0,0 +1,283 @@
+/* Plays a match between two engine configurations and reports Elo and SPRT results */
+
+use std::{env, fs, path::Path, process::ExitCode, sync::Arc};
+
+use rusty_engine::{
+    game::engine_match::{self, GameRecord, MatchPlayer, MatchScore, SearcherPlayer, Sprt, SprtDecision, UciPlayer},
+    repr::move_gen::MoveGen,
+    search::{
+        nnue::Network,
+        search_config::{EvalKind, SearchMode},
+        searcher::MAX_SEARCH_DEPTH,
+        syzygy::SyzygyTablebase,
+        tablebase::LocalTablebase,
+    },
+    utils::{fen_tool::DEFAULT_FEN, zobrist::Zobrist},
+};
+
+const DEFAULT_GAMES: u32 = 1000;
+const DEFAULT_TIME: u64 = 100; //ms per move
+const USAGE: &str = "usage: match --engine <spec> --engine <spec> [--openings <file.epd|file.pgn>] [--games <n>] \
+[--time <ms> | --depth <plies>] [--elo0 <elo>] [--elo1 <elo>] [--alpha <p>] [--beta <p>] [--pgn <out.pgn>]
+engine spec: comma separated key=value pairs
+  in process: name, eval=handcrafted|nnue, network=<file>, quiescence=on|off, tablebase=<dir>, syzygy=<dirs>, tbdepth=<plies>
+  over UCI:   cmd=<binary>, name, arg=<argument>, option.<Name>=<value>";
+
+struct MatchArgs {
+    engines: Vec<String>,
+    openings: Option<String>,
+    games: u32,
+    search_mode: SearchMode,
+    sprt: Sprt,
+    pgn: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args: MatchArgs = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let move_gen: MoveGen = MoveGen::init();
+    let zobrist: Zobrist = Zobrist::default();
+    let openings: Vec<String> = match &args.openings {
+        Some(path) => match load_openings(path, &move_gen, &zobrist) {
+            Ok(openings) if !openings.is_empty() => openings,
+            Ok(_) => {
+                eprintln!("{}: no openings", path);
+                return ExitCode::FAILURE;
+            }
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => vec![DEFAULT_FEN.to_string()],
+    };
+    let chess960: bool = engine_match::needs_chess960(&openings, &move_gen, &zobrist);
+    let mut players: Vec<Box<dyn MatchPlayer>> = vec![];
+    for (i, spec) in args.engines.iter().enumerate() {
+        match build_player(spec, &format!("Engine {}", i + 1), chess960) {
+            Ok(player) => players.push(player),
+            Err(err) => {
+                eprintln!("--engine {}: {}", spec, err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let (first, second) = players.split_at_mut(1);
+    let (first, second): (&mut dyn MatchPlayer, &mut dyn MatchPlayer) = (first[0].as_mut(), second[0].as_mut());
+    let (name1, name2): (String, String) = (first.name().to_string(), second.name().to_string());
+
+    let mut pgn_text: String = String::new();
+    let mut score: MatchScore = MatchScore::default();
+    let mut decision: SprtDecision = SprtDecision::Continue;
+    let (lower, upper): (f64, f64) = args.sprt.bounds();
+    for i in 0..args.games {
+        //every opening is played twice, with colours reversed
+        let opening: &str = &openings[(i / 2) as usize % openings.len()];
+        let first_is_white: bool = i % 2 == 0;
+        let record: GameRecord = match if first_is_white {
+            engine_match::play_game(first, second, opening, args.search_mode, &move_gen, &zobrist)
+        } else {
+            engine_match::play_game(second, first, opening, args.search_mode, &move_gen, &zobrist)
+        } {
+            Ok(record) => record,
+            Err(err) => {
+                eprintln!("Opening {}: {}", opening, err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let (white, black): (&str, &str) = if first_is_white { (&name1, &name2) } else { (&name2, &name1) };
+        score.add(if first_is_white { record.white_score() } else { 1.0 - record.white_score() });
+        println!(
+            "[{:>4}/{}] {} vs {}: {} ({}, {} plies)",
+            i + 1,
+            args.games,
+            white,
+            black,
+            record.pgn_result(),
+            record.termination(),
+            record.moves.len()
+        );
+        print_score(&name1, &name2, &score, &args.sprt);
+        if args.pgn.is_some() {
+            let round: String = (i + 1).to_string();
+            let tags: [(&str, &str); 4] = [("Event", "match"), ("Round", &round), ("White", white), ("Black", black)];
+            pgn_text.push_str(&record.to_pgn(&tags, &move_gen, &zobrist));
+            pgn_text.push('\n');
+        }
+        decision = args.sprt.decision(&score);
+        if decision != SprtDecision::Continue {
+            break;
+        }
+    }
+
+    if let Some(path) = &args.pgn {
+        if let Err(err) = fs::write(path, pgn_text) {
+            eprintln!("Could not write {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!();
+    println!(
+        "SPRT elo0 {} elo1 {} alpha {} beta {}: {}",
+        args.sprt.elo0,
+        args.sprt.elo1,
+        args.sprt.alpha,
+        args.sprt.beta,
+        match decision {
+            SprtDecision::AcceptH1 => format!("H1 accepted, {} is stronger (LLR >= {:.2})", name1, upper),
+            SprtDecision::AcceptH0 => format!("H0 accepted, {} is not stronger (LLR <= {:.2})", name1, lower),
+            SprtDecision::Continue => "no decision".to_string(),
+        }
+    );
+    ExitCode::SUCCESS
+}
+
+fn print_score(name1: &str, name2: &str, score: &MatchScore, sprt: &Sprt) {
+    let (lower, upper): (f64, f64) = sprt.bounds();
+    println!(
+        "Score of {} vs {}: {} - {} - {} [{:.3}] {}\nElo {:.1} +/- {:.1}, LLR {:.2} ({:.2}, {:.2})",
+        name1,
+        name2,
+        score.wins,
+        score.losses,
+        score.draws,
+        score.score(),
+        score.games(),
+        score.elo(),
+        score.elo_error(),
+        score.llr(sprt.elo0, sprt.elo1),
+        lower,
+        upper
+    );
+}
+
+fn load_openings(path: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Vec<String>, String> {
+    let text: String = fs::read_to_string(path).map_err(|err| format!("Could not read: {}", err))?;
+    let is_pgn: bool = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pgn"));
+    if is_pgn {
+        engine_match::openings_from_pgn(&text, move_gen, zobrist)
+    } else {
+        engine_match::openings_from_epd(&text, move_gen, zobrist)
+    }
+}
+
+fn build_player(spec: &str, default_name: &str, chess960: bool) -> Result<Box<dyn MatchPlayer>, String> {
+    let mut settings: Vec<(&str, &str)> = vec![];
+    for pair in spec.split(',').filter(|pair| !pair.is_empty()) {
+        settings.push(pair.split_once('=').ok_or(format!("Expected key=value: {}", pair))?);
+    }
+    let name: Option<&str> = settings.iter().find(|(key, _)| *key == "name").map(|(_, value)| *value);
+
+    if let Some((_, cmd)) = settings.iter().find(|(key, _)| *key == "cmd") {
+        let mut args: Vec<String> = vec![];
+        let mut options: Vec<(String, String)> = vec![];
+        for (key, value) in settings.iter() {
+            match *key {
+                "cmd" | "name" => {}
+                "arg" => args.push(value.to_string()),
+                _ => match key.strip_prefix("option.") {
+                    Some(option) => options.push((option.to_string(), value.to_string())),
+                    None => return Err(format!("Unknown UCI engine key: {}", key)),
+                },
+            }
+        }
+        return Ok(Box::new(UciPlayer::launch(cmd, &args, &options, name, chess960)?));
+    }
+
+    let mut player: SearcherPlayer = SearcherPlayer::new(name.unwrap_or(default_name));
+    let searcher = &mut player.cpu_game.searcher;
+    for (key, value) in settings.iter() {
+        match *key {
+            "name" => {}
+            "eval" => {
+                searcher.search_config.eval_kind = match *value {
+                    "handcrafted" => EvalKind::Handcrafted,
+                    "nnue" => EvalKind::Nnue,
+                    _ => return Err(format!("eval must be handcrafted or nnue: {}", value)),
+                }
+            }
+            "network" => {
+                searcher.network = Some(Arc::new(Network::load(value)?));
+                searcher.search_config.eval_kind = EvalKind::Nnue;
+            }
+            "quiescence" => {
+                searcher.search_config.quiescence = match *value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("quiescence must be on or off: {}", value)),
+                }
+            }
+            "tablebase" => searcher.tablebase = Some(Arc::new(LocalTablebase::load(value)?)),
+            "syzygy" => searcher.tablebase = Some(Arc::new(SyzygyTablebase::open(value)?)),
+            "tbdepth" => {
+                searcher.search_config.tb_probe_depth = value
+                    .parse()
+                    .ok()
+                    .filter(|d| (1..=MAX_SEARCH_DEPTH).contains(d))
+                    .ok_or(format!("tbdepth must be within 1..={}", MAX_SEARCH_DEPTH))?
+            }
+            _ => return Err(format!("Unknown engine key: {}", key)),
+        }
+    }
+    Ok(Box::new(player))
+}
+
+fn parse_args(args: Vec<String>) -> Result<MatchArgs, String> {
+    let mut match_args: MatchArgs = MatchArgs {
+        engines: vec![],
+        openings: None,
+        games: DEFAULT_GAMES,
+        search_mode: SearchMode::StaticTime(DEFAULT_TIME),
+        sprt: Sprt::default(),
+        pgn: None,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--engine" => match_args.engines.push(value()?),
+            "--openings" => match_args.openings = Some(value()?),
+            "--pgn" => match_args.pgn = Some(value()?),
+            "--games" => {
+                match_args.games = value()?
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or("--games needs a positive number")?
+            }
+            "--time" | "--depth" => {
+                let n: u64 = value()?.parse().map_err(|_| format!("{} needs a number", arg))?;
+                match_args.search_mode = if arg == "--time" {
+                    SearchMode::StaticTime(n)
+                } else if (1..=MAX_SEARCH_DEPTH as u64).contains(&n) {
+                    SearchMode::StaticDepth(n as usize)
+                } else {
+                    return Err(format!("--depth must be within 1..={}", MAX_SEARCH_DEPTH));
+                };
+            }
+            "--elo0" | "--elo1" | "--alpha" | "--beta" => {
+                let x: f64 = value()?.parse().map_err(|_| format!("{} needs a number", arg))?;
+                match arg.as_str() {
+                    "--elo0" => match_args.sprt.elo0 = x,
+                    "--elo1" => match_args.sprt.elo1 = x,
+                    _ if !(x > 0.0 && x < 0.5) => return Err(format!("{} must be within (0, 0.5)", arg)),
+                    "--alpha" => match_args.sprt.alpha = x,
+                    _ => match_args.sprt.beta = x,
+                }
+            }
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    if match_args.engines.len() != 2 {
+        return Err("Exactly two --engine specs are needed".to_string());
+    }
+    if match_args.sprt.elo1 <= match_args.sprt.elo0 {
+        return Err("--elo1 must be greater than --elo0".to_string());
+    }
+    Ok(match_args)
+}
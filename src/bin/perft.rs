@@ -0,0 +1,54 @@
+/* Counts move generation paths of a position, optionally split by root move */
+
+use std::{env, process::ExitCode, time::Instant};
+
+use rusty_engine::{
+    repr::{_move, move_gen::MoveGen, perft, position::Position},
+    search::searcher::MAX_SEARCH_DEPTH,
+    utils::{fen_tool::DEFAULT_FEN, zobrist::Zobrist},
+};
+
+const USAGE: &str = "usage: perft <depth> [<fen>] [--divide]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let divide: bool = args.iter().any(|arg| arg == "--divide");
+    let mut rest = args.iter().filter(|arg| *arg != "--divide");
+    let depth: usize = match rest.next().and_then(|d| d.parse().ok()) {
+        Some(depth) if depth <= MAX_SEARCH_DEPTH => depth,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let fen: String = match rest.map(String::as_str).collect::<Vec<&str>>().join(" ") {
+        fen if fen.is_empty() => DEFAULT_FEN.to_string(),
+        fen => fen,
+    };
+
+    let move_gen: MoveGen = MoveGen::init();
+    let zobrist: Zobrist = Zobrist::default();
+    let mut pos: Position = match Position::from(&fen, &move_gen, &zobrist) {
+        Ok(pos) => pos,
+        Err(err) => {
+            eprintln!("{}: {}", err, fen);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start: Instant = Instant::now();
+    let nodes: u64 = if divide && depth > 0 {
+        let counts: Vec<(u32, u64)> = perft::perft_divide(depth, &mut pos, &move_gen, &zobrist);
+        for (mov, nodes) in counts.iter() {
+            println!("{}: {}", _move::to_uci(*mov, &pos.board), nodes);
+        }
+        println!();
+        counts.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft::perft(depth, &mut pos, &move_gen, &zobrist)
+    };
+    let ms: u128 = start.elapsed().as_millis();
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {} ms ({} nps)", ms, nodes as u128 * 1000 / ms.max(1));
+    ExitCode::SUCCESS
+}
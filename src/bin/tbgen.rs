@@ -0,0 +1,73 @@
+/* Generates local endgame tablebases into a directory */
+
+use std::{env, process::ExitCode, time::Instant};
+
+use rusty_engine::{
+    repr::move_gen::MoveGen,
+    search::tablebase::{LocalTablebase, MAX_LOCAL_PIECES},
+    utils::zobrist::Zobrist,
+};
+
+const USAGE: &str = "usage: tbgen <dir> [--pieces <n>] [<material>...], e.g. tbgen tb KQvKR KRPvK";
+
+struct Args {
+    dir: String,
+    max_pieces: u32,
+    materials: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let Args { dir, max_pieces, materials } = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let move_gen: MoveGen = MoveGen::init();
+    let zobrist: Zobrist = Zobrist::default();
+    //tables already in the directory are not generated again
+    let mut tablebase: LocalTablebase = LocalTablebase::load(&dir).unwrap_or_default();
+    let started: Instant = Instant::now();
+
+    let generated = if materials.is_empty() {
+        tablebase.generate_all(max_pieces, &move_gen, &zobrist)
+    } else {
+        materials.iter().try_for_each(|material| tablebase.generate(material, &move_gen, &zobrist))
+    };
+    if let Err(err) = generated.and_then(|_| tablebase.save(&dir)) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "{} tables in {} after {:.1}s: {}",
+        tablebase.len(),
+        dir,
+        started.elapsed().as_secs_f64(),
+        tablebase.names().join(" ")
+    );
+    ExitCode::SUCCESS
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut dir: Option<String> = None;
+    let mut max_pieces: u32 = MAX_LOCAL_PIECES;
+    let mut materials: Vec<String> = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pieces" => {
+                max_pieces = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|pieces| (3..=MAX_LOCAL_PIECES).contains(pieces))
+                    .ok_or(format!("--pieces needs a number within 3..={}", MAX_LOCAL_PIECES))?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unexpected argument: {}", arg)),
+            _ if dir.is_none() => dir = Some(arg),
+            _ => materials.push(arg),
+        }
+    }
+    let dir: String = dir.ok_or("Missing output directory")?;
+    Ok(Args { dir, max_pieces, materials })
+}
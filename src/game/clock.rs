@@ -0,0 +1,145 @@
+/* Chess clocks for Game: Fischer increment, Bronstein delay and moves-per-session controls */
+
+use std::time::Instant;
+
+use crate::{repr::types::WHITE, search::search_config::SearchMode};
+
+///All times in ms
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: u64,
+    pub increment: u64,                 //Fischer, added after every move
+    pub delay: u64,                     //Bronstein, time used up to this much is given back after every move
+    pub moves_per_session: Option<u32>, //base is added again after every this many moves of a side
+}
+
+impl TimeControl {
+    pub fn sudden_death(base: u64) -> Self {
+        Self {
+            base,
+            increment: 0,
+            delay: 0,
+            moves_per_session: None,
+        }
+    }
+
+    pub fn fischer(base: u64, increment: u64) -> Self {
+        Self {
+            increment,
+            ..Self::sudden_death(base)
+        }
+    }
+
+    pub fn bronstein(base: u64, delay: u64) -> Self {
+        Self {
+            delay,
+            ..Self::sudden_death(base)
+        }
+    }
+
+    ///e.g. 40 moves in 90 minutes, repeating
+    pub fn sessions(moves: u32, base: u64) -> Self {
+        Self {
+            moves_per_session: Some(moves),
+            ..Self::sudden_death(base)
+        }
+    }
+}
+
+///Both sides' clocks, indexed by color. Only the side to move's clock runs. <br>
+///Methods take the current instant so callers decide when time is read.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    pub time_control: TimeControl,
+    remaining: [u64; 2], //at the start of the running side's move
+    moves_made: [u32; 2],
+    running: Option<(u32, Instant)>, //side to move and when its clock was started
+    flagged: Option<u32>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        Self {
+            time_control,
+            remaining: [time_control.base; 2],
+            moves_made: [0; 2],
+            running: None,
+            flagged: None,
+        }
+    }
+
+    ///Starts **turn**'s clock, stopping the other one without adding anything to it
+    pub fn start(&mut self, turn: u32, now: Instant) {
+        self.stop(now);
+        self.running = Some((turn, now));
+    }
+
+    ///Stops the running clock, e.g. when the game ended
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((turn, _)) = self.running {
+            self.remaining[turn as usize] = self.remaining(turn, now);
+            self.running = None;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    ///Time left for **color**, counting down while its clock runs
+    pub fn remaining(&self, color: u32, now: Instant) -> u64 {
+        match self.running {
+            Some((turn, started)) if turn == color => {
+                self.remaining[color as usize].saturating_sub(now.saturating_duration_since(started).as_millis() as u64)
+            }
+            _ => self.remaining[color as usize],
+        }
+    }
+
+    ///Side whose time ran out, only the running clock can fall
+    pub fn flag(&mut self, now: Instant) -> Option<u32> {
+        if self.flagged.is_none() {
+            if let Some((turn, started)) = self.running {
+                if now.saturating_duration_since(started).as_millis() as u64 >= self.remaining[turn as usize] {
+                    self.flagged = Some(turn);
+                    self.running = None;
+                    self.remaining[turn as usize] = 0;
+                }
+            }
+        }
+        self.flagged
+    }
+
+    ///Ends the running side's move: applies delay, increment and session time, then starts the opponent's clock. <br>
+    ///Err with the flagged side if the time ran out before the move.
+    pub fn punch(&mut self, now: Instant) -> Result<(), u32> {
+        if let Some(flagged) = self.flag(now) {
+            return Err(flagged);
+        }
+        let Some((turn, started)) = self.running else {
+            return Ok(());
+        };
+        let used: u64 = now.saturating_duration_since(started).as_millis() as u64;
+        let tc: TimeControl = self.time_control;
+        let side: usize = turn as usize;
+        self.moves_made[side] += 1;
+        self.remaining[side] = self.remaining[side] - used + used.min(tc.delay) + tc.increment;
+        if tc.moves_per_session.is_some_and(|moves| self.moves_made[side].is_multiple_of(moves)) {
+            self.remaining[side] += tc.base;
+        }
+        self.running = Some((turn ^ 1, now));
+        Ok(())
+    }
+
+    ///Search budget for **turn** from the live clock. The Bronstein delay counts like an increment since it is free to use.
+    pub fn search_mode(&self, turn: u32, now: Instant) -> SearchMode {
+        let bonus: u64 = self.time_control.increment + self.time_control.delay;
+        SearchMode::time_control_with_margin(
+            self.remaining(WHITE, now),
+            self.remaining(WHITE ^ 1, now),
+            bonus,
+            bonus,
+            turn == WHITE,
+        )
+    }
+}
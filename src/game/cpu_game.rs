@@ -1,5 +1,7 @@
 use crate::{
-    repr::{_move::{self, from_string, promotion_matches}, move_gen::MoveGen, position::Position}, search::searcher::Searcher, utils::zobrist::Zobrist,
+    game::{game_state::GameState, pgn::PgnGame},
+    repr::{_move::{self, from_string, promotion_matches}, move_gen::MoveGen, position::Position}, search::searcher::Searcher,
+    utils::{fen_tool::DEFAULT_FEN, polyglot::PolyglotBook, zobrist::Zobrist},
 };
 
 /// Game object that is optimized for CPU vs CPU games, no need for game state tracking
@@ -9,6 +11,10 @@ pub struct CpuGame {
     pub searcher: Searcher,
     pub move_gen: MoveGen,
     pub zobrist: Zobrist,
+    pub start_fen: String, //base position of the last import, for PGN export
+    pub chess960: bool,    //UCI_Chess960, castling is read and written as king takes rook
+    pub book: Option<PolyglotBook>, //BookFile
+    pub own_book: bool,             //OwnBook, play from the book before searching
 }
 
 impl CpuGame {
@@ -23,6 +29,7 @@ impl CpuGame {
                     return Err(format!("Invalid FEN: {}", err));
                 }
             };
+        new_pos.board.chess960 |= self.chess960;
         let mut board_hash_history: Vec<u64> = vec![new_pos.board.zhash];
 
         for m in moves.iter() {
@@ -36,7 +43,7 @@ impl CpuGame {
             let mov: u32 =
                 match new_pos.legal_moves().iter().copied().find(|mov| {
                     _move::get_init(*mov) == from
-                        && _move::get_target(*mov) == to
+                        && _move::uci_target(*mov, &new_pos.board) == to
                         && promotion_matches(*mov, promotion)
                 }) {
                     Some(mov) => mov,
@@ -53,6 +60,7 @@ impl CpuGame {
 
         self.searcher.import_position(&new_pos, Some(board_hash_history));
         self.position = new_pos;
+        self.start_fen = base_pos_fen.to_string();
         Ok(())
     }
 
@@ -60,7 +68,7 @@ impl CpuGame {
         let mov: u32 = match from_string(mov) {
             Ok((from, to, promotion)) => match self.position.legal_moves().iter().copied().find(|mov| {
                 _move::get_init(*mov) == from
-                    && _move::get_target(*mov) == to
+                    && _move::uci_target(*mov, &self.position.board) == to
                     && promotion_matches(*mov, promotion)
             }) {
                 Some(mov) => mov,
@@ -77,6 +85,30 @@ impl CpuGame {
         Ok(())
     }
 
+    /// Move from the opening book if OwnBook is set and the position is in it, consulted before starting a search <br>
+    /// Polyglot keys only know standard castling, so Chess960 games never use the book
+    pub fn book_move(&self) -> Option<u32> {
+        if !self.own_book || self.position.board.chess960 {
+            return None;
+        }
+        self.book.as_ref()?.pick(&self.position, &mut rand::rng())
+    }
+
+    ///CpuGame does not track its game state, so the caller provides it for the result
+    pub fn to_pgn(&self, tags: &[(&str, &str)], evals: Option<&[i16]>, game_state: &GameState) -> String {
+        PgnGame::from_moves(
+            tags,
+            &self.start_fen,
+            &self.position.played_moves_stack,
+            evals,
+            game_state.pgn_result(),
+            &self.move_gen,
+            &self.zobrist,
+        )
+        .expect("Played moves are legal")
+        .to_string()
+    }
+
 }
 
 impl Default for CpuGame {
@@ -90,6 +122,10 @@ impl Default for CpuGame {
             searcher,
             move_gen,
             zobrist,
+            start_fen: DEFAULT_FEN.to_string(),
+            chess960: false,
+            book: None,
+            own_book: false,
         }
     }
 }
@@ -0,0 +1,386 @@
+/* Engine versus engine matches with Elo estimates and a sequential probability ratio test */
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    game::{cpu_game::CpuGame, game_state::GameState, pgn::{self, PgnGame}},
+    repr::{_move, board::Board, move_gen::MoveGen, position::Position, types::WHITE},
+    search::search_config::SearchMode,
+    uci::{
+        client::{SearchReply, UciClient},
+        uci_command::{GoCommand, PositionCommand},
+    },
+    utils::{epd, fen_tool::board_to_fen, zobrist::Zobrist},
+};
+
+const MOVE_TIMEOUT_MARGIN: Duration = Duration::from_secs(5); //on top of the movetime before an engine forfeits
+const DEPTH_MOVE_TIMEOUT: Duration = Duration::from_secs(300);
+const Z_95: f64 = 1.959964; //two sided 95% quantile of the normal distribution
+
+/// One side of a match, asked for a move in UCI notation
+pub trait MatchPlayer {
+    fn name(&self) -> &str;
+    fn new_game(&mut self) -> Result<(), String>;
+    /// Move for the position after **moves** (UCI notation) from **start_fen**
+    fn best_move(&mut self, start_fen: &str, moves: &[String], search_mode: SearchMode) -> Result<String, String>;
+}
+
+/// Searches in process, configured through the CpuGame's searcher (SearchConfig, Evaluator, network, tablebase)
+pub struct SearcherPlayer {
+    pub name: String,
+    pub cpu_game: CpuGame,
+}
+
+impl SearcherPlayer {
+    pub fn new(name: &str) -> Self {
+        let mut cpu_game: CpuGame = CpuGame::default();
+        cpu_game.searcher.search_config.log_uci_diagnostics = false;
+        Self {
+            name: name.to_string(),
+            cpu_game,
+        }
+    }
+}
+
+impl MatchPlayer for SearcherPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        self.cpu_game.searcher.tt.clear();
+        Ok(())
+    }
+
+    fn best_move(&mut self, start_fen: &str, moves: &[String], search_mode: SearchMode) -> Result<String, String> {
+        self.cpu_game.import_position(start_fen, moves.to_vec())?;
+        if let Some(mov) = self.cpu_game.book_move() {
+            return Ok(_move::to_uci(mov, &self.cpu_game.position.board));
+        }
+        self.cpu_game.searcher.search_config.search_mode = search_mode;
+        self.cpu_game.searcher.start_search(&self.cpu_game.move_gen, &self.cpu_game.zobrist, None);
+        self.cpu_game
+            .searcher
+            .collect_best_move()
+            .map(|mov| _move::to_uci(mov, &self.cpu_game.position.board))
+            .ok_or("Search found no move".to_string())
+    }
+}
+
+/// Engine binary launched as a local child process and spoken to over UCI
+pub struct UciPlayer {
+    name: String,
+    client: UciClient,
+}
+
+impl UciPlayer {
+    /// Sends every setoption in **options** after the handshake, UCI_Chess960 first if the openings need **chess960**. <br>
+    /// The engine's id name is used unless **name** is given.
+    pub fn launch(
+        command: &str,
+        args: &[String],
+        options: &[(String, String)],
+        name: Option<&str>,
+        chess960: bool,
+    ) -> Result<Self, String> {
+        let mut client: UciClient = UciClient::launch(command, args)?;
+        if chess960 {
+            client.set_option("UCI_Chess960", "true")?;
+        }
+        for (option, value) in options.iter() {
+            client.set_option(option, value)?;
+        }
+        client.is_ready()?;
+        Ok(Self {
+            name: name.map_or(client.name.clone(), str::to_string),
+            client,
+        })
+    }
+}
+
+impl MatchPlayer for UciPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        self.client.new_game()
+    }
+
+    fn best_move(&mut self, start_fen: &str, moves: &[String], search_mode: SearchMode) -> Result<String, String> {
+        let position: PositionCommand = PositionCommand::new(Some(start_fen.to_string()), moves.to_vec());
+        let (go, timeout): (GoCommand, Duration) = match search_mode {
+            SearchMode::StaticDepth(d) => (GoCommand::new_depth_tc(false, d), DEPTH_MOVE_TIMEOUT),
+            SearchMode::StaticTime(t) => (GoCommand::new_movetime_tc(false, t), Duration::from_millis(t) + MOVE_TIMEOUT_MARGIN),
+        };
+        let started: Instant = Instant::now();
+        let reply: SearchReply = self.client.search(&position, &go, timeout)?;
+        if started.elapsed() > timeout {
+            return Err(format!("{} ran out of time", self.name));
+        }
+        match reply.best_move.as_str() {
+            "(none)" | "0000" => Err(format!("{} returned no move", self.name)),
+            _ => Ok(reply.best_move),
+        }
+    }
+}
+
+pub struct GameRecord {
+    pub start_fen: String,
+    pub moves: Vec<u32>,
+    pub state: GameState, //still InProgress if the game ended by forfeit
+    pub forfeit: Option<(u32, String)>, //side that lost by an illegal, missing or late move, and why
+}
+
+impl GameRecord {
+    /// 1, 0.5 or 0
+    pub fn white_score(&self) -> f64 {
+        match self.pgn_result() {
+            "1-0" => 1.0,
+            "0-1" => 0.0,
+            _ => 0.5,
+        }
+    }
+
+    pub fn pgn_result(&self) -> &'static str {
+        match &self.forfeit {
+            Some((loser, _)) => if *loser == WHITE { "0-1" } else { "1-0" },
+            None => self.state.pgn_result(),
+        }
+    }
+
+    pub fn termination(&self) -> String {
+        match &self.forfeit {
+            Some((_, reason)) => format!("Forfeit, {}", reason),
+            None => self.state.to_string(),
+        }
+    }
+
+    pub fn to_pgn(&self, tags: &[(&str, &str)], move_gen: &MoveGen, zobrist: &Zobrist) -> String {
+        let termination: String = self.termination();
+        let mut tags: Vec<(&str, &str)> = tags.to_vec();
+        tags.push(("Termination", &termination));
+        PgnGame::from_moves(&tags, &self.start_fen, &self.moves, None, self.pgn_result(), move_gen, zobrist)
+            .expect("Played moves are legal")
+            .to_string()
+    }
+}
+
+/// Plays **start_fen** out between **white** and **black** and adjudicates it with GameState. <br>
+/// A player that errs or answers with an illegal move loses the game. Only fails on an invalid **start_fen**.
+pub fn play_game(
+    white: &mut dyn MatchPlayer,
+    black: &mut dyn MatchPlayer,
+    start_fen: &str,
+    search_mode: SearchMode,
+    move_gen: &MoveGen,
+    zobrist: &Zobrist,
+) -> Result<GameRecord, String> {
+    let mut pos: Position = Position::from(start_fen, move_gen, zobrist).map_err(|err| format!("Invalid FEN: {}", err))?;
+    let mut record: GameRecord = GameRecord {
+        start_fen: start_fen.to_string(),
+        moves: vec![],
+        state: GameState::InProgress,
+        forfeit: None,
+    };
+    let mut players: [&mut dyn MatchPlayer; 2] = [white, black]; //indexed by color
+    for (color, player) in (0..).zip(players.iter_mut()) {
+        if let Err(err) = player.new_game() {
+            record.forfeit = Some((color, err));
+            return Ok(record);
+        }
+    }
+    let mut uci_moves: Vec<String> = vec![];
+    let mut history: Vec<Board> = vec![pos.board.clone()]; //since the last irreversible move
+
+    record.state = GameState::adjudicate(&pos, false);
+    while let GameState::InProgress = record.state {
+        let turn: u32 = pos.board.turn;
+        let player: &mut dyn MatchPlayer = &mut *players[turn as usize];
+        let answer: Result<u32, String> = player.best_move(start_fen, &uci_moves, search_mode).and_then(|mov| {
+            let (from, to, promotion) = _move::from_string(&mov).map_err(|err| format!("Invalid move {}: {}", mov, err))?;
+            pos.legal_moves()
+                .iter()
+                .copied()
+                .find(|m| {
+                    _move::get_init(*m) == from && _move::uci_target(*m, &pos.board) == to && _move::promotion_matches(*m, promotion)
+                })
+                .ok_or(format!("Illegal move {} by {}", mov, player.name()))
+        });
+        let mov: u32 = match answer {
+            Ok(mov) => mov,
+            Err(err) => {
+                record.forfeit = Some((turn, err));
+                break;
+            }
+        };
+
+        uci_moves.push(_move::to_uci(mov, &pos.board));
+        record.moves.push(mov);
+        if _move::is_unrepeatable(mov) {
+            history.clear();
+        }
+        pos.make_move(mov, false, false, false, move_gen, zobrist);
+        let repetitions: usize = history.iter().filter(|board| pos.board.eq(board, move_gen)).count();
+        history.push(pos.board.clone());
+        record.state = GameState::adjudicate(&pos, repetitions >= 2);
+    }
+    Ok(record)
+}
+
+/// Start positions of an EPD file, only the positions are used
+pub fn openings_from_epd(text: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Vec<String>, String> {
+    Ok(epd::parse_epd(text, move_gen, zobrist)?.into_iter().map(|record| record.fen).collect())
+}
+
+/// Positions at the end of every game's main line
+pub fn openings_from_pgn(text: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Vec<String>, String> {
+    pgn::parse_pgn(text, move_gen, zobrist)?
+        .iter()
+        .map(|game| {
+            let mut pos: Position =
+                Position::from(game.start_fen(), move_gen, zobrist).map_err(|err| format!("Invalid FEN: {}", err))?;
+            for pgn_move in game.moves.iter() {
+                pos.make_move(pgn_move.mov, false, false, false, move_gen, zobrist);
+            }
+            Ok(board_to_fen(&pos.board))
+        })
+        .collect()
+}
+
+/// Whether any of **openings** castles the Chess960 way, UCI engines then need UCI_Chess960
+pub fn needs_chess960(openings: &[String], move_gen: &MoveGen, zobrist: &Zobrist) -> bool {
+    openings
+        .iter()
+        .any(|fen| Position::from(fen, move_gen, zobrist).is_ok_and(|pos| pos.board.chess960))
+}
+
+/// Results from the first engine's point of view
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// **score** is 1, 0.5 or 0 for the first engine
+    pub fn add(&mut self, score: f64) {
+        if score > 0.5 {
+            self.wins += 1;
+        } else if score < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Mean points per game, 0.5 before any game
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    /// Per game variance of the points
+    pub fn variance(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        let s: f64 = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2) + self.draws as f64 * (0.5 - s).powi(2) + self.losses as f64 * s.powi(2))
+            / self.games() as f64
+    }
+
+    /// Logistic Elo difference, infinite for a perfect score either way
+    pub fn elo(&self) -> f64 {
+        score_to_elo(self.score())
+    }
+
+    /// Half width of the 95% confidence interval of elo()
+    pub fn elo_error(&self) -> f64 {
+        if self.games() == 0 {
+            return f64::INFINITY;
+        }
+        let margin: f64 = Z_95 * (self.variance() / self.games() as f64).sqrt();
+        if self.score() - margin <= 0.0 || self.score() + margin >= 1.0 {
+            return f64::INFINITY;
+        }
+        (score_to_elo(self.score() + margin) - score_to_elo(self.score() - margin)) / 2.0
+    }
+
+    /// Log-likelihood ratio of H1: elo == **elo1** against H0: elo == **elo0**, normal approximation of the trinomial GSPRT
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance: f64 = self.variance();
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (s0, s1): (f64, f64) = (elo_to_score(elo0), elo_to_score(elo1));
+        self.games() as f64 * (s1 - s0) * (2.0 * self.score() - s0 - s1) / (2.0 * variance)
+    }
+}
+
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn score_to_elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        return f64::NEG_INFINITY;
+    } else if score >= 1.0 {
+        return f64::INFINITY;
+    }
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtDecision {
+    Continue,
+    AcceptH0, //not better than elo0
+    AcceptH1, //at least elo1 better
+}
+
+/// Sequential probability ratio test of H0: elo == elo0 against H1: elo == elo1
+/// with false positive rate **alpha** and false negative rate **beta**
+#[derive(Clone, Copy, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// (lower, upper) log-likelihood ratio bounds
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn decision(&self, score: &MatchScore) -> SprtDecision {
+        let llr: f64 = score.llr(self.elo0, self.elo1);
+        let (lower, upper): (f64, f64) = self.bounds();
+        if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
@@ -1,10 +1,19 @@
 use crate::{
-    game::game_state::GameState,
-    repr::{_move, board::Board, move_gen::MoveGen, position::Position},
+    game::{
+        clock::{Clock, TimeControl},
+        game_state::GameState,
+        pgn::PgnGame,
+    },
+    repr::{
+        _move,
+        board::Board,
+        move_gen::{GenType, MoveGen},
+        position::Position,
+    },
     search::searcher::Searcher,
-    utils::zobrist::Zobrist,
+    utils::{fen_tool::board_to_fen, zobrist::Zobrist},
 };
-use std::fmt::Error;
+use std::{fmt::Error, time::Instant};
 
 ///Game object that is also compatible for UI on-board-games
 pub struct Game {
@@ -14,6 +23,9 @@ pub struct Game {
     pub zobrist: Zobrist,
     pub game_state: GameState,
     pub board_history: Vec<Board>,
+    pub clock: Option<Clock>, //untimed if None, then the searcher keeps its own search mode
+    redo_moves: Vec<u32>, //taken back moves, the next one to redo last
+    line_result: &'static str, //PGN result at the end of the line while there are moves to redo
     repetition_relevant_history_idx: usize, // board_history[this..] are relevant for checking repetition
 }
 
@@ -25,7 +37,30 @@ impl Game {
         self.board_history.clear();
         self.board_history.push(self.position.board.clone());
         self.repetition_relevant_history_idx = 0;
+        self.redo_moves.clear();
         self.game_state = self.current_game_state();
+        if let Some(clock) = &self.clock {
+            self.set_time_control(Some(clock.time_control));
+        }
+    }
+
+    ///Fresh clocks for both sides, the side to move's starts running unless the game is over
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.clock = time_control.map(Clock::new);
+        if let Some(clock) = &mut self.clock {
+            if !self.game_state.is_over() {
+                clock.start(self.position.board.turn, Instant::now());
+            }
+        }
+    }
+
+    ///Ends the game if the side to move ran out of time, for polling while waiting for a move
+    pub fn check_flag(&mut self) -> bool {
+        let flagged: Option<u32> = self.clock.as_mut().and_then(|clock| clock.flag(Instant::now()));
+        if let (Some(flagged), false) = (flagged, self.is_over()) {
+            self.game_state = GameState::flag_fall(&self.position.board, flagged);
+        }
+        self.is_over()
     }
 
     ///For making moves on the board, not called in search
@@ -39,14 +74,28 @@ impl Game {
         match mov {
             Some(m) => {
                 //println!("Successfully moved: {}", _move::to_string(m, true));
+                let now: Instant = Instant::now();
+                if let Some(Err(flagged)) = self.clock.as_mut().map(|clock| clock.punch(now)) {
+                    self.game_state = GameState::flag_fall(&self.position.board, flagged);
+                    println!("Game state: {}", self.game_state.to_string());
+                    return Err(Error::default());
+                }
                 self.position
                     .make_move(m, false, false, false, &self.move_gen, &self.zobrist);
                 self.searcher.sync_new_move(&self.position, Some(m));
                 if _move::is_unrepeatable(m) {
                     self.repetition_relevant_history_idx = self.board_history.len();
                 }
+                if self.redo_moves.last() == Some(&m) {
+                    self.redo_moves.pop();
+                } else {
+                    self.redo_moves.clear();
+                }
                 self.board_history.push(self.position.board.clone());
                 self.game_state = self.current_game_state();
+                if let (Some(clock), true) = (&mut self.clock, self.game_state.is_over()) {
+                    clock.stop(now);
+                }
                 println!("Game state: {}", self.game_state.to_string());
                 return Ok(m);
             }
@@ -54,7 +103,115 @@ impl Game {
         }
     }
 
+    ///Moves played since the last imported position
+    pub fn ply(&self) -> usize {
+        self.board_history.len() - 1
+    }
+
+    ///The moves ply counts, in order
+    pub fn played_moves(&self) -> &[u32] {
+        let played: &[u32] = &self.position.played_moves_stack;
+        &played[played.len() - self.ply()..]
+    }
+
+    ///played_moves followed by the taken back moves redo would replay, the whole line goto_ply can reach
+    pub fn line(&self) -> Vec<u32> {
+        let mut line: Vec<u32> = self.played_moves().to_vec();
+        line.extend(self.redo_moves.iter().rev());
+        line
+    }
+
+    ///line in SAN
+    pub fn line_san(&self) -> Vec<String> {
+        PgnGame::from_moves(
+            &[],
+            &board_to_fen(&self.board_history[0]),
+            &self.line(),
+            None,
+            "*",
+            &self.move_gen,
+            &self.zobrist,
+        )
+        .expect("Played moves are legal")
+        .moves
+        .into_iter()
+        .map(|mov| mov.san)
+        .collect()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.ply() > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_moves.is_empty()
+    }
+
+    ///Takes back the last move, it can be redone until a different move is played
+    pub fn undo(&mut self) -> Option<u32> {
+        if !self.can_undo() {
+            return None;
+        }
+        let mov: u32 = self.position.played_moves_stack.last().copied()?;
+        if self.redo_moves.is_empty() {
+            self.line_result = self.game_state.pgn_result();
+        }
+        self.position.unmake_move(mov, &self.zobrist);
+        //unmaking pops the root's move range, the previous position becomes the new root
+        self.position.move_arr_idx = vec![0, 0];
+        self.position.generate_moves(GenType::All, false, false, &self.move_gen);
+        self.board_history.pop();
+        self.redo_moves.push(mov);
+        self.sync_history();
+        Some(mov)
+    }
+
+    pub fn redo(&mut self) -> Option<u32> {
+        let mov: u32 = self.redo_moves.pop()?;
+        self.position
+            .make_move(mov, false, false, false, &self.move_gen, &self.zobrist);
+        self.board_history.push(self.position.board.clone());
+        self.sync_history();
+        Some(mov)
+    }
+
+    ///Undoes or redoes moves until **ply** moves since the import are on the board, as far as possible <br>
+    ///Returns the ply reached
+    pub fn goto_ply(&mut self, ply: usize) -> usize {
+        while self.ply() > ply && self.undo().is_some() {}
+        while self.ply() < ply && self.redo().is_some() {}
+        self.ply()
+    }
+
+    ///After undo or redo: repetition window, searcher hash history and game state follow the board again. <br>
+    ///The clock runs for the side to move, a fallen flag stays down.
+    fn sync_history(&mut self) {
+        self.repetition_relevant_history_idx = self
+            .played_moves()
+            .iter()
+            .rposition(|mov| _move::is_unrepeatable(*mov))
+            .map_or(0, |i| i + 1);
+        let board_hash_history: Vec<u64> = self.board_history[self.repetition_relevant_history_idx..]
+            .iter()
+            .map(|board| board.zhash)
+            .collect();
+        self.searcher.import_position(&self.position, Some(board_hash_history));
+        self.game_state = self.current_game_state();
+        if let Some(clock) = &mut self.clock {
+            let now: Instant = Instant::now();
+            match clock.flag(now) {
+                Some(flagged) => self.game_state = GameState::flag_fall(&self.position.board, flagged),
+                None if self.game_state.is_over() => clock.stop(now),
+                None => clock.start(self.position.board.turn, now),
+            }
+        }
+    }
+
+    ///Searches with a budget from the live clock if the game is timed
     pub fn play_cpu_move(&mut self) -> Result<u32, Error> {
+        if let Some(clock) = &self.clock {
+            self.searcher.search_config.search_mode = clock.search_mode(self.position.board.turn, Instant::now());
+        }
         self.searcher.start_search(&self.move_gen, &self.zobrist, None);
         let mov: Option<u32> = self.searcher.collect_best_move();
         match mov {
@@ -74,24 +231,28 @@ impl Game {
     }
 
     fn current_game_state(&self) -> GameState {
-        if self.position.in_checkmate() {
-            return GameState::Checkmate(self.position.board.turn);
-        } else if self.position.in_stalemate() {
-            return GameState::Stalemate;
-        } else if self.cur_pos_is_threefold() {
-            return GameState::DrawByRepetition;
-        } else if self.position.board.is_fifty_move_draw() {
-            return GameState::DrawByFiftyMoveRule;
-        } else {
-            return GameState::InProgress;
-        }
+        GameState::adjudicate(&self.position, self.cur_pos_is_threefold())
     }
 
     pub fn is_over(&self) -> bool {
-        return match self.game_state {
-            GameState::InProgress => false,
-            _ => true,
-        };
+        return self.game_state.is_over();
+    }
+
+    ///The whole line, taken back moves included, with the result at its end
+    ///**evals** are optional per move eval comments, centipawns from white's point of view
+    pub fn to_pgn(&self, tags: &[(&str, &str)], evals: Option<&[i16]>) -> String {
+        let result: &str = if self.can_redo() { self.line_result } else { self.game_state.pgn_result() };
+        PgnGame::from_moves(
+            tags,
+            &board_to_fen(&self.board_history[0]),
+            &self.line(),
+            evals,
+            result,
+            &self.move_gen,
+            &self.zobrist,
+        )
+        .expect("Played moves are legal")
+        .to_string()
     }
 
     fn cur_pos_is_threefold(&self) -> bool {
@@ -103,22 +264,6 @@ impl Game {
         }
         return count >= 3;
     }
-
-    /* ///Public api ease of use and safety method DEPRECATED
-    pub fn try_unmake_move(&mut self) -> Result<u32, Error> {
-        let mov: Option<u32> = self.played_moves_stack.last().copied();
-        match mov {
-            Some(m) => {
-                println!("Successfully unmade: {}", _move::to_string(m, true));
-                self.unmake_move(m);
-                return Ok(m);
-            },
-            None => {
-                println!("Tried to unmake move with no moves played");
-                return Err(Error::default());
-            }
-        }
-    } */
 }
 
 impl Default for Game {
@@ -136,6 +281,9 @@ impl Default for Game {
             zobrist,
             game_state: GameState::InProgress,
             board_history,
+            clock: None,
+            redo_moves: vec![],
+            line_result: "*",
             repetition_relevant_history_idx: 0,
         };
     }
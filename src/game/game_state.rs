@@ -1,3 +1,5 @@
+use crate::repr::{board::Board, position::Position, types::{WHITE, W_KING_U, NOF_PIECE_TYPES_U}};
+
 pub enum GameState {
     InProgress,
     Checkmate(u32), //WHITE == 0 or BLACK == 1
@@ -6,9 +8,47 @@ pub enum GameState {
     DrawByFiftyMoveRule,
     DrawByInsufficientMaterial,
     DrawByTimeout,
+    Timeout(u32), //side that ran out of time, WHITE == 0 or BLACK == 1
 }
 
 impl GameState {
+    ///State of **position**, **threefold** tells whether it occurred for the third time since the last irreversible move
+    pub fn adjudicate(position: &Position, threefold: bool) -> Self {
+        if position.in_checkmate() {
+            GameState::Checkmate(position.board.turn)
+        } else if position.in_stalemate() {
+            GameState::Stalemate
+        } else if threefold {
+            GameState::DrawByRepetition
+        } else if position.board.is_fifty_move_draw() {
+            GameState::DrawByFiftyMoveRule
+        } else if position.board.is_insufficient_material() {
+            GameState::DrawByInsufficientMaterial
+        } else {
+            GameState::InProgress
+        }
+    }
+
+    ///Result of **flagged** running out of time: a loss, unless the opponent could never mate. <br>
+    ///That is a lone king, or material that is insufficient against the flagged side's too.
+    pub fn flag_fall(board: &Board, flagged: u32) -> Self {
+        let opponent: usize = (flagged ^ 1) as usize * NOF_PIECE_TYPES_U;
+        let opponent_material: u64 = board.pieces[opponent..opponent + NOF_PIECE_TYPES_U]
+            .iter()
+            .enumerate()
+            .filter(|(kind, _)| *kind != W_KING_U)
+            .fold(0, |acc, (_, pieces)| acc | pieces);
+        if opponent_material == 0 || board.is_insufficient_material() {
+            GameState::DrawByTimeout
+        } else {
+            GameState::Timeout(flagged)
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        !matches!(self, GameState::InProgress)
+    }
+
     pub fn is_draw(&self) -> bool {
         match self {
             GameState::DrawByRepetition => true,
@@ -20,6 +60,15 @@ impl GameState {
         }
     }
 
+    ///PGN result token, "*" while in progress
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            GameState::InProgress => "*",
+            GameState::Checkmate(turn) | GameState::Timeout(turn) => if *turn == WHITE { "0-1" } else { "1-0" },
+            _ => "1/2-1/2",
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
             GameState::InProgress => "In Progress".to_string(),
@@ -32,6 +81,11 @@ impl GameState {
             GameState::DrawByFiftyMoveRule => "Draw by Fifty Move Rule".to_string(),
             GameState::DrawByInsufficientMaterial => "Draw by Insufficient Material".to_string(),
             GameState::DrawByTimeout => "Draw by Timeout".to_string(),
+            GameState::Timeout(turn) => format!(
+                "{} ran out of time, {} wins",
+                if *turn == WHITE { "White" } else { "Black" },
+                if *turn == WHITE { "Black" } else { "White" }
+            ),
         }
     }
 }
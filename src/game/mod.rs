@@ -1,3 +1,6 @@
+pub mod clock;
 pub mod cpu_game;
+pub mod engine_match;
 pub mod game;
 pub mod game_state;
+pub mod pgn;
@@ -0,0 +1,413 @@
+/* Portable Game Notation import and export */
+
+use std::fmt;
+
+use crate::{
+    repr::{_move, move_gen::MoveGen, position::Position},
+    search::eval::{MATE_BOUND, MATE_EVAL},
+    utils::{fen_tool::DEFAULT_FEN, zobrist::Zobrist},
+};
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const UNKNOWN_TAG_VALUES: [&str; 7] = ["?", "?", "????.??.??", "?", "?", "?", "*"];
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+//move suffix annotations, their index + 1 is the NAG they stand for
+const SUFFIX_NAGS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+const MAX_LINE_LEN: usize = 80;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnMove {
+    pub mov: u32,
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub leading_comments: Vec<String>, //only on the first move of a line
+    pub comments: Vec<String>,
+    pub variations: Vec<Vec<PgnMove>>, //alternatives to this move
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    /// Builds a main line game from moves played from **start_fen**. <br>
+    /// Missing Seven Tag Roster tags are filled with unknown values, SetUp and FEN are added for non standard starts.
+    /// **evals** are centipawns from white's point of view after each move, written as comments.
+    pub fn from_moves(
+        tags: &[(&str, &str)],
+        start_fen: &str,
+        moves: &[u32],
+        evals: Option<&[i16]>,
+        result: &str,
+        move_gen: &MoveGen,
+        zobrist: &Zobrist,
+    ) -> Result<Self, String> {
+        let mut pos: Position = Position::from(start_fen, move_gen, zobrist).map_err(|err| format!("Invalid FEN: {}", err))?;
+        let mut pgn_moves: Vec<PgnMove> = Vec::with_capacity(moves.len());
+        for (i, mov) in moves.iter().copied().enumerate() {
+            if !pos.legal_moves().contains(&mov) {
+                return Err(format!("Illegal move: {}", _move::to_string(mov, true)));
+            }
+            let comments: Vec<String> = match evals.and_then(|evals| evals.get(i)) {
+                Some(eval) => vec![format_eval(*eval)],
+                None => vec![],
+            };
+            pgn_moves.push(PgnMove {
+                mov,
+                san: pos.move_to_san(mov, move_gen, zobrist),
+                comments,
+                ..Default::default()
+            });
+            pos.make_move(mov, false, false, false, move_gen, zobrist);
+        }
+
+        let mut game_tags: Vec<(String, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .zip(UNKNOWN_TAG_VALUES)
+            .map(|(name, unknown)| {
+                let value: &str = if *name == "Result" {
+                    result
+                } else {
+                    tags.iter().find(|(tag, _)| tag == name).map_or(unknown, |(_, value)| *value)
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        for (name, value) in tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(name) && *name != "SetUp" && *name != "FEN" {
+                game_tags.push((name.to_string(), value.to_string()));
+            }
+        }
+        if start_fen != DEFAULT_FEN {
+            game_tags.push(("SetUp".to_string(), "1".to_string()));
+            game_tags.push(("FEN".to_string(), start_fen.to_string()));
+        }
+
+        Ok(Self {
+            tags: game_tags,
+            moves: pgn_moves,
+            result: result.to_string(),
+        })
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn start_fen(&self) -> &str {
+        self.tag("FEN").unwrap_or(DEFAULT_FEN)
+    }
+
+    /// Main line in UCI notation, e.g. for CpuGame::import_position
+    pub fn uci_moves(&self) -> Vec<String> {
+        self.moves.iter().map(|m| _move::to_string(m.mov, true)).collect()
+    }
+
+    fn start_ply(&self) -> usize {
+        let fields: Vec<&str> = self.start_fen().split_whitespace().collect();
+        let fullmove: usize = fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1).max(1);
+        let black_to_move: bool = fields.get(1) == Some(&"b");
+        (fullmove - 1) * 2 + black_to_move as usize
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.tags.iter() {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        let mut words: Vec<String> = vec![];
+        movetext_words(&self.moves, self.start_ply(), &mut words);
+        words.push(self.result.clone());
+        //glue parentheses to the words they enclose
+        let mut i: usize = 0;
+        while i + 1 < words.len() {
+            if words[i] == "(" {
+                words[i] = format!("({}", words.remove(i + 1));
+            } else if words[i + 1] == ")" {
+                words.remove(i + 1);
+                words[i].push(')');
+                continue;
+            }
+            i += 1;
+        }
+        let mut line_len: usize = 0;
+        for word in words.iter() {
+            if line_len > 0 && line_len + 1 + word.len() > MAX_LINE_LEN {
+                writeln!(f)?;
+                line_len = 0;
+            } else if line_len > 0 {
+                write!(f, " ")?;
+                line_len += 1;
+            }
+            write!(f, "{}", word)?;
+            line_len += word.len();
+        }
+        writeln!(f)
+    }
+}
+
+fn movetext_words(line: &[PgnMove], start_ply: usize, words: &mut Vec<String>) {
+    let mut needs_number: bool = true; //black moves get a "N..." number after interruptions
+    for (i, m) in line.iter().enumerate() {
+        let ply: usize = start_ply + i;
+        for comment in m.leading_comments.iter() {
+            words.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+        if ply.is_multiple_of(2) {
+            words.push(format!("{}.", ply / 2 + 1));
+        } else if needs_number {
+            words.push(format!("{}...", ply / 2 + 1));
+        }
+        words.push(m.san.clone());
+        needs_number = false;
+        for nag in m.nags.iter() {
+            words.push(format!("${}", nag));
+        }
+        for comment in m.comments.iter() {
+            words.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+        for variation in m.variations.iter() {
+            words.push("(".to_string());
+            movetext_words(variation, ply, words);
+            words.push(")".to_string());
+            needs_number = true;
+        }
+    }
+}
+
+/// Pawns with two decimals, or the number of moves to mate like +M3
+pub fn format_eval(eval: i16) -> String {
+    let sign: char = if eval < 0 { '-' } else { '+' };
+    let abs: i32 = (eval as i32).abs();
+    if abs >= MATE_BOUND as i32 {
+        format!("{}M{}", sign, (MATE_EVAL as i32 - abs + 1) / 2)
+    } else {
+        format!("{}{}.{:02}", sign, abs / 100, abs % 100)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    San(String),
+}
+
+/// Parses every game of a PGN file. <br>
+/// Moves are replayed through Position::make_move, so each PgnMove carries the resolved move and canonical SAN.
+/// Variations, comments and NAGs are kept, escape lines and move numbers are skipped.
+pub fn parse_pgn(text: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Vec<PgnGame>, String> {
+    let tokens: Vec<Token> = tokenize(text)?;
+    let mut games: Vec<PgnGame> = vec![];
+    let mut idx: usize = 0;
+
+    while idx < tokens.len() {
+        let mut game: PgnGame = PgnGame::default();
+        while let Some(Token::Tag(name, value)) = tokens.get(idx) {
+            game.tags.push((name.clone(), value.clone()));
+            idx += 1;
+        }
+        let game_nr: usize = games.len() + 1;
+        let mut pos: Position = Position::from(game.start_fen(), move_gen, zobrist)
+            .map_err(|err| format!("Game {}: invalid FEN: {}", game_nr, err))?;
+        game.moves = parse_line(&tokens, &mut idx, &mut pos, 0, move_gen, zobrist)
+            .map_err(|err| format!("Game {}: {}", game_nr, err))?;
+        game.result = match tokens.get(idx) {
+            Some(Token::Result(result)) => {
+                idx += 1;
+                result.clone()
+            }
+            _ => game.tag("Result").unwrap_or("*").to_string(),
+        };
+        games.push(game);
+    }
+    Ok(games)
+}
+
+fn parse_line(
+    tokens: &[Token],
+    idx: &mut usize,
+    pos: &mut Position,
+    depth: usize,
+    move_gen: &MoveGen,
+    zobrist: &Zobrist,
+) -> Result<Vec<PgnMove>, String> {
+    let mut line: Vec<PgnMove> = vec![];
+    let mut leading_comments: Vec<String> = vec![];
+    let mut before_last: Option<Position> = None; //where variations of the last move start
+    let mut closed: bool = false;
+
+    while let Some(token) = tokens.get(*idx) {
+        match token {
+            Token::Tag(_, _) | Token::Result(_) if depth == 0 => break,
+            Token::Tag(_, _) => return Err("Unterminated variation".to_string()),
+            Token::Result(_) => {}
+            Token::Close if depth == 0 => return Err("Unexpected ')'".to_string()),
+            Token::Close => {
+                *idx += 1;
+                closed = true;
+                break;
+            }
+            Token::Open => {
+                let mut var_pos: Position = before_last.clone().ok_or("Variation without a preceding move")?;
+                *idx += 1;
+                let variation: Vec<PgnMove> = parse_line(tokens, idx, &mut var_pos, depth + 1, move_gen, zobrist)?;
+                line.last_mut().expect("before_last implies a move").variations.push(variation);
+                continue;
+            }
+            Token::Comment(comment) => match line.last_mut() {
+                Some(last) => last.comments.push(comment.clone()),
+                None => leading_comments.push(comment.clone()),
+            },
+            Token::Nag(nag) => match line.last_mut() {
+                Some(last) => last.nags.push(*nag),
+                None => return Err(format!("NAG ${} without a move", nag)),
+            },
+            Token::San(san) => {
+                let mov: u32 = pos.parse_san(san).map_err(|err| format!("{}: {}", err, san))?;
+                let canonical: String = pos.move_to_san(mov, move_gen, zobrist);
+                before_last = Some(pos.clone());
+                pos.make_move(mov, false, false, false, move_gen, zobrist);
+                line.push(PgnMove {
+                    mov,
+                    san: canonical,
+                    leading_comments: std::mem::take(&mut leading_comments),
+                    ..Default::default()
+                });
+            }
+        }
+        *idx += 1;
+    }
+    if depth > 0 && !closed {
+        return Err("Unterminated variation".to_string());
+    }
+    Ok(line)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = vec![];
+    let chars: Vec<char> = text.chars().collect();
+    let mut i: usize = 0;
+    let mut line_start: bool = true;
+
+    while i < chars.len() {
+        let c: char = chars[i];
+        if line_start && c == '%' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        line_start = c == '\n';
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '[' => {
+                let end: usize = find_tag_end(&chars, i)?;
+                tokens.push(parse_tag(&chars[i + 1..end])?);
+                i = end + 1;
+            }
+            '{' => {
+                let end: usize = chars[i..].iter().position(|c| *c == '}').ok_or("Unterminated comment")? + i;
+                let comment: String = chars[i + 1..end].iter().collect();
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<&str>>().join(" ")));
+                i = end + 1;
+            }
+            ';' => {
+                let end: usize = chars[i..].iter().position(|c| *c == '\n').map_or(chars.len(), |p| p + i);
+                tokens.push(Token::Comment(chars[i + 1..end].iter().collect::<String>().trim().to_string()));
+                i = end;
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            _ => {
+                let start: usize = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"[]{};()".contains(chars[i]) {
+                    i += 1;
+                }
+                push_word(&chars[start..i].iter().collect::<String>(), &mut tokens)?;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn find_tag_end(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut in_string: bool = false;
+    let mut i: usize = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if in_string => i += 1,
+            '"' => in_string = !in_string,
+            ']' if !in_string => return Ok(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    Err("Unterminated tag".to_string())
+}
+
+fn parse_tag(inner: &[char]) -> Result<Token, String> {
+    let inner: String = inner.iter().collect();
+    let (name, rest) = inner.trim().split_once(char::is_whitespace).ok_or(format!("Invalid tag: [{}]", inner))?;
+    let rest: &str = rest.trim();
+    if rest.len() < 2 || !rest.starts_with('"') || !rest.ends_with('"') {
+        return Err(format!("Invalid tag value: [{}]", inner));
+    }
+    let mut value: String = String::new();
+    let mut escaped: bool = false;
+    for c in rest[1..rest.len() - 1].chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else {
+            value.push(c);
+            escaped = false;
+        }
+    }
+    Ok(Token::Tag(name.to_string(), value))
+}
+
+fn push_word(word: &str, tokens: &mut Vec<Token>) -> Result<(), String> {
+    if RESULTS.contains(&word) {
+        tokens.push(Token::Result(word.to_string()));
+        return Ok(());
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        tokens.push(Token::Nag(nag.parse().map_err(|_| format!("Invalid NAG: {}", word))?));
+        return Ok(());
+    }
+    //move numbers like "12." and "12...", possibly glued to the move
+    let word: &str = match word.find(|c: char| !c.is_ascii_digit()) {
+        Some(p) if p > 0 && word[p..].starts_with('.') => word[p..].trim_start_matches('.'),
+        _ => word,
+    };
+    if word.is_empty() {
+        return Ok(());
+    }
+    let san: &str = word.trim_end_matches(['!', '?']);
+    if san.is_empty() {
+        return Err(format!("Annotation without a move: {}", word));
+    }
+    tokens.push(Token::San(san.to_string()));
+    if let Some(p) = SUFFIX_NAGS.iter().position(|suffix| *suffix == &word[san.len()..]) {
+        tokens.push(Token::Nag(p as u8 + 1));
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::{repr::{board::square_to_string, types::*}};
+use crate::{repr::{board::{square_to_string, Board}, types::*}};
 //moves are represented with 32 bit integers
 //0-5: source square
 //6-11: target square
@@ -7,7 +7,7 @@ use crate::{repr::{board::square_to_string, types::*}};
 //14-17: eaten piece
 //18: see is negative?
 //19: see is positive?
-//20: free
+//20: castling? king moves from its square to the g or c file, the rook's squares come from the board
 //21: is promotion?
 //22-25: promotion piece
 //26-29: moved piece
@@ -16,11 +16,12 @@ use crate::{repr::{board::square_to_string, types::*}};
 //this file contains utility methods for using them
 
 pub const NULL_MOVE: u32 = u32::MAX;
-//castling moves:
-pub const WHITE_SHORT: u32 = 2483028356; //1 0 0101 0000 0 00 0 0000 0 0 000110 000100
-pub const WHITE_LONG: u32 = 2483028100;  //1 0 0101 0000 0 00 0 0000 0 0 000010 000100
-pub const BLACK_SHORT: u32 = 738201532;  //0 0 1011 0000 0 00 0 0000 0 0 111110 111100
-pub const BLACK_LONG: u32 = 738201276;   //0 0 1011 0000 0 00 0 0000 0 0 111010 111100
+const CASTLE_FLAG: u32 = 1048576; //2^20
+//castling moves from the standard start squares:
+pub const WHITE_SHORT: u32 = 2484076932; //1 0 0101 0000 0 10 0 0000 0 0 000110 000100
+pub const WHITE_LONG: u32 = 2484076676;  //1 0 0101 0000 0 10 0 0000 0 0 000010 000100
+pub const BLACK_SHORT: u32 = 739250108;  //0 0 1011 0000 0 10 0 0000 0 0 111110 111100
+pub const BLACK_LONG: u32 = 739249852;   //0 0 1011 0000 0 10 0 0000 0 0 111010 111100
 
 ///Encode move to u32 <br>
 ///taken piece idx is found and added after checking pseudolegal is legal to save compute
@@ -55,6 +56,14 @@ pub fn create_castling(mover: u32, is_short: bool) -> u32 {
     }
 }
 
+///Castling with the king starting on **king_from**, which is anywhere on the back rank in Chess960
+#[inline]
+pub fn create_castling_from(king_from: u32, mover: u32, is_short: bool) -> u32 {
+    let king_to: u32 = (king_from / 8) * 8 + if is_short { 6 } else { 2 };
+    let king: u32 = if mover == WHITE { W_KING } else { B_KING };
+    create(king_from, king_to, false, mover, king) | CASTLE_FLAG
+}
+
 ///Pawn double push move
 #[inline]
 pub fn create_double_push(from: u32, to: u32, mover: u32, moved_piece: u32) -> u32 {
@@ -178,17 +187,17 @@ pub fn is_white_move(mov: u32) -> bool {
 
 #[inline]
 pub fn is_short_castle(mov: u32) -> bool {
-    return mov == WHITE_SHORT || mov == BLACK_SHORT;
+    return is_castle(mov) && get_target(mov) % 8 == 6;
 }
 
 #[inline]
 pub fn is_long_castle(mov: u32) -> bool {
-    return mov == WHITE_LONG || mov == BLACK_LONG;
+    return is_castle(mov) && get_target(mov) % 8 == 2;
 }
 
 #[inline]
 pub fn is_castle(mov: u32) -> bool {
-    return is_short_castle(mov) || is_long_castle(mov);
+    return mov != NULL_MOVE && (mov & CASTLE_FLAG) > 0;
 }
 
 #[inline]
@@ -201,6 +210,12 @@ pub fn is_promotion(mov: u32) -> bool {
     return (mov & 2097152) > 0;
 }
 
+///Captures and promotions, what GenType::Noisy generates
+#[inline]
+pub fn is_noisy(mov: u32) -> bool {
+    is_eating(mov) || is_promotion(mov)
+}
+
 #[inline]
 pub fn is_en_passant(mov: u32) -> bool {
     return (mov & 1073741824) > 0; //2^30
@@ -328,6 +343,25 @@ fn parse_promotion_piece(piece: u8, target_rank: u8) -> Result<u32, &'static str
     };
 }
 
+///Square a UCI move string names as target. <br>
+///Chess960 castling is written as king takes own rook, e.g. e1h1, so it can't be confused with a king step.
+pub fn uci_target(mov: u32, board: &Board) -> u32 {
+    if board.chess960 && is_castle(mov) {
+        return board.castling_rook_squares(is_white_move(mov), is_short_castle(mov)).0;
+    }
+    get_target(mov)
+}
+
+///UCI notation that respects the board's Chess960 castling convention
+pub fn to_uci(mov: u32, board: &Board) -> String {
+    if mov == NULL_MOVE || !board.chess960 || !is_castle(mov) {
+        return to_string(mov, true);
+    }
+    let mut res = square_to_string(get_init(mov));
+    res.push_str(&square_to_string(uci_target(mov, board)));
+    res
+}
+
 pub fn to_string(mov: u32, uci: bool) -> String {
     if mov == NULL_MOVE {
         if uci {
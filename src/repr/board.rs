@@ -2,7 +2,11 @@ use crate::{
     repr::{
         _move::NULL_MOVE,
         move_gen::{add_en_passant, MoveGen},
-        types::{opposite_turn, B_KING, B_KING_U, B_PAWN_U, WHITE, W_KING, W_KING_U, W_PAWN_U},
+        types::{
+            opposite_turn, B_BISHOP_U, B_KING, B_KING_U, B_KNIGHT_U, B_PAWN_U, B_QUEEN_U,
+            B_ROOK_U, WHITE, W_BISHOP_U, W_KING, W_KING_U, W_KNIGHT_U, W_PAWN_U, W_QUEEN_U,
+            W_ROOK_U,
+        },
     },
     search::eval::{MAX_LATE_GAME_PHASE, PHASE_MULTIPLIERS},
     utils::zobrist::Zobrist,
@@ -29,6 +33,10 @@ pub const RANKS: [u64; 8] = [
     0xFF00000000000000,
 ];
 pub const EDGES: u64 = FILES[0] | FILES[7] | RANKS[0] | RANKS[7];
+pub const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
+//castling rook start squares in ws, wl, bs, bl order
+pub const STANDARD_CASTLING_ROOKS: [u32; 4] = [7, 0, 63, 56];
 /// Mutable state board representing a legal chess position
 #[derive(Clone, Debug)]
 pub struct Board {
@@ -56,6 +64,8 @@ pub struct Board {
     wl: u32, //semaphore-like usage or "castling distance"
     bs: u32, //if 0, then has right else, num tells how many moves ago you had the right
     bl: u32,
+    pub castling_rooks: [u32; 4], //start squares of the castling rooks in ws, wl, bs, bl order
+    pub chess960: bool, //castling is notated king takes rook
 }
 
 impl Board {
@@ -152,23 +162,23 @@ impl Board {
         if is_white_turn {
             short_dist = &mut self.ws;
             long_dist = &mut self.wl;
-            short_corner_idx = 7;
-            long_corner_idx = 0;
             king_piece_idx = W_KING;
             non_mover_s_dist = &mut self.bs;
             non_mover_l_dist = &mut self.bl;
-            non_mover_s_corner_idx = 63;
-            non_mover_l_corner_idx = 56;
         } else {
             short_dist = &mut self.bs;
             long_dist = &mut self.bl;
-            short_corner_idx = 63;
-            long_corner_idx = 56;
             king_piece_idx = B_KING;
             non_mover_s_dist = &mut self.ws;
             non_mover_l_dist = &mut self.wl;
-            non_mover_s_corner_idx = 7;
-            non_mover_l_corner_idx = 0;
+        }
+        let [ws_rook, wl_rook, bs_rook, bl_rook] = self.castling_rooks;
+        if is_white_turn {
+            (short_corner_idx, long_corner_idx) = (ws_rook, wl_rook);
+            (non_mover_s_corner_idx, non_mover_l_corner_idx) = (bs_rook, bl_rook);
+        } else {
+            (short_corner_idx, long_corner_idx) = (bs_rook, bl_rook);
+            (non_mover_s_corner_idx, non_mover_l_corner_idx) = (ws_rook, wl_rook);
         }
         let short_right: bool = *short_dist == 0;
         let long_right: bool = *long_dist == 0;
@@ -323,6 +333,8 @@ impl Board {
             major_minor_count,
             zhash: 0,
             half_move_clock,
+            castling_rooks: STANDARD_CASTLING_ROOKS,
+            chess960: false,
         }; //set computable to some defaults and compute now to get correct vals
         let non_mover_attacks: u64 = move_gen.compute_attacked(&mut res, opposite_turn(turn));
         if turn == WHITE {
@@ -353,6 +365,13 @@ impl Board {
     pub fn bl(&self) -> bool {
         return self.bl == 0;
     }
+    ///(rook_from, rook_to) of castling, the rook always ends next to the king on the f or d file
+    pub fn castling_rook_squares(&self, white: bool, is_short: bool) -> (u32, u32) {
+        let idx: usize = 2 * (!white as usize) + (!is_short as usize);
+        let back_rank: u32 = if white { 0 } else { 56 };
+        (self.castling_rooks[idx], back_rank + if is_short { 5 } else { 3 })
+    }
+
     ///Is there a legal en passant capture in this pos?
     fn no_legal_en_passant(&self, move_gen: &MoveGen) -> bool {
         if self.ep_square.is_some() {
@@ -381,6 +400,25 @@ impl Board {
         return self.half_move_clock >= 100;
     }
 
+    ///Neither side can ever deliver mate: KvK, KNvK, KBvK or only bishops all on same colored squares
+    pub fn is_insufficient_material(&self) -> bool {
+        let pawns_majors: u64 = self.pieces[W_PAWN_U]
+            | self.pieces[W_ROOK_U]
+            | self.pieces[W_QUEEN_U]
+            | self.pieces[B_PAWN_U]
+            | self.pieces[B_ROOK_U]
+            | self.pieces[B_QUEEN_U];
+        if pawns_majors != 0 {
+            return false;
+        }
+        let knights: u64 = self.pieces[W_KNIGHT_U] | self.pieces[B_KNIGHT_U];
+        let bishops: u64 = self.pieces[W_BISHOP_U] | self.pieces[B_BISHOP_U];
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+        knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & DARK_SQUARES == 0)
+    }
+
     pub fn eq(&self, other: &Self, move_gen: &MoveGen) -> bool {
         return self.pieces == other.pieces
             && self.turn == other.turn
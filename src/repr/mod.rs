@@ -3,5 +3,7 @@ pub mod bitboard;
 pub mod board;
 pub mod magic_bb_loader;
 pub mod move_gen;
+pub mod perft;
 pub mod position;
+pub mod san;
 pub mod types;
@@ -21,12 +21,16 @@ pub const DIAG_STEPS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
 pub const CARDINAL_STEPS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
 pub const AVG_BRANCH_FAC: usize = 35;
 
-const WS_CASTLING_GAP_BB: u64 = 96; //2^5 + 2^6
-const WL_CASTLING_GAP_BB: u64 = 14; //2^1 + 2^2 + 2^3
-const BS_CASTLING_GAP_BB: u64 = 6917529027641081856; //2^61 + 2^62
-const BL_CASTLING_GAP_BB: u64 = 1008806316530991104; //2^57 + 2^58 + 2^59
-const WL_ATTACK_GAP_BB: u64 = 12; //2^2 + 2^3
-const BL_ATTACK_GAP_BB: u64 = 864691128455135232; //2^58 + 2^59
+///Which moves a generation call produces, "noisy" means captures (including en passant) and promotions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenType {
+    All,
+    Noisy,
+    Quiet, //everything not noisy, including castling
+    Evasions, //only when in check: king moves, plus captures and blocks on check_block_sqrs. Same moves as All there
+    QuietChecks, //quiet moves giving a direct or discovered check
+}
+
 ///Uses **magic_bb** handle for precomputed slide moves.
 pub struct MoveGen {
     pub attack_bbs: [[u64; 64]; 12], //empty board attack bbs, for pawns doesn't include forward moves, since they aren't attacked by pawns. Doesn't include en passant or castling either.
@@ -71,7 +75,7 @@ impl MoveGen {
     ///Called once upon arriving to a new position. <br>
     ///Not called when reverting move, since just fetched from stack.
     ///Returns how many moves generated
-    ///"noisy" means captures + promotions
+    ///Only the moves of **gen_type** are generated, see GenType
     pub fn generate_legal(
         &self,
         board: &Board,
@@ -81,7 +85,7 @@ impl MoveGen {
         move_arr_s_idx: usize,
         in_search: bool,
         in_perft_debug: bool,
-        noisy_only: bool,
+        gen_type: GenType,
     ) -> usize {
         let mut generated: usize = 0;
         let pseudolegals_generated: usize = self.generate_pseudolegal(
@@ -90,36 +94,109 @@ impl MoveGen {
             mover,
             in_search,
             in_perft_debug,
-            noisy_only,
+            gen_type,
         );
 
         for mov in pseudo_move_arr[0..pseudolegals_generated].iter() {
             if self.pseudolegal_is_legal(*mov, board) {
-                //add taken piece idx to move (if eating) now, since it is necessary
-                let m: u32;
-                if _move::is_en_passant(*mov) {
-                    if mover == WHITE {
-                        m = _move::with_eaten_piece(*mov, 6);
-                    } else {
-                        m = *mov; //white pawn is 0 so do nothing
-                    }
-                } else if _move::is_eating(*mov) {
-                    let eaten_piece: u32 =
-                        board.get_piece_type_at(_move::get_target(*mov), mover ^ 1);
-                    m = _move::with_eaten_piece(*mov, eaten_piece);
-                } else {
-                    if noisy_only && !_move::is_promotion(*mov) {
-                        panic!("Quiet in noisy_only mode");
-                    }
-                    m = *mov;
+                if gen_type == GenType::Noisy && !_move::is_eating(*mov) && !_move::is_promotion(*mov) {
+                    panic!("Quiet in noisy_only mode");
                 }
-                move_arr[move_arr_s_idx + generated] = m;
+                move_arr[move_arr_s_idx + generated] = with_eaten_piece_for(*mov, board, mover);
                 generated += 1;
             }
         }
         return generated;
     }
 
+    ///Is **mov** (ignoring its SEE bits) a pseudolegal move in **board**, encoded as generate_legal would encode it? <br>
+    ///Checks that the moved piece is on its square, the path is clear, castling rights, the en passant square and the
+    ///promotion rank without generating anything, so moves from elsewhere (TT, killers) are cheap to validate. <br>
+    ///Castling is fully checked here, for other moves follow with pseudolegal_is_legal.
+    pub fn is_pseudolegal(&self, board: &Board, mov: u32) -> bool {
+        let mover: u32 = board.turn;
+        if mov == NULL_MOVE || _move::is_white_move(mov) != (mover == WHITE) {
+            return false;
+        }
+        let mov: u32 = _move::with_see_cleared(mov);
+        let from: u32 = _move::get_init(mov);
+        let to: u32 = _move::get_target(mov);
+        let piece: u32 = _move::get_moved_piece(mov);
+        if piece > B_KING || piece / 6 != mover || !bitboard::contains_square(board.pieces[piece as usize], from) {
+            return false;
+        }
+        if _move::is_castle(mov) {
+            let mut castles: [u32; 2] = [NULL_MOVE; 2];
+            let added: usize = add_castling(self, board, mover, &mut castles, 0);
+            return castles[..added].contains(&mov);
+        }
+        let (own_occupied, opponent_occupied) = if mover == WHITE {
+            (board.white_occupation, board.black_occupation)
+        } else {
+            (board.black_occupation, board.white_occupation)
+        };
+        if bitboard::contains_square(own_occupied, to) {
+            return false;
+        }
+        let is_take: bool = bitboard::contains_square(opponent_occupied, to);
+        let expected: u32 = if piece % 6 == W_PAWN {
+            match self.pawn_move_for(board, mov, is_take) {
+                Some(expected) => expected,
+                None => return false,
+            }
+        } else {
+            let targets: u64 =
+                self.pseudolegal_for(from, piece, mover, board, &mut [], 0, false, true, false, false, GenType::All);
+            if !bitboard::contains_square(targets, to) {
+                return false;
+            }
+            _move::create(from, to, is_take, mover, piece)
+        };
+        with_eaten_piece_for(expected, board, mover) == mov
+    }
+
+    ///Pawn part of is_pseudolegal, the encoding the generator would give the pawn's move from **mov**'s squares if it has one
+    fn pawn_move_for(&self, board: &Board, mov: u32, is_take: bool) -> Option<u32> {
+        let mover: u32 = board.turn;
+        let from: u32 = _move::get_init(mov);
+        let to: u32 = _move::get_target(mov);
+        let piece: u32 = _move::get_moved_piece(mov);
+        let (forward, forward2, start_rank, promotion_rank) = if mover == WHITE {
+            (from + 8, from + 16, RANKS[1], RANKS[7])
+        } else {
+            (from.wrapping_sub(8), from.wrapping_sub(16), RANKS[6], RANKS[0])
+        };
+        let attacks: u64 = self.attack_bbs[piece as usize][from as usize];
+        let expected: u32 = if _move::is_en_passant(mov) {
+            if board.ep_square != Some(to) || !bitboard::contains_square(attacks, to) {
+                return None;
+            }
+            _move::create_en_passant(from, to, mover, piece)
+        } else if is_take {
+            if !bitboard::contains_square(attacks, to) {
+                return None;
+            }
+            _move::create(from, to, true, mover, piece)
+        } else if to == forward {
+            _move::create(from, to, false, mover, piece)
+        } else if to == forward2 && bitboard::contains_square(start_rank, from) && !board.is_occupied(forward) {
+            _move::create_double_push(from, to, mover, piece)
+        } else {
+            return None;
+        };
+        if !bitboard::contains_square(promotion_rank, to) {
+            return Some(expected);
+        }
+        let promotion_piece: u32 = _move::get_promotion_piece(mov);
+        if !_move::is_promotion(mov)
+            || promotion_piece / 6 != mover
+            || !(W_KNIGHT..=W_QUEEN).contains(&(promotion_piece % 6))
+        {
+            return None;
+        }
+        Some(_move::create_promotion(from, to, is_take, promotion_piece, mover, piece))
+    }
+
     ///Edge cases: For en passant check pin edge case, for king check not moving to attacked squares
     pub fn pseudolegal_is_legal(&self, mov: u32, board: &Board) -> bool {
         let init: u32 = _move::get_init(mov);
@@ -242,8 +319,13 @@ impl MoveGen {
         mover: u32,
         in_search: bool,
         in_perft_debug: bool,
-        noisy_only: bool,
+        gen_type: GenType,
     ) -> usize {
+        match gen_type {
+            GenType::Evasions => return self.pseudolegal_evasions(board, pseudo_move_arr, mover, in_search, in_perft_debug),
+            GenType::QuietChecks => return self.pseudolegal_quiet_checks(board, pseudo_move_arr, mover),
+            _ => {}
+        }
         let mut i: usize;
         let e: usize;
         if mover == WHITE {
@@ -269,22 +351,144 @@ impl MoveGen {
                     false,
                     in_search,
                     in_perft_debug,
-                    noisy_only,
+                    gen_type,
                 ) as usize;
             }
             i += 1;
         }
-        added += add_en_passant(board, mover, pseudo_move_arr, added);
-        if !noisy_only {
-            added += add_castling(board, mover, pseudo_move_arr, added);
+        if gen_type != GenType::Quiet {
+            added += add_en_passant(board, mover, pseudo_move_arr, added);
+        }
+        if gen_type != GenType::Noisy {
+            added += add_castling(self, board, mover, pseudo_move_arr, added);
         }
         return added;
     }
 
+    ///King moves, and with a single checker the other pieces' moves onto check_block_sqrs (the checker or its line). <br>
+    ///With two checkers only the king can move.
+    fn pseudolegal_evasions(
+        &self,
+        board: &Board,
+        pseudo_move_arr: &mut [u32],
+        mover: u32,
+        in_search: bool,
+        in_perft_debug: bool,
+    ) -> usize {
+        let king: usize = if mover == WHITE { W_KING_U } else { B_KING_U };
+        let king_sqr: u32 = board.get_king_sqr_idx(mover);
+        let mut added: usize =
+            self.pseudolegal_for(king_sqr, king as u32, mover, board, pseudo_move_arr, 0, false, false, in_search, in_perft_debug, GenType::All) as usize;
+        if board.nof_checkers > 1 {
+            return added;
+        }
+        for piece in (king - W_KING_U)..king {
+            let mut piece_bb: u64 = board.pieces[piece];
+            while piece_bb != 0 {
+                let from: u32 = bitboard::pop_lsb(&mut piece_bb);
+                let s: usize = added;
+                added += self.pseudolegal_for(from, piece as u32, mover, board, pseudo_move_arr, s, false, false, in_search, in_perft_debug, GenType::All) as usize;
+                added = retain_targets(pseudo_move_arr, s, added, board.check_block_sqrs);
+            }
+        }
+        //pseudolegal_is_legal decides whether the captured pawn is the checker
+        added + add_en_passant(board, mover, pseudo_move_arr, added)
+    }
+
+    ///Quiet moves onto a square from which the piece attacks the enemy king, and moves of a piece blocking
+    ///one of its own sliders from the enemy king that leave the line. Castling if the rook lands with check.
+    fn pseudolegal_quiet_checks(&self, board: &Board, pseudo_move_arr: &mut [u32], mover: u32) -> usize {
+        let (first, opponent_pawn) = if mover == WHITE { (W_PAWN_U, B_PAWN_U) } else { (B_PAWN_U, W_PAWN_U) };
+        let enemy_king: usize = board.get_king_sqr_idx(mover ^ 1) as usize;
+        let occupied: u64 = board.total_occupation();
+        let diag_checks: u64 = self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, occupied, false), false);
+        let cardinal_checks: u64 = self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, occupied, true), true);
+        //indexed by piece type, no direct checks by the king
+        let direct_checks: [u64; 6] = [
+            self.attack_bbs[opponent_pawn][enemy_king],
+            self.attack_bbs[W_KNIGHT_U][enemy_king],
+            diag_checks,
+            cardinal_checks,
+            diag_checks | cardinal_checks,
+            0,
+        ];
+        let mut added: usize = 0;
+        for piece in first..first + 6 {
+            let mut piece_bb: u64 = board.pieces[piece];
+            while piece_bb != 0 {
+                let from: u32 = bitboard::pop_lsb(&mut piece_bb);
+                let mut checking: u64 = direct_checks[piece - first];
+                if bitboard::contains_square(diag_checks | cardinal_checks, from) {
+                    if let Some(line) = self.discovered_check_line(board, from, enemy_king, mover) {
+                        checking |= !line;
+                    }
+                }
+                if checking == 0 {
+                    continue;
+                }
+                let s: usize = added;
+                added += self.pseudolegal_for(from, piece as u32, mover, board, pseudo_move_arr, s, false, false, false, false, GenType::Quiet) as usize;
+                added = retain_targets(pseudo_move_arr, s, added, checking);
+            }
+        }
+        let mut castles: [u32; 2] = [NULL_MOVE; 2];
+        let nof_castles: usize = add_castling(self, board, mover, &mut castles, 0);
+        for castle in castles[..nof_castles].iter().copied() {
+            if self.castle_gives_check(board, castle, enemy_king, mover) {
+                pseudo_move_arr[added] = castle;
+                added += 1;
+            }
+        }
+        added
+    }
+
+    ///If the **mover**'s piece at **from** is all that stands between one of its sliders and the enemy king,
+    ///returns the squares between the king and that slider, moving anywhere else gives a discovered check
+    fn discovered_check_line(&self, board: &Board, from: u32, enemy_king: usize, mover: u32) -> Option<u64> {
+        let (rooks, bishops, queens) = if mover == WHITE {
+            (board.pieces[W_ROOK_U], board.pieces[W_BISHOP_U], board.pieces[W_QUEEN_U])
+        } else {
+            (board.pieces[B_ROOK_U], board.pieces[B_BISHOP_U], board.pieces[B_QUEEN_U])
+        };
+        let occupied: u64 = board.total_occupation();
+        for (cardinal, sliders) in [(true, rooks | queens), (false, bishops | queens)] {
+            let seen: u64 = self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, occupied, cardinal), cardinal);
+            if !bitboard::contains_square(seen, from) {
+                continue;
+            }
+            let without_from: u64 = bitboard::with_clear_square(occupied, from);
+            let xray: u64 = self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, without_from, cardinal), cardinal);
+            let revealed: u64 = xray & !seen & sliders;
+            if revealed != 0 {
+                let slider: usize = revealed.trailing_zeros() as usize;
+                let from_slider: u64 =
+                    self.get_sliding_for(slider, self.get_relevant_blockers(slider, without_from, cardinal), cardinal);
+                return Some(xray & from_slider);
+            }
+        }
+        None
+    }
+
+    ///Does **castle** leave a slider of **mover**, most likely the castled rook, attacking the enemy king
+    fn castle_gives_check(&self, board: &Board, castle: u32, enemy_king: usize, mover: u32) -> bool {
+        let (rook_from, rook_to) = board.castling_rook_squares(mover == WHITE, _move::is_short_castle(castle));
+        let moved: u64 = (1 << _move::get_init(castle)) | (1 << rook_from);
+        let landed: u64 = (1 << _move::get_target(castle)) | (1 << rook_to);
+        let occupied: u64 = (board.total_occupation() & !moved) | landed;
+        let (rook_piece, bishops, queens) = if mover == WHITE {
+            (W_ROOK_U, board.pieces[W_BISHOP_U], board.pieces[W_QUEEN_U])
+        } else {
+            (B_ROOK_U, board.pieces[B_BISHOP_U], board.pieces[B_QUEEN_U])
+        };
+        let rooks: u64 = (board.pieces[rook_piece] & !(1 << rook_from)) | (1 << rook_to);
+        self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, occupied, true), true) & (rooks | queens) != 0
+            || self.get_sliding_for(enemy_king, self.get_relevant_blockers(enemy_king, occupied, false), false) & (bishops | queens) != 0
+    }
+
     ///Adds pseudolegal moves for **piece** at **from** to move vector **move_vec**. <br>
     ///1. If pawn, handle separately <br>
     ///2. Get target squares (including eating own pieces), sliding gen or simply attack_bb <br>
-    ///3. If **noisy_only**, remove non-captures by binary ANDing with opponent_occupation <br>
+    ///3. If **gen_type** is Noisy, remove non-captures by binary ANDing with opponent_occupation, if Quiet remove captures <br>
     ///4. If !**keep_protected**, remove "eating own piece" moves by binary ANDing with !own_occupation <br>
     ///5. If **targets_only** just return targets bitboard.
     ///6. Else Pop-lsb 1-by-1 and make move and add to **move_arr** until none left. Returns number of moves added
@@ -300,7 +504,7 @@ impl MoveGen {
         targets_only: bool,
         in_search: bool,
         in_perft_debug: bool,
-        noisy_only: bool,
+        gen_type: GenType,
     ) -> u64 {
         if piece == W_PAWN || piece == B_PAWN {
             //no en passant from this
@@ -313,7 +517,7 @@ impl MoveGen {
                 move_arr_s_idx,
                 in_search,
                 in_perft_debug,
-                noisy_only,
+                gen_type,
             );
             return added;
         }
@@ -354,12 +558,16 @@ impl MoveGen {
             _ => panic!("Couldn't match piece in pseudolegal_for. Reached unreachable case."),
         };
         //3.
-        if noisy_only {
-            if mover == WHITE {
-                targets &= board.black_occupation;
-            } else {
-                targets &= board.white_occupation;
+        match gen_type {
+            GenType::All | GenType::Evasions => {}
+            GenType::Noisy => {
+                if mover == WHITE {
+                    targets &= board.black_occupation;
+                } else {
+                    targets &= board.white_occupation;
+                }
             }
+            GenType::Quiet | GenType::QuietChecks => targets &= !board.total_occupation(),
         }
         //4.
         let opponent_occupied: u64;
@@ -527,37 +735,15 @@ impl MoveGen {
     ///attacked squares change after moving piece. <br>
     ///we call this after moving to get the updated attacked squares <br>
     ///this means we compute targets twice, but the alternative implementations seem even worse <br><br>
-    ///given we compute this, we also easily get nof_checkers
+    ///the checkers come from attackers_to on the opponent king, sliding checkers get their lines in compute_pinned
     pub fn compute_attacked(&self, board: &mut Board, side: u32) -> u64 {
         let mut res: u64 = pawn_attacked(board, side);
         let opponent_king_sqr: u32 = board.get_king_sqr_idx(side ^ 1);
-        if bitboard::contains_square(res, opponent_king_sqr) {
-            //pawn checker
-            board.nof_checkers += 1;
-            //set pawn check block sqr
-            let mover_pawns: u64;
-            let l_offset: i32;
-            let r_offset: i32;
-            if side == WHITE {
-                mover_pawns = board.pieces[W_PAWN_U];
-                l_offset = -9;
-                r_offset = -7;
-            } else {
-                mover_pawns = board.pieces[B_PAWN_U];
-                l_offset = 7;
-                r_offset = 9;
-            }
-            //possible left pawn checker
-            if !bitboard::contains_square(FILES[0], opponent_king_sqr) {
-                board.check_block_sqrs |=
-                    mover_pawns & (1 << (opponent_king_sqr as i32 + l_offset));
-            }
-            //possible right pawn checker
-            if !bitboard::contains_square(FILES[7], opponent_king_sqr) {
-                board.check_block_sqrs |=
-                    mover_pawns & (1 << (opponent_king_sqr as i32 + r_offset));
-            }
-        }
+        let side_occupation: u64 = if side == WHITE { board.white_occupation } else { board.black_occupation };
+        let checkers: u64 =
+            self.attackers_to(board, opponent_king_sqr as usize, board.total_occupation()) & side_occupation;
+        board.nof_checkers += checkers.count_ones();
+        board.check_block_sqrs |= checkers; //can always eat the checker
         let mut i: usize;
         let e: usize;
         if side == WHITE {
@@ -571,7 +757,7 @@ impl MoveGen {
             let mut piece_bb: u64 = board.pieces[i];
             while piece_bb != 0 {
                 let piece_idx: u32 = bitboard::pop_lsb(&mut piece_bb);
-                let targets: u64 = self.pseudolegal_for(
+                res |= self.pseudolegal_for(
                     piece_idx,
                     i as u32,
                     side,
@@ -583,19 +769,62 @@ impl MoveGen {
                     true,
                     true,
                     false,
-                    false, // attack maps must include non-capturing attacks
+                    GenType::All, // attack maps must include non-capturing attacks
                 );
-                if bitboard::contains_square(targets, opponent_king_sqr) {
-                    //see if checker
-                    board.nof_checkers += 1;
-                    bitboard::set_square(&mut board.check_block_sqrs, piece_idx);
-                }
-                res |= targets;
             }
             i += 1;
         }
         return res;
     }
+
+    ///Pieces of both sides attacking **sqr** with **occupied** as the blockers. <br>
+    ///Pieces missing from **occupied** don't attack, so SEE can take out the pieces that already captured.
+    pub fn attackers_to(&self, board: &Board, sqr: usize, occupied: u64) -> u64 {
+        let sqr_bb: u64 = 1 << sqr;
+        //derived from the target since the pawn attack tables are empty on the first and eighth ranks
+        let pawns: u64 = ((((sqr_bb & !FILES[7]) >> 7) | ((sqr_bb & !FILES[0]) >> 9)) & board.pieces[W_PAWN_U])
+            | ((((sqr_bb & !FILES[7]) << 9) | ((sqr_bb & !FILES[0]) << 7)) & board.pieces[B_PAWN_U]);
+        let knights: u64 = board.pieces[W_KNIGHT_U] | board.pieces[B_KNIGHT_U];
+        let kings: u64 = board.pieces[W_KING_U] | board.pieces[B_KING_U];
+        let (diag_sliders, cardinal_sliders) = sliders(board);
+        let mut attackers: u64 = pawns
+            | (self.attack_bbs[W_KNIGHT_U][sqr] & knights)
+            | (self.attack_bbs[W_KING_U][sqr] & kings);
+        if self.attack_bbs[W_BISHOP_U][sqr] & diag_sliders != 0 { //quick check before magic indexing
+            attackers |= self.get_sliding_for(sqr, self.get_relevant_blockers(sqr, occupied, false), false) & diag_sliders;
+        }
+        if self.attack_bbs[W_ROOK_U][sqr] & cardinal_sliders != 0 {
+            attackers |= self.get_sliding_for(sqr, self.get_relevant_blockers(sqr, occupied, true), true) & cardinal_sliders;
+        }
+        attackers & occupied
+    }
+
+    ///Sliders of both sides attacking **sqr** through exactly one blocker in **occupied**, e.g. a queen behind a rook
+    ///or a bishop behind a pinned knight
+    pub fn xray_attackers_to(&self, board: &Board, sqr: usize, occupied: u64) -> u64 {
+        let (diag_sliders, cardinal_sliders) = sliders(board);
+        let mut xrays: u64 = 0;
+        for (cardinal, line_sliders) in [(false, diag_sliders), (true, cardinal_sliders)] {
+            if self.attack_bbs[if cardinal { W_ROOK_U } else { W_BISHOP_U }][sqr] & line_sliders == 0 {
+                continue;
+            }
+            let direct: u64 = self.get_sliding_for(sqr, self.get_relevant_blockers(sqr, occupied, cardinal), cardinal);
+            let behind_blockers: u64 = occupied & !(direct & occupied);
+            xrays |= self.get_sliding_for(sqr, self.get_relevant_blockers(sqr, behind_blockers, cardinal), cardinal)
+                & !direct
+                & line_sliders;
+        }
+        xrays & occupied
+    }
+}
+
+///Diagonal and cardinal sliders of both sides, queens in both
+fn sliders(board: &Board) -> (u64, u64) {
+    let queens: u64 = board.pieces[W_QUEEN_U] | board.pieces[B_QUEEN_U];
+    (
+        board.pieces[W_BISHOP_U] | board.pieces[B_BISHOP_U] | queens,
+        board.pieces[W_ROOK_U] | board.pieces[B_ROOK_U] | queens,
+    )
 }
 
 ///Compute and add attacking bitboard for all pieces at (x, y)
@@ -782,47 +1011,90 @@ fn pawn_attacks_white_for(sqr: u32) -> u64 {
     return res;
 }
 
-///Adds LEGAL castling moves for **mover** to **move_vec**.
-fn add_castling(board: &Board, mover: u32, move_arr: &mut [u32], move_arr_s_idx: usize) -> usize {
-    let mut added: usize = 0;
-    if board.nof_checkers == 0 {
+///Adds LEGAL castling moves for **mover** to **move_vec**. <br>
+///Handles any Chess960 setup: apart from the castling king and rook, every square between the king and its target and
+///between the rook and its target must be empty, the king may not pass an attacked square, and its target may not be
+///attacked along the back rank once the castling rook has moved out of the way.
+fn add_castling(move_gen: &MoveGen, board: &Board, mover: u32, move_arr: &mut [u32], move_arr_s_idx: usize) -> usize {
+    if board.nof_checkers > 0 {
         //can't castle from check
-        let opponent_attacks: u64;
+        return 0;
+    }
+    let mut added: usize = 0;
+    let (opponent_attacks, opponent_cardinal, own_rooks, rights) = if mover == WHITE {
+        (
+            board.black_attacks,
+            board.pieces[B_ROOK_U] | board.pieces[B_QUEEN_U],
+            board.pieces[W_ROOK_U],
+            [board.ws(), board.wl()],
+        )
+    } else {
+        (
+            board.white_attacks,
+            board.pieces[W_ROOK_U] | board.pieces[W_QUEEN_U],
+            board.pieces[B_ROOK_U],
+            [board.bs(), board.bl()],
+        )
+    };
+    let king_from: u32 = board.get_king_sqr_idx(mover);
+    let total_occ: u64 = board.total_occupation();
+    for (is_short, has_right) in [(true, rights[0]), (false, rights[1])] {
+        if !has_right {
+            continue;
+        }
+        let (rook_from, rook_to) = board.castling_rook_squares(mover == WHITE, is_short);
+        if !bitboard::contains_square(own_rooks, rook_from) {
+            continue;
+        }
+        let king_to: u32 = (king_from / 8) * 8 + if is_short { 6 } else { 2 };
+        let others: u64 = total_occ & !(1 << king_from) & !(1 << rook_from);
+        let king_path: u64 = rank_span(king_from, king_to);
+        if (king_path | rank_span(rook_from, rook_to)) & others != 0 || king_path & opponent_attacks != 0 {
+            continue;
+        }
+        //the castling rook may have been shielding the king's target from a rook or queen further along the rank
+        let occ_after: u64 = others | (1 << king_to) | (1 << rook_to);
+        let relevant_blockers: u64 = move_gen.get_relevant_blockers(king_to as usize, occ_after, true);
+        if move_gen.get_sliding_for(king_to as usize, relevant_blockers, true) & opponent_cardinal != 0 {
+            continue;
+        }
+        move_arr[move_arr_s_idx + added] = _move::create_castling_from(king_from, mover, is_short);
+        added += 1;
+    }
+    return added;
+}
+
+///Keeps the moves in **move_arr**[**s**..**e**] whose target is in **targets**, returns the new end
+fn retain_targets(move_arr: &mut [u32], s: usize, e: usize, targets: u64) -> usize {
+    let mut kept: usize = s;
+    for i in s..e {
+        if bitboard::contains_square(targets, _move::get_target(move_arr[i])) {
+            move_arr[kept] = move_arr[i];
+            kept += 1;
+        }
+    }
+    kept
+}
+
+///Squares from **a** to **b** inclusive, both on the same rank
+fn rank_span(a: u32, b: u32) -> u64 {
+    let (lo, hi) = (a.min(b), a.max(b));
+    (u64::MAX >> (63 - hi)) & (u64::MAX << lo)
+}
+
+///Adds the taken piece idx to a pseudolegal move of **mover**, generated moves carry it since make_move needs it
+fn with_eaten_piece_for(mov: u32, board: &Board, mover: u32) -> u32 {
+    if _move::is_en_passant(mov) {
         if mover == WHITE {
-            opponent_attacks = board.black_attacks;
-            let total_occ: u64 = board.total_occupation();
-            if board.ws() && WS_CASTLING_GAP_BB & (total_occ | opponent_attacks) == 0 {
-                //short is legal
-                move_arr[move_arr_s_idx + added] = WHITE_SHORT;
-                added += 1;
-            }
-            if board.wl()
-                && WL_CASTLING_GAP_BB & total_occ == 0
-                && WL_ATTACK_GAP_BB & opponent_attacks == 0
-            {
-                //long is legal
-                move_arr[move_arr_s_idx + added] = WHITE_LONG;
-                added += 1;
-            }
+            _move::with_eaten_piece(mov, 6)
         } else {
-            opponent_attacks = board.white_attacks;
-            let total_occ: u64 = board.total_occupation();
-            if board.bs() && BS_CASTLING_GAP_BB & (total_occ | opponent_attacks) == 0 {
-                //short is legal
-                move_arr[move_arr_s_idx + added] = BLACK_SHORT;
-                added += 1;
-            }
-            if board.bl()
-                && BL_CASTLING_GAP_BB & total_occ == 0
-                && BL_ATTACK_GAP_BB & opponent_attacks == 0
-            {
-                //long is legal
-                move_arr[move_arr_s_idx + added] = BLACK_LONG;
-                added += 1;
-            }
+            mov //white pawn is 0 so do nothing
         }
+    } else if _move::is_eating(mov) {
+        _move::with_eaten_piece(mov, board.get_piece_type_at(_move::get_target(mov), mover ^ 1))
+    } else {
+        mov
     }
-    return added;
 }
 
 ///Adds **pseudolegal** en passants to move_vec
@@ -880,7 +1152,7 @@ pub fn pseudolegal_pawn(
     move_arr_s_idx: usize,
     in_search: bool,
     in_perft_debug: bool,
-    noisy_only: bool,
+    gen_type: GenType,
 ) -> u64 {
     let mut added: usize = 0;
     //following are relative to color:
@@ -906,8 +1178,12 @@ pub fn pseudolegal_pawn(
         pawn_piece_idx = B_PAWN_U;
         enemy_occupied = board.white_occupation;
     }
-    //add attacking moves
-    let mut characteristic_attacks: u64 = move_gen.attack_bbs[pawn_piece_idx][from as usize]; //bitboard
+    //add attacking moves, all of them are noisy
+    let mut characteristic_attacks: u64 = if gen_type == GenType::Quiet {
+        0
+    } else {
+        move_gen.attack_bbs[pawn_piece_idx][from as usize] //bitboard
+    };
     while characteristic_attacks != 0 {
         //check each attack
         let attack_sqr: u32 = bitboard::pop_lsb(&mut characteristic_attacks);
@@ -942,7 +1218,7 @@ pub fn pseudolegal_pawn(
             }
         }
     }
-    if noisy_only && !is_promotion {
+    if (gen_type == GenType::Noisy && !is_promotion) || (gen_type == GenType::Quiet && is_promotion) {
         return added as u64;
     }
     //now check forward moves
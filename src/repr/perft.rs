@@ -0,0 +1,75 @@
+/* Move generation path enumeration, the standard check of the move generator against other engines */
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::{
+    repr::{move_gen::MoveGen, position::Position},
+    utils::zobrist::Zobrist,
+};
+
+/// Number of leaf nodes **depth** plies from **pos**. <br>
+/// Bulk counts the last ply with the move count make_move already generated, so the leaves are never made.
+pub fn perft(depth: usize, pos: &mut Position, move_gen: &MoveGen, zobrist: &Zobrist) -> u64 {
+    match depth {
+        0 => 1,
+        1 => pos.legal_search_moves().len() as u64,
+        _ => {
+            let mut nodes: u64 = 0;
+            let (s, e) = pos.search_move_bounds();
+            for i in s..e {
+                let mov: u32 = pos.move_arr[i];
+                pos.make_move(mov, true, true, false, move_gen, zobrist);
+                nodes += perft(depth - 1, pos, move_gen, zobrist);
+                pos.unmake_move(mov, zobrist);
+            }
+            nodes
+        }
+    }
+}
+
+/// perft split by root move, in move generation order. <br>
+/// The counts sum to perft(depth), at depth 1 every move counts as one leaf.
+pub fn perft_divide(depth: usize, pos: &mut Position, move_gen: &MoveGen, zobrist: &Zobrist) -> Vec<(u32, u64)> {
+    perft_divide_until(depth, pos, move_gen, zobrist, &AtomicBool::new(false)).expect("never stopped")
+}
+
+/// perft_divide that gives up with None once **kill_switch** is set, **pos** is restored either way
+pub fn perft_divide_until(
+    depth: usize,
+    pos: &mut Position,
+    move_gen: &MoveGen,
+    zobrist: &Zobrist,
+    kill_switch: &AtomicBool,
+) -> Option<Vec<(u32, u64)>> {
+    assert!(depth > 0, "divide needs at least one ply");
+    let mut divide: Vec<(u32, u64)> = vec![];
+    let (s, e) = pos.search_move_bounds();
+    for i in s..e {
+        let mov: u32 = pos.move_arr[i];
+        pos.make_move(mov, true, true, false, move_gen, zobrist);
+        let nodes: Option<u64> = perft_until(depth - 1, pos, move_gen, zobrist, kill_switch);
+        pos.unmake_move(mov, zobrist);
+        divide.push((mov, nodes?));
+    }
+    Some(divide)
+}
+
+/// perft, checking **kill_switch** before every node that isn't bulk counted
+fn perft_until(depth: usize, pos: &mut Position, move_gen: &MoveGen, zobrist: &Zobrist, kill_switch: &AtomicBool) -> Option<u64> {
+    if depth < 2 {
+        return Some(perft(depth, pos, move_gen, zobrist));
+    }
+    if kill_switch.load(Relaxed) {
+        return None;
+    }
+    let mut nodes: u64 = 0;
+    let (s, e) = pos.search_move_bounds();
+    for i in s..e {
+        let mov: u32 = pos.move_arr[i];
+        pos.make_move(mov, true, true, false, move_gen, zobrist);
+        let child: Option<u64> = perft_until(depth - 1, pos, move_gen, zobrist, kill_switch);
+        pos.unmake_move(mov, zobrist);
+        nodes += child?;
+    }
+    Some(nodes)
+}
@@ -1,11 +1,14 @@
+use std::sync::Arc;
+
 use crate::repr::types::MAX_PSEUDO_MOVES_IN_POS;
 use crate::repr::*;
+use crate::search::nnue::{Network, NnueState};
 use crate::search::searcher::MAX_SEARCH_DEPTH;
 use crate::{
     repr::{
         _move::NULL_MOVE,
         board::Board,
-        move_gen::{MoveGen, AVG_BRANCH_FAC},
+        move_gen::{GenType, MoveGen, AVG_BRANCH_FAC},
         types::{
             BoardStateInfo, BLACK, B_PAWN, B_PAWN_U, B_ROOK_U, WHITE, W_PAWN, W_PAWN_U, W_ROOK_U,
         },
@@ -26,6 +29,7 @@ pub struct Position {
     pub played_moves_stack: Vec<u32>,
     pub last_target: u32,
     pub move_generation_temp_arr: Vec<u32>,
+    pub nnue: Option<NnueState>, //only kept in sync when a network is attached
 }
 
 impl Position {
@@ -42,7 +46,7 @@ impl Position {
             0,
             false,
             false,
-            false,
+            GenType::All,
         );
         let move_arr_idx: Vec<usize> = vec![0, generated];
         let board_state_info_stack: Vec<BoardStateInfo> = vec![];
@@ -56,6 +60,7 @@ impl Position {
             move_arr_idx,
             last_target,
             move_generation_temp_arr,
+            nnue: None,
         };
     }
 
@@ -75,7 +80,7 @@ impl Position {
             0,
             false,
             false,
-            false,
+            GenType::All,
         );
         let move_arr_idx: Vec<usize> = vec![0, generated];
 
@@ -90,6 +95,7 @@ impl Position {
             move_arr_idx,
             last_target,
             move_generation_temp_arr,
+            nnue: None,
         });
     }
 
@@ -99,6 +105,21 @@ impl Position {
         return &self.move_arr[0..end];
     }
 
+    ///Drops the root moves **keep** rejects, e.g. the ones a tablebase shows to spoil the result
+    pub fn retain_legal_moves(&mut self, keep: impl Fn(u32) -> bool) {
+        let end: usize = self.move_arr_idx[1];
+        let mut kept: usize = 0;
+        for i in 0..end {
+            let mov: u32 = self.move_arr[i];
+            if keep(mov) {
+                self.move_arr[kept] = mov;
+                kept += 1;
+            }
+        }
+        self.move_arr_idx.truncate(2);
+        self.move_arr_idx[1] = kept;
+    }
+
     ///Slice to current search moves (last ply)
     pub fn legal_search_moves(&self) -> &[u32] {
         let s: usize = self.move_arr_idx[self.move_arr_idx.len() - 2];
@@ -115,6 +136,11 @@ impl Position {
         );
     }
 
+    ///Attaching refreshes the accumulator for the current board, None detaches
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| NnueState::new(network, &self.board.pieces));
+    }
+
     pub fn is_late_game(&self) -> bool {
         return self.board.major_minor_count <= 7;
     }
@@ -129,6 +155,18 @@ impl Position {
         move_gen: &MoveGen,
         zobrist: &Zobrist,
     ) {
+        self.make_move_lazy(mov, in_search, move_gen, zobrist);
+        let gen_type: GenType = if in_quiescence && self.board.nof_checkers == 0 {
+            GenType::Noisy
+        } else {
+            GenType::All
+        };
+        self.generate_moves(gen_type, in_search, in_perft_debug, move_gen);
+    }
+
+    ///As make_move, but the new ply's move range is left empty. <br>
+    ///The search fills it with generate_moves stage by stage, so a cutoff by an early move skips the rest of the generation.
+    pub fn make_move_lazy(&mut self, mov: u32, in_search: bool, move_gen: &MoveGen, zobrist: &Zobrist) {
         let is_white_turn: bool = self.board.turn == WHITE;
         let is_promotion: bool = _move::is_promotion(mov);
         let from: u32 = _move::get_init(mov);
@@ -136,7 +174,8 @@ impl Position {
         let moved_piece: usize = _move::get_moved_piece(mov) as usize;
         let is_eating: bool = _move::is_eating(mov);
         let is_castle: bool = _move::is_castle(mov);
-        let is_short_castle: bool = is_castle && _move::is_short_castle(mov);
+        let castling_rook: Option<(u32, u32)> =
+            is_castle.then(|| self.board.castling_rook_squares(is_white_turn, _move::is_short_castle(mov)));
         let is_double_push: bool = _move::is_double_push(mov);
         let is_en_passant: bool = _move::is_en_passant(mov);
         let promotion_piece: Option<usize> = if is_promotion {
@@ -183,6 +222,7 @@ impl Position {
         };
 
         self.board_state_info_stack.push(cur_board_state_info);
+        let pieces_before: [u64; 12] = self.board.pieces;
 
         /*
          *
@@ -209,35 +249,13 @@ impl Position {
             let promotion_piece: usize =
                 promotion_piece.expect("Was promotion but no promotion piece found");
             bitboard::set_square(&mut self.board.pieces[promotion_piece], to);
-        } else if is_castle {
-            let rook_from: u32;
-            let rook_to: u32;
-            let rook_piece_idx: usize;
-            if is_short_castle {
-                if is_white_turn {
-                    rook_from = 7;
-                    rook_to = 5;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 63;
-                    rook_to = 61;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            } else {
-                if is_white_turn {
-                    rook_from = 0;
-                    rook_to = 3;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 56;
-                    rook_to = 59;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            }
+        } else if let Some((rook_from, rook_to)) = castling_rook {
+            let rook_piece_idx: usize = if is_white_turn { W_ROOK_U } else { B_ROOK_U };
             bitboard::clear_square(&mut self.board.pieces[rook_piece_idx], rook_from);
             bitboard::set_square(&mut self.board.pieces[rook_piece_idx], rook_to);
             bitboard::clear_square(own_occupation, rook_from);
             bitboard::set_square(own_occupation, rook_to);
+            bitboard::set_square(own_occupation, to); //in Chess960 the king may land on the rook's start square
         }
 
         if is_en_passant {
@@ -260,6 +278,13 @@ impl Position {
             bitboard::clear_square(opponent_pawns, eating_sqr);
             bitboard::clear_square(opponent_occupation, eating_sqr);
         }
+        if let Some(nnue) = &mut self.nnue {
+            if in_search {
+                nnue.push(&pieces_before, &self.board.pieces);
+            } else {
+                nnue.refresh(&self.board.pieces); //root shifts
+            }
+        }
         /*
          *
          * 2. Update rest of board state
@@ -303,8 +328,7 @@ impl Position {
             promotion_piece,
             is_eating,
             eaten_piece,
-            is_castle,
-            is_short_castle,
+            castling_rook.map(|(rook_from, rook_to)| (rook_from as usize, rook_to as usize)),
             is_double_push,
             is_en_passant,
             lost_ws,
@@ -353,20 +377,38 @@ impl Position {
             self.move_arr_idx.clear();
             self.move_arr_idx.push(0); // 0 ply ends at 0 (exclusive)
         }
+        self.move_arr_idx.push(move_arr_s_idx);
+        self.played_moves_stack.push(mov);
+        self.last_target = to;
+        return;
+    }
+
+    ///Appends the legal moves of **gen_type** to the last ply's range, returns the bounds of the appended moves
+    pub fn generate_moves(
+        &mut self,
+        gen_type: GenType,
+        in_search: bool,
+        in_perft_debug: bool,
+        move_gen: &MoveGen,
+    ) -> (usize, usize) {
+        let s: usize = self.move_arr_idx.last().copied().expect("move_arr_idx was empty");
+        let gen_type: GenType = if gen_type == GenType::All && self.board.nof_checkers > 0 {
+            GenType::Evasions //same moves, fewer pseudolegal candidates
+        } else {
+            gen_type
+        };
         let generated: usize = move_gen.generate_legal(
             &self.board,
-            turn,
+            self.board.turn,
             &mut self.move_arr,
             &mut self.move_generation_temp_arr,
-            move_arr_s_idx,
+            s,
             in_search,
             in_perft_debug,
-            in_quiescence && self.board.nof_checkers == 0,
+            gen_type,
         );
-        self.move_arr_idx.push(move_arr_s_idx + generated);
-        self.played_moves_stack.push(mov);
-        self.last_target = to;
-        return;
+        *self.move_arr_idx.last_mut().expect("move_arr_idx was empty") += generated;
+        (s, s + generated)
     }
 
     /// Unmakes move, resulting that the state of position is equivalent as to before moving.
@@ -377,7 +419,8 @@ impl Position {
         let to: u32 = _move::get_target(mov);
         let moved_piece: usize = _move::get_moved_piece(mov) as usize;
         let is_castle: bool = _move::is_castle(mov);
-        let is_short_castle: bool = is_castle && _move::is_short_castle(mov);
+        let castling_rook: Option<(u32, u32)> =
+            is_castle.then(|| self.board.castling_rook_squares(unmaking_white_move, _move::is_short_castle(mov)));
         let is_promotion: bool = _move::is_promotion(mov);
         let promotion_piece: Option<usize> = if is_promotion {
             Some(_move::get_promotion_piece(mov) as usize)
@@ -429,35 +472,13 @@ impl Position {
             bitboard::set_square(&mut self.board.pieces[moved_piece], from);
         }
 
-        if is_castle {
-            let rook_from: u32;
-            let rook_to: u32;
-            let rook_piece_idx: usize;
-            if is_short_castle {
-                if unmaking_white_move {
-                    rook_from = 7;
-                    rook_to = 5;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 63;
-                    rook_to = 61;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            } else {
-                if unmaking_white_move {
-                    rook_from = 0;
-                    rook_to = 3;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 56;
-                    rook_to = 59;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            }
+        if let Some((rook_from, rook_to)) = castling_rook {
+            let rook_piece_idx: usize = if unmaking_white_move { W_ROOK_U } else { B_ROOK_U };
             bitboard::clear_square(&mut self.board.pieces[rook_piece_idx], rook_to);
             bitboard::set_square(&mut self.board.pieces[rook_piece_idx], rook_from);
             bitboard::clear_square(own_occupation, rook_to);
             bitboard::set_square(own_occupation, rook_from);
+            bitboard::set_square(own_occupation, from); //in Chess960 the rook may have ended on the king's start square
         }
 
         if is_en_passant {
@@ -475,6 +496,9 @@ impl Position {
             bitboard::set_square(opponent_pawns, eating_sqr);
             bitboard::set_square(opponent_occupation, eating_sqr);
         }
+        if let Some(nnue) = &mut self.nnue {
+            nnue.pop(&self.board.pieces);
+        }
         /*
          *
          * 2. Update rest of board state
@@ -508,8 +532,7 @@ impl Position {
             promotion_piece,
             is_eating,
             eaten_piece,
-            is_castle,
-            is_short_castle,
+            castling_rook.map(|(rook_from, rook_to)| (rook_from as usize, rook_to as usize)),
             is_double_push,
             is_en_passant,
             gained_ws,
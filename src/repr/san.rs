@@ -0,0 +1,175 @@
+/* Standard Algebraic Notation for moves of a Position */
+
+use crate::{
+    repr::{
+        _move,
+        board::square_to_string,
+        move_gen::MoveGen,
+        position::Position,
+        types::*,
+    },
+    utils::zobrist::Zobrist,
+};
+
+//indexed by piece kind (W_PAWN_U..=W_KING_U)
+const SAN_PIECE_CHARS: [char; NOF_PIECE_TYPES_U] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+impl Position {
+    /// SAN of **mov**, which has to be one of legal_moves(). <br>
+    /// The move is made on a copy to find out the check and mate suffixes.
+    pub fn move_to_san(&self, mov: u32, move_gen: &MoveGen, zobrist: &Zobrist) -> String {
+        let mut res: String = if _move::is_short_castle(mov) {
+            "O-O".to_string()
+        } else if _move::is_long_castle(mov) {
+            "O-O-O".to_string()
+        } else {
+            self.san_body(mov)
+        };
+
+        let mut after: Position = self.clone();
+        after.nnue = None;
+        after.make_move(mov, false, false, false, move_gen, zobrist);
+        if after.in_checkmate() {
+            res.push('#');
+        } else if after.board.nof_checkers > 0 {
+            res.push('+');
+        }
+        res
+    }
+
+    fn san_body(&self, mov: u32) -> String {
+        let from: u32 = _move::get_init(mov);
+        let to: u32 = _move::get_target(mov);
+        let kind: usize = _move::get_moved_piece(mov) as usize % NOF_PIECE_TYPES_U;
+        let from_str: String = square_to_string(from);
+        let mut res: String = String::new();
+
+        if kind == W_PAWN_U {
+            if _move::is_eating(mov) {
+                res.push_str(&from_str[0..1]);
+            }
+        } else {
+            res.push(SAN_PIECE_CHARS[kind]);
+            //same kind of piece that can also reach target
+            let rivals: Vec<u32> = self
+                .legal_moves()
+                .iter()
+                .copied()
+                .filter(|other| {
+                    _move::get_target(*other) == to
+                        && _move::get_init(*other) != from
+                        && _move::get_moved_piece(*other) == _move::get_moved_piece(mov)
+                })
+                .map(_move::get_init)
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|sqr| sqr % 8 != from % 8) {
+                    res.push_str(&from_str[0..1]);
+                } else if rivals.iter().all(|sqr| sqr / 8 != from / 8) {
+                    res.push_str(&from_str[1..2]);
+                } else {
+                    res.push_str(&from_str);
+                }
+            }
+        }
+
+        if _move::is_eating(mov) {
+            res.push('x');
+        }
+        res.push_str(&square_to_string(to));
+        if _move::is_promotion(mov) {
+            res.push('=');
+            res.push(SAN_PIECE_CHARS[_move::get_promotion_piece(mov) as usize % NOF_PIECE_TYPES_U]);
+        }
+        res
+    }
+
+    /// Resolves SAN against legal_moves(). <br>
+    /// Lenient: accepts 0-0 castling, missing or extra check marks and annotations, promotions without '='
+    /// in either case, and long algebraic like Ng1-f3.
+    pub fn parse_san(&self, san: &str) -> Result<u32, &'static str> {
+        let san: &str = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        match san {
+            "O-O" | "0-0" => return self.find_castle(true),
+            "O-O-O" | "0-0-0" => return self.find_castle(false),
+            _ => {}
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+        let kind: usize = match chars.first().and_then(|c| SAN_PIECE_CHARS.iter().position(|p| p == c)) {
+            Some(kind) => {
+                chars.remove(0);
+                kind
+            }
+            None => W_PAWN_U,
+        };
+
+        let mut promotion: Option<usize> = None;
+        if kind == W_PAWN_U && chars.len() >= 3 {
+            let last: char = chars[chars.len() - 1];
+            if let Some(p) = SAN_PIECE_CHARS[W_KNIGHT_U..W_KING_U]
+                .iter()
+                .position(|c| *c == last.to_ascii_uppercase())
+            {
+                promotion = Some(p + W_KNIGHT_U);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err("Invalid SAN move");
+        }
+        let to: u32 = parse_san_square(chars[chars.len() - 2], chars[chars.len() - 1])?;
+        if kind == W_PAWN_U && promotion.is_none() && (to / 8 == 0 || to / 8 == 7) {
+            return Err("Missing SAN promotion piece");
+        }
+        let mut from_file: Option<u32> = None;
+        let mut from_rank: Option<u32> = None;
+        for c in chars[..chars.len() - 2].iter() {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(*c as u32 - 'a' as u32),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(*c as u32 - '1' as u32),
+                _ => return Err("Invalid SAN move"),
+            }
+        }
+
+        let mut candidates = self.legal_moves().iter().copied().filter(|mov| {
+            let from: u32 = _move::get_init(*mov);
+            _move::get_moved_piece(*mov) as usize % NOF_PIECE_TYPES_U == kind
+                && _move::get_target(*mov) == to
+                && from_file.is_none_or(|file| from % 8 == file)
+                && from_rank.is_none_or(|rank| from / 8 == rank)
+                && _move::lift_promotion_piece(*mov).map(|p| p as usize % NOF_PIECE_TYPES_U) == promotion
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mov), None) => Ok(mov),
+            (None, _) => Err("Illegal SAN move"),
+            (Some(_), Some(_)) => Err("Ambiguous SAN move"),
+        }
+    }
+
+    fn find_castle(&self, short: bool) -> Result<u32, &'static str> {
+        self.legal_moves()
+            .iter()
+            .copied()
+            .find(|mov| {
+                if short {
+                    _move::is_short_castle(*mov)
+                } else {
+                    _move::is_long_castle(*mov)
+                }
+            })
+            .ok_or("Illegal SAN move")
+    }
+}
+
+fn parse_san_square(file: char, rank: char) -> Result<u32, &'static str> {
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return Err("Invalid SAN square");
+    }
+    Ok((rank as u32 - '1' as u32) * 8 + (file as u32 - 'a' as u32))
+}
@@ -0,0 +1,285 @@
+use std::sync::OnceLock;
+
+use crate::{
+    repr::{
+        bitboard,
+        board::{FILES, LIGHT_SQUARES},
+        move_gen::{CARDINAL_STEPS, DIAG_STEPS},
+        types::*,
+    },
+    search::eval::PIECE_MATERIAL_VALUE,
+};
+
+/// Base score of a recognised won endgame, mop-up terms are added on top. <br>
+/// Stays well below MATE_BOUND so real mates are always preferred.
+pub const KNOWN_WIN: i16 = 10_000;
+
+const MOP_UP_WEIGHT: i16 = 20;
+const PAWN_RANK_WEIGHT: i16 = 20;
+
+//2 sides to move * 24 pawn squares (files a-d, ranks 2-7) * 64 * 64 king squares
+const NOF_KPK_POSITIONS: usize = 2 * 24 * 64 * 64;
+//bit flags so that classification can union successor results
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+static KPK_BITBASE: OnceLock<KpkBitbase> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndgameKind {
+    KQK,
+    KRK,
+    KBNK,
+    KPK,
+}
+
+/// Recognises a basic endgame from material alone. <br>
+/// Returns the kind and the side holding the extra material, the other side must have a bare king.
+pub fn classify(pieces: &[u64; 12]) -> Option<(EndgameKind, u32)> {
+    if pieces[W_KING_U] == 0 || pieces[B_KING_U] == 0 {
+        return None;
+    }
+    for (strong_side, strong, weak) in [(WHITE, W_PAWN_U, B_PAWN_U), (BLACK, B_PAWN_U, W_PAWN_U)] {
+        if (W_PAWN_U..W_KING_U).any(|p| pieces[weak + p] != 0) {
+            continue;
+        }
+        let counts: [u32; 5] = std::array::from_fn(|p| pieces[strong + p].count_ones());
+        let kind: Option<EndgameKind> = match counts {
+            [0, 0, 0, 0, 1] => Some(EndgameKind::KQK),
+            [0, 0, 0, 1, 0] => Some(EndgameKind::KRK),
+            [0, 1, 1, 0, 0] => Some(EndgameKind::KBNK),
+            [1, 0, 0, 0, 0] => Some(EndgameKind::KPK),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            return Some((kind, strong_side));
+        }
+    }
+    None
+}
+
+/// Dedicated evaluation for recognised endgames, negamax compliant like Evaluator::eval. <br>
+/// **mover** has to be the side to move since KPK results depend on it.
+pub fn probe(pieces: &[u64; 12], mover: u32) -> Option<i16> {
+    let (kind, strong_side) = classify(pieces)?;
+    let (strong, weak): (usize, usize) = if strong_side == WHITE {
+        (W_PAWN_U, B_PAWN_U)
+    } else {
+        (B_PAWN_U, W_PAWN_U)
+    };
+    let strong_king: u32 = pieces[strong + W_KING_U].trailing_zeros();
+    let weak_king: u32 = pieces[weak + W_KING_U].trailing_zeros();
+    let closeness: i16 = MOP_UP_WEIGHT * (7 - distance(strong_king, weak_king));
+
+    let score: i16 = match kind {
+        EndgameKind::KQK | EndgameKind::KRK => {
+            let piece: usize = if kind == EndgameKind::KQK { W_QUEEN_U } else { W_ROOK_U };
+            KNOWN_WIN + PIECE_MATERIAL_VALUE[piece] + push_to_edge(weak_king) + closeness
+        }
+        EndgameKind::KBNK => {
+            //mate is only possible in a corner of the bishop's color, a1 and h8 are dark
+            let corner_king: u32 = if pieces[strong + W_BISHOP_U] & LIGHT_SQUARES != 0 {
+                weak_king ^ 56
+            } else {
+                weak_king
+            };
+            KNOWN_WIN
+                + PIECE_MATERIAL_VALUE[W_KNIGHT_U]
+                + PIECE_MATERIAL_VALUE[W_BISHOP_U]
+                + push_to_dark_corner(corner_king)
+                + closeness
+        }
+        EndgameKind::KPK => {
+            let pawn: u32 = pieces[strong + W_PAWN_U].trailing_zeros();
+            if !kpk_bitbase().probe(strong_side, strong_king, pawn, weak_king, mover) {
+                return Some(0);
+            }
+            let relative_rank: i16 = if strong_side == WHITE {
+                (pawn / 8) as i16
+            } else {
+                7 - (pawn / 8) as i16
+            };
+            KNOWN_WIN + PIECE_MATERIAL_VALUE[W_PAWN_U] + PAWN_RANK_WEIGHT * relative_rank
+        }
+    };
+
+    if mover == strong_side {
+        Some(score)
+    } else {
+        Some(-score)
+    }
+}
+
+/// Shared KPK bitbase, generated on first call
+pub fn kpk_bitbase() -> &'static KpkBitbase {
+    KPK_BITBASE.get_or_init(KpkBitbase::generate)
+}
+
+/// Exact win/draw knowledge for king and pawn versus king, one bit per position. <br>
+/// Built by retrograde iteration from white's perspective with the pawn on files a-d.
+pub struct KpkBitbase {
+    wins: Vec<u64>,
+}
+
+impl KpkBitbase {
+    pub fn generate() -> Self {
+        let king_bbs: [u64; 64] = std::array::from_fn(|sqr| king_attacks(sqr as u32));
+        let mut results: Vec<u8> = (0..NOF_KPK_POSITIONS)
+            .map(|idx| initial_kpk_result(idx, &king_bbs))
+            .collect();
+
+        let mut changed: bool = true;
+        while changed {
+            changed = false;
+            for idx in 0..NOF_KPK_POSITIONS {
+                if results[idx] == UNKNOWN {
+                    let result: u8 = classify_kpk(idx, &results, &king_bbs);
+                    if result != UNKNOWN {
+                        results[idx] = result;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        //positions still unknown can't be forced to a win
+        let mut wins: Vec<u64> = vec![0; NOF_KPK_POSITIONS / 64];
+        for (idx, result) in results.iter().enumerate() {
+            if *result == WIN {
+                wins[idx / 64] |= 1u64 << (idx % 64);
+            }
+        }
+        Self { wins }
+    }
+
+    /// Is the position won for **strong_side**, the side owning the pawn? <br>
+    /// Any legal placement is accepted, the position is normalized internally.
+    pub fn probe(&self, strong_side: u32, strong_king: u32, pawn: u32, weak_king: u32, mover: u32) -> bool {
+        let (mut wk, mut psq, mut bk): (u32, u32, u32) = (strong_king, pawn, weak_king);
+        if strong_side == BLACK {
+            wk ^= 56;
+            psq ^= 56;
+            bk ^= 56;
+        }
+        if psq % 8 >= 4 {
+            wk ^= 7;
+            psq ^= 7;
+            bk ^= 7;
+        }
+        let stm: u32 = if mover == strong_side { WHITE } else { BLACK };
+        let idx: usize = kpk_index(stm, bk, wk, psq);
+        self.wins[idx / 64] & (1u64 << (idx % 64)) != 0
+    }
+}
+
+fn kpk_index(stm: u32, bk: u32, wk: u32, psq: u32) -> usize {
+    (wk | (bk << 6) | (stm << 12) | ((psq % 8) << 13) | ((6 - psq / 8) << 15)) as usize
+}
+
+///(stm, bk, wk, psq)
+fn decode_kpk_index(idx: usize) -> (u32, u32, u32, u32) {
+    let idx: u32 = idx as u32;
+    let psq: u32 = ((idx >> 13) & 3) + 8 * (6 - (idx >> 15));
+    ((idx >> 12) & 1, (idx >> 6) & 63, idx & 63, psq)
+}
+
+fn initial_kpk_result(idx: usize, king_bbs: &[u64; 64]) -> u8 {
+    let (stm, bk, wk, psq) = decode_kpk_index(idx);
+    let push_sqr: u32 = psq + 8;
+    let pawn_attacks: u64 = white_pawn_attacks(psq);
+
+    if distance(wk, bk) <= 1
+        || wk == psq
+        || bk == psq
+        || (stm == WHITE && bitboard::contains_square(pawn_attacks, bk))
+    {
+        INVALID
+    } else if stm == WHITE
+        && psq / 8 == 6
+        && wk != push_sqr
+        && (distance(bk, push_sqr) > 1 || distance(wk, push_sqr) == 1)
+    {
+        WIN //promotes safely
+    } else if stm == BLACK
+        && (king_bbs[bk as usize] & !(king_bbs[wk as usize] | pawn_attacks) == 0
+            || bitboard::contains_square(king_bbs[bk as usize] & !king_bbs[wk as usize], psq))
+    {
+        DRAW //stalemate or undefended pawn is captured
+    } else {
+        UNKNOWN
+    }
+}
+
+fn classify_kpk(idx: usize, results: &[u8], king_bbs: &[u64; 64]) -> u8 {
+    let (stm, bk, wk, psq) = decode_kpk_index(idx);
+    let (good, bad): (u8, u8) = if stm == WHITE { (WIN, DRAW) } else { (DRAW, WIN) };
+    //illegal successors are INVALID and don't contribute
+    let mut successors: u8 = INVALID;
+
+    if stm == WHITE {
+        let mut king_moves: u64 = king_bbs[wk as usize];
+        while king_moves > 0 {
+            successors |= results[kpk_index(BLACK, bk, bitboard::pop_lsb(&mut king_moves), psq)];
+        }
+        if psq / 8 < 6 {
+            successors |= results[kpk_index(BLACK, bk, wk, psq + 8)];
+        }
+        if psq / 8 == 1 && psq + 8 != wk && psq + 8 != bk {
+            successors |= results[kpk_index(BLACK, bk, wk, psq + 16)];
+        }
+    } else {
+        let mut king_moves: u64 = king_bbs[bk as usize];
+        while king_moves > 0 {
+            successors |= results[kpk_index(WHITE, bitboard::pop_lsb(&mut king_moves), wk, psq)];
+        }
+    }
+
+    if successors & good != 0 {
+        good
+    } else if successors & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+fn king_attacks(sqr: u32) -> u64 {
+    let (x, y): (i32, i32) = ((sqr % 8) as i32, (sqr / 8) as i32);
+    DIAG_STEPS
+        .iter()
+        .chain(CARDINAL_STEPS.iter())
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .filter(|(nx, ny)| (0..8).contains(nx) && (0..8).contains(ny))
+        .fold(0, |bb, (nx, ny)| bitboard::with_set_square(bb, (nx + 8 * ny) as u32))
+}
+
+fn white_pawn_attacks(sqr: u32) -> u64 {
+    let mut attacks: u64 = 0;
+    if !bitboard::contains_square(FILES[0], sqr) {
+        bitboard::set_square(&mut attacks, sqr + 7);
+    }
+    if !bitboard::contains_square(FILES[7], sqr) {
+        bitboard::set_square(&mut attacks, sqr + 9);
+    }
+    attacks
+}
+
+/// King move distance
+fn distance(a: u32, b: u32) -> i16 {
+    let file_diff: i16 = ((a % 8) as i16 - (b % 8) as i16).abs();
+    let rank_diff: i16 = ((a / 8) as i16 - (b / 8) as i16).abs();
+    file_diff.max(rank_diff)
+}
+
+fn push_to_edge(sqr: u32) -> i16 {
+    let center_file_dist: i16 = (3 - (sqr % 8) as i16).max((sqr % 8) as i16 - 4);
+    let center_rank_dist: i16 = (3 - (sqr / 8) as i16).max((sqr / 8) as i16 - 4);
+    MOP_UP_WEIGHT * (center_file_dist + center_rank_dist)
+}
+
+//distance from the a8-h1 diagonal, greatest in a1 and h8
+fn push_to_dark_corner(sqr: u32) -> i16 {
+    MOP_UP_WEIGHT * (7 - (sqr % 8) as i16 - (sqr / 8) as i16).abs()
+}
@@ -1,148 +1,332 @@
 use crate::{
-    repr::{bitboard, types::*},
-    search::table_loader::parse_table_values,
+    repr::{
+        bitboard,
+        board::{DARK_SQUARES, LIGHT_SQUARES},
+        types::*,
+    },
+    search::{
+        endgame::{self, EndgameKind},
+        table_loader::parse_table_values,
+    },
 };
 
 pub const PHASE_MULTIPLIERS: [i16; 12] = [0, 1, 1, 2, 4, 0, 0, 1, 1, 2, 4, 0]; //giving 25 distinct phases
 pub const MAX_LATE_GAME_PHASE: usize = 24;
-const PHASE_COEFFS: [(f32, f32); MAX_LATE_GAME_PHASE + 1] = [
-    (1.0, 0.0),
-    (0.96, 0.04),
-    (0.92, 0.08),
-    (0.88, 0.12),
-    (0.84, 0.16),
-    (0.8, 0.2),
-    (0.76, 0.24),
-    (0.72, 0.28),
-    (0.68, 0.32),
-    (0.64, 0.36),
-    (0.6, 0.4),
-    (0.56, 0.44),
-    (0.52, 0.48),
-    (0.48, 0.52),
-    (0.44, 0.56),
-    (0.4, 0.6),
-    (0.36, 0.64),
-    (0.32, 0.68),
-    (0.28, 0.72),
-    (0.24, 0.76),
-    (0.2, 0.8),
-    (0.16, 0.84),
-    (0.12, 0.88),
-    (0.08, 0.92),
-    (0.04, 0.96),
-];
 
 pub const MATE_EVAL: i16 = 25_000;
 pub const MATE_BOUND: i16 = MATE_EVAL - 1000;
-const TABLE_SOURCES: [&str; 8] = [
+//indexed by piece kind (W_PAWN_U..=W_KING_U), early game and late game
+const MG_TABLE_SOURCES: [&str; NOF_PIECE_TYPES_U] = [
     include_str!("../../assets/piece_square_tables/pawn_e.txt"),
     include_str!("../../assets/piece_square_tables/knight.txt"),
     include_str!("../../assets/piece_square_tables/bishop.txt"),
     include_str!("../../assets/piece_square_tables/rook.txt"),
     include_str!("../../assets/piece_square_tables/queen.txt"),
     include_str!("../../assets/piece_square_tables/king_e.txt"),
+];
+const EG_TABLE_SOURCES: [&str; NOF_PIECE_TYPES_U] = [
     include_str!("../../assets/piece_square_tables/pawn_l.txt"),
+    include_str!("../../assets/piece_square_tables/knight.txt"),
+    include_str!("../../assets/piece_square_tables/bishop.txt"),
+    include_str!("../../assets/piece_square_tables/rook.txt"),
+    include_str!("../../assets/piece_square_tables/queen.txt"),
     include_str!("../../assets/piece_square_tables/king_l.txt"),
 ];
 
 pub const PIECE_MATERIAL_VALUE: [i16; 12] = [
     100, 320, 330, 500, 900, 20000, 100, 320, 330, 500, 900, 20000,
 ];
-const PAWN_LATE_GAME_PST_IDX: usize = 6;
-const KING_LATE_GAME_PST_IDX: usize = 7;
+
+//late game score is multiplied by scale / SCALE_NORMAL
+pub const SCALE_NORMAL: i32 = 64;
+const SCALE_OPPOSITE_BISHOPS: i32 = 16;
+const SCALE_PAWNLESS_MINOR_UP: i32 = 0;
+const SCALE_PAWNLESS_VS_MINOR: i32 = 4;
+const SCALE_PAWNLESS_VS_MAJOR: i32 = 14;
+
+/// Tunable evaluation terms, indexed by piece kind (W_PAWN_U..=W_KING_U). <br>
+/// Tables are from white's perspective, black looks them up with sqr ^ 56.
+#[derive(Clone)]
+pub struct EvalParams {
+    pub material_mg: [i16; NOF_PIECE_TYPES_U],
+    pub material_eg: [i16; NOF_PIECE_TYPES_U],
+    pub pst_mg: [[i16; 64]; NOF_PIECE_TYPES_U],
+    pub pst_eg: [[i16; 64]; NOF_PIECE_TYPES_U],
+}
+
+impl Default for EvalParams {
+    /// Embedded tables. Only pawns and kings have distinct late game tables.
+    fn default() -> Self {
+        let parse = |source: &str| -> [i16; 64] {
+            parse_table_values(source)
+                .ok()
+                .and_then(|table| table.try_into().ok())
+                .expect("embedded piece-square table must be valid")
+        };
+        let material: [i16; NOF_PIECE_TYPES_U] =
+            std::array::from_fn(|p| PIECE_MATERIAL_VALUE[p]);
+
+        Self {
+            material_mg: material,
+            material_eg: material,
+            pst_mg: MG_TABLE_SOURCES.map(parse),
+            pst_eg: EG_TABLE_SOURCES.map(parse),
+        }
+    }
+}
 
 //pst: piece square table
 pub struct Evaluator {
-    pst: [Vec<i16>; 8], // [6] == pawn late game, [7] == king late game
-    pst_pawn_early: Vec<f32>,
-    pst_pawn_late: Vec<f32>,
-    pst_king_early: Vec<f32>,
-    pst_king_late: Vec<f32>,
+    params: EvalParams,
+    //material folded in, indexed by piece idx and square so black tables are already mirrored
+    mg_table: [[i16; 64]; 12],
+    eg_table: [[i16; 64]; 12],
 }
 
 impl Default for Evaluator {
     fn default() -> Self {
-        let pst = TABLE_SOURCES.map(|source| {
-            parse_table_values(source).expect("embedded piece-square table must be valid")
-        });
-        let to_f32 = |table: &[i16]| -> Vec<f32> { table.iter().copied().map(f32::from).collect() };
-
-        let pst_pawn_early = to_f32(&pst[W_PAWN_U]);
-        let pst_pawn_late = to_f32(&pst[PAWN_LATE_GAME_PST_IDX]);
-        let pst_king_early = to_f32(&pst[W_KING_U]);
-        let pst_king_late = to_f32(&pst[KING_LATE_GAME_PST_IDX]);
+        Self::with_params(EvalParams::default())
+    }
+}
 
+impl Evaluator {
+    /// Also makes sure the shared KPK bitbase is generated before any search
+    pub fn with_params(params: EvalParams) -> Self {
+        endgame::kpk_bitbase();
+        let mut mg_table: [[i16; 64]; 12] = [[0; 64]; 12];
+        let mut eg_table: [[i16; 64]; 12] = [[0; 64]; 12];
+        for p in 0..12 {
+            for sqr in 0..64 {
+                for (mg, eg) in piece_terms(&params, p, sqr) {
+                    mg_table[p][sqr] += mg;
+                    eg_table[p][sqr] += eg;
+                }
+            }
+        }
         Self {
-            pst,
-            pst_pawn_early,
-            pst_pawn_late,
-            pst_king_early,
-            pst_king_late,
+            params,
+            mg_table,
+            eg_table,
         }
     }
-}
 
-impl Evaluator {
-    /// mover is only required for negamax algorithm's sake <br>
-    /// late_game_phase ranges from [0, MAX_LATE_GAME_PHASE]
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    /// mover is the side to move, negamax compliant <br>
+    /// late_game_phase ranges from [0, MAX_LATE_GAME_PHASE] <br>
+    /// Recognised basic endgames are evaluated by the endgame module instead
     pub fn eval(&self, pieces: [u64; 12], mover: u32, late_game_phase: usize) -> i16 {
-        let mut white_v: i16 = 0;
-        let mut black_v: i16 = 0;
-        let phase_coeffs: (f32, f32) = PHASE_COEFFS[late_game_phase];
-
-        let mut w_pawn_bb: u64 = pieces[W_PAWN_U];
-        while w_pawn_bb > 0 {
-            white_v += PIECE_MATERIAL_VALUE[W_PAWN_U];
-            let idx: usize = bitboard::pop_lsb(&mut w_pawn_bb) as usize;
-            white_v += (phase_coeffs.0 * self.pst_pawn_early[idx]
-                + phase_coeffs.1 * self.pst_pawn_late[idx]) as i16;
-        }
+        let mut mg: i32 = 0;
+        let mut eg: i32 = 0;
 
-        for p in W_KNIGHT_U..=W_QUEEN_U {
-            let mut p_bb: u64 = pieces[p];
+        for (p, piece_bb) in pieces.iter().enumerate() {
+            let mut p_bb: u64 = *piece_bb;
             while p_bb > 0 {
-                white_v += PIECE_MATERIAL_VALUE[p];
-                white_v += self.pst[p][bitboard::pop_lsb(&mut p_bb) as usize];
+                let idx: usize = bitboard::pop_lsb(&mut p_bb) as usize;
+                if p < B_PAWN_U {
+                    mg += self.mg_table[p][idx] as i32;
+                    eg += self.eg_table[p][idx] as i32;
+                } else {
+                    mg -= self.mg_table[p][idx] as i32;
+                    eg -= self.eg_table[p][idx] as i32;
+                }
             }
         }
-        let mut w_king_bb: u64 = pieces[W_KING_U];
-        while w_king_bb > 0 {
-            white_v += PIECE_MATERIAL_VALUE[W_KING_U];
-            let idx: usize = bitboard::pop_lsb(&mut w_king_bb) as usize;
-            white_v += (phase_coeffs.0 * self.pst_king_early[idx]
-                + phase_coeffs.1 * self.pst_king_late[idx]) as i16;
-        }
 
-        let mut b_pawn_bb: u64 = pieces[B_PAWN_U];
-        while b_pawn_bb > 0 {
-            black_v += PIECE_MATERIAL_VALUE[B_PAWN_U];
-            let idx: usize = bitboard::pop_lsb(&mut b_pawn_bb) as usize ^ 56;
-            black_v += (phase_coeffs.0 * self.pst_pawn_early[idx]
-                + phase_coeffs.1 * self.pst_pawn_late[idx]) as i16;
+        let (white_v, _) = white_score(&pieces, mover, mg, eg, late_game_phase);
+        //negamax compliant
+        if mover == WHITE {
+            white_v
+        } else {
+            -white_v
         }
+    }
+}
+
+impl Evaluator {
+    /// Breakdown of eval into its terms, per side and per game phase. <br>
+    /// All values are in centipawns, totals and the final score are from white's perspective.
+    pub fn trace(&self, pieces: [u64; 12], mover: u32, late_game_phase: usize) -> EvalTrace {
+        let mut terms: Vec<EvalTerm> = vec![EvalTerm::new("Material")];
+        terms.extend(PIECE_NAMES.iter().map(|name| EvalTerm::new(&format!("{name} PST"))));
 
-        for p in B_KNIGHT_U..=B_QUEEN_U {
-            let mut p_bb: u64 = pieces[p];
+        for (p, piece_bb) in pieces.iter().enumerate() {
+            let (kind, side): (usize, usize) = (p % NOF_PIECE_TYPES_U, p / NOF_PIECE_TYPES_U);
+            let mut p_bb: u64 = *piece_bb;
             while p_bb > 0 {
-                black_v += PIECE_MATERIAL_VALUE[p];
-                black_v +=
-                    self.pst[p - NOF_PIECE_TYPES_U][bitboard::pop_lsb(&mut p_bb) as usize ^ 56];
+                let idx: usize = bitboard::pop_lsb(&mut p_bb) as usize;
+                for ((mg, eg), term) in piece_terms(&self.params, p, idx).into_iter().zip([0, kind + 1]) {
+                    terms[term].mg[side] += mg as i32;
+                    terms[term].eg[side] += eg as i32;
+                }
             }
         }
-        let mut b_king_bb: u64 = pieces[B_KING_U];
-        while b_king_bb > 0 {
-            black_v += PIECE_MATERIAL_VALUE[B_KING_U];
-            let idx: usize = bitboard::pop_lsb(&mut b_king_bb) as usize ^ 56;
-            black_v += (phase_coeffs.0 * self.pst_king_early[idx]
-                + phase_coeffs.1 * self.pst_king_late[idx]) as i16;
+
+        let mg: i32 = terms.iter().map(EvalTerm::mg_total).sum();
+        let eg: i32 = terms.iter().map(EvalTerm::eg_total).sum();
+        let (score, scale) = white_score(&pieces, mover, mg, eg, late_game_phase);
+        let endgame: Option<(EndgameKind, u32)> = endgame::classify(&pieces);
+
+        EvalTrace {
+            terms,
+            mg,
+            eg,
+            scale,
+            late_game_phase,
+            endgame,
+            score,
+        }
+    }
+}
+
+const PIECE_NAMES: [&str; NOF_PIECE_TYPES_U] = ["Pawn", "Knight", "Bishop", "Rook", "Queen", "King"];
+
+/// Early and late game values of piece idx **p** on **sqr** for its own side: material, then piece-square. <br>
+/// eval sums them up front into its tables, trace lists them as separate terms
+fn piece_terms(params: &EvalParams, p: usize, sqr: usize) -> [(i16, i16); 2] {
+    let kind: usize = p % NOF_PIECE_TYPES_U;
+    let sqr: usize = if p < B_PAWN_U { sqr } else { sqr ^ 56 };
+    [
+        (params.material_mg[kind], params.material_eg[kind]),
+        (params.pst_mg[kind][sqr], params.pst_eg[kind][sqr]),
+    ]
+}
+
+/// White's score from the summed terms and the endgame scale applied to **eg**. <br>
+/// Recognised basic endgames are scored by the endgame module instead
+fn white_score(pieces: &[u64; 12], mover: u32, mg: i32, eg: i32, late_game_phase: usize) -> (i16, i32) {
+    let strong_side: u32 = if eg >= 0 { WHITE } else { BLACK };
+    let scale: i32 = endgame_scale(pieces, strong_side);
+    let score: i16 = match endgame::probe(pieces, mover) {
+        Some(score) if mover == WHITE => score,
+        Some(score) => -score,
+        None => taper(mg, eg * scale / SCALE_NORMAL, late_game_phase),
+    };
+    (score, scale)
+}
+
+/// Single evaluation term, mg and eg are indexed by side
+pub struct EvalTerm {
+    pub name: String,
+    pub mg: [i32; 2],
+    pub eg: [i32; 2],
+}
+
+impl EvalTerm {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            mg: [0; 2],
+            eg: [0; 2],
         }
+    }
 
-        //negamax compliant
-        if mover == WHITE {
-            return white_v - black_v;
+    /// white - black
+    pub fn mg_total(&self) -> i32 {
+        self.mg[WHITE as usize] - self.mg[BLACK as usize]
+    }
+
+    /// white - black
+    pub fn eg_total(&self) -> i32 {
+        self.eg[WHITE as usize] - self.eg[BLACK as usize]
+    }
+}
+
+/// Result of Evaluator::trace, printed as a table by the UCI eval command
+pub struct EvalTrace {
+    pub terms: Vec<EvalTerm>,
+    pub mg: i32,
+    pub eg: i32,
+    pub scale: i32, //applied to eg before the phase blend
+    pub late_game_phase: usize,
+    pub endgame: Option<(EndgameKind, u32)>, //recognised endgame and its strong side, overrides the blend
+    pub score: i16,
+}
+
+impl std::fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const SEPARATOR: &str = "-------------+-------------+-------------+-------------";
+
+        writeln!(f, "        Term |    White    |    Black    |    Total")?;
+        writeln!(f, "             |   MG    EG  |   MG    EG  |   MG    EG")?;
+        writeln!(f, "{SEPARATOR}")?;
+        for term in self.terms.iter() {
+            writeln!(
+                f,
+                "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                term.name,
+                term.mg[WHITE as usize],
+                term.eg[WHITE as usize],
+                term.mg[BLACK as usize],
+                term.eg[BLACK as usize],
+                term.mg_total(),
+                term.eg_total()
+            )?;
+        }
+        writeln!(f, "{SEPARATOR}")?;
+        writeln!(f, "{:>12} |             |             | {:>5} {:>5}", "Total", self.mg, self.eg)?;
+        writeln!(f)?;
+        writeln!(f, "Late game phase: {}/{}", self.late_game_phase, MAX_LATE_GAME_PHASE)?;
+        writeln!(f, "Endgame scale: {}/{}", self.scale, SCALE_NORMAL)?;
+        if let Some((kind, strong_side)) = self.endgame {
+            let side: &str = if strong_side == WHITE { "white" } else { "black" };
+            writeln!(f, "Recognised endgame: {kind:?} for {side}, overrides the blend")?;
+        }
+        write!(f, "Final evaluation: {} (white side)", self.score)
+    }
+}
+
+/// Integer blend of early and late game scores, late_game_phase ranges from [0, MAX_LATE_GAME_PHASE]
+#[inline]
+pub fn taper(mg: i32, eg: i32, late_game_phase: usize) -> i16 {
+    let late: i32 = late_game_phase as i32;
+    let early: i32 = MAX_LATE_GAME_PHASE as i32 - late;
+    ((mg * early + eg * late) / MAX_LATE_GAME_PHASE as i32) as i16
+}
+
+/// Scale in [0, SCALE_NORMAL] pulling the late game score towards zero in drawish material configurations <br>
+/// **strong_side** is the side the unscaled late game score favours
+pub fn endgame_scale(pieces: &[u64; 12], strong_side: u32) -> i32 {
+    let (strong, weak): (usize, usize) = if strong_side == WHITE {
+        (W_PAWN_U, B_PAWN_U)
+    } else {
+        (B_PAWN_U, W_PAWN_U)
+    };
+    let non_pawn_material = |offset: usize| -> i32 {
+        (W_KNIGHT_U..=W_QUEEN_U)
+            .map(|p| pieces[offset + p].count_ones() as i32 * PIECE_MATERIAL_VALUE[p] as i32)
+            .sum()
+    };
+    let strong_npm: i32 = non_pawn_material(strong);
+    let weak_npm: i32 = non_pawn_material(weak);
+    let bishop_value: i32 = PIECE_MATERIAL_VALUE[W_BISHOP_U] as i32;
+
+    //without pawns being up a minor piece or less doesn't win, e.g. KBK, KRKN, KRBKR
+    if pieces[strong + W_PAWN_U] == 0 && strong_npm - weak_npm <= bishop_value {
+        if strong_npm < PIECE_MATERIAL_VALUE[W_ROOK_U] as i32 {
+            return SCALE_PAWNLESS_MINOR_UP;
+        } else if weak_npm <= bishop_value {
+            return SCALE_PAWNLESS_VS_MINOR;
         } else {
-            return black_v - white_v;
+            return SCALE_PAWNLESS_VS_MAJOR;
         }
     }
+
+    //a single bishop each on opposite colors with only pawns otherwise
+    let white_bishops: u64 = pieces[W_BISHOP_U];
+    let black_bishops: u64 = pieces[B_BISHOP_U];
+    if strong_npm == bishop_value
+        && weak_npm == bishop_value
+        && white_bishops.count_ones() == 1
+        && black_bishops.count_ones() == 1
+    {
+        let bishops: u64 = white_bishops | black_bishops;
+        if bishops & LIGHT_SQUARES != 0 && bishops & DARK_SQUARES != 0 {
+            return SCALE_OPPOSITE_BISHOPS;
+        }
+    }
+
+    SCALE_NORMAL
 }
@@ -1,7 +1,12 @@
+pub mod endgame;
 pub mod eval;
+pub mod move_picker;
+pub mod nnue;
 pub mod search_config;
 pub mod search_data;
 pub mod searcher;
 pub mod table_loader;
+pub mod tablebase;
 pub mod see;
+pub mod syzygy;
 pub mod tt;
\ No newline at end of file
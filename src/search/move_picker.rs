@@ -0,0 +1,265 @@
+/*
+ * Staged move ordering for the search. Moves are handed out one at a time and each stage
+ * is generated only once the previous ones are exhausted, so a cutoff by the TT move skips move generation entirely.
+ *
+ * Order: priority (TT and previous PV) moves, good captures and promotions, killers, quiets by history, bad captures.
+ * Quiescence skips killers and quiets, its first ply may search quiet checks instead.
+ */
+
+use crate::{
+    repr::{
+        _move::{self, NULL_MOVE},
+        move_gen::{GenType, MoveGen},
+        position::Position,
+    },
+    search::{eval::PIECE_MATERIAL_VALUE, search_data::SearchData},
+};
+
+const PROMOTION_SCORE: i32 = 1_000;
+const EATING_MULTIPLIER: i32 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Priority,
+    GenerateEvasions,
+    GenerateNoisy,
+    GoodNoisy,
+    Killers,
+    GenerateQuiets,
+    GenerateQuietChecks,
+    Quiets,
+    BadNoisy,
+    Done,
+}
+
+///Picks the moves of the position's last ply. <br>
+///Noisy moves come first in the ply's range of move_arr, bad captures are collected to the end of them and quiets follow
+pub struct MovePicker {
+    stage: Stage,
+    priority: [u32; 2],
+    killers: [u32; 2],
+    special_idx: usize, //next priority or killer slot
+    specials: [u32; 4], //priority moves and killers already handed out, skipped when generated
+    nof_specials: usize,
+    noisy_only: bool,
+    quiet_checks: bool,
+    pregenerated: bool,
+    noisy_cur: usize,
+    bad_s: usize,
+    bad_cur: usize,
+    quiets_s: usize,
+    quiet_cur: usize,
+    quiets_e: usize,
+}
+
+impl MovePicker {
+    ///**priority** moves are searched first as given, the caller makes sure they are legal. <br>
+    ///**killers** are checked for legality here. <br>
+    ///If **noisy_only**, killers and quiets are skipped (quiescence when not in check), apart from quiet checks if **quiet_checks**. <br>
+    ///Moves already generated for the ply (root) are ordered in place instead of generating them again,
+    ///in check all evasions are generated at once and ordered the same way
+    pub fn new(pos: &mut Position, priority: [u32; 2], killers: [u32; 2], noisy_only: bool, quiet_checks: bool) -> Self {
+        let (s, e) = pos.search_move_bounds();
+        let mut picker: MovePicker = MovePicker {
+            stage: Stage::Priority,
+            priority,
+            killers,
+            special_idx: 0,
+            specials: [NULL_MOVE; 4],
+            nof_specials: 0,
+            noisy_only,
+            quiet_checks,
+            pregenerated: s != e,
+            noisy_cur: s,
+            bad_s: s,
+            bad_cur: s,
+            quiets_s: s,
+            quiet_cur: s,
+            quiets_e: s,
+        };
+        if picker.pregenerated {
+            picker.partition(pos, s, e);
+        }
+        picker
+    }
+
+    ///Next move to search, None when all moves have been picked
+    pub fn next(&mut self, pos: &mut Position, move_gen: &MoveGen, search_data: &mut SearchData) -> Option<u32> {
+        loop {
+            match self.stage {
+                Stage::Priority => {
+                    while self.special_idx < self.priority.len() {
+                        let mov: u32 = self.priority[self.special_idx];
+                        self.special_idx += 1;
+                        if mov != NULL_MOVE && !self.is_special(mov) {
+                            self.add_special(mov);
+                            return Some(mov);
+                        }
+                    }
+                    self.special_idx = 0;
+                    self.stage = if self.pregenerated {
+                        Stage::GoodNoisy
+                    } else if pos.board.nof_checkers > 0 {
+                        Stage::GenerateEvasions
+                    } else {
+                        Stage::GenerateNoisy
+                    };
+                }
+                Stage::GenerateEvasions => {
+                    let (s, e) = pos.generate_moves(GenType::Evasions, true, false, move_gen);
+                    self.partition(pos, s, e);
+                    self.pregenerated = true;
+                    self.stage = Stage::GoodNoisy;
+                }
+                Stage::GenerateNoisy => {
+                    let (s, e) = pos.generate_moves(GenType::Noisy, true, false, move_gen);
+                    self.set_noisy_bounds(s, e);
+                    self.set_quiet_bounds(e, e);
+                    self.stage = Stage::GoodNoisy;
+                }
+                Stage::GoodNoisy => {
+                    while self.noisy_cur < self.bad_s {
+                        let best_i: usize = (self.noisy_cur..self.bad_s)
+                            .max_by_key(|i| noisy_score(pos.move_arr[*i]))
+                            .expect("noisy range was empty");
+                        pos.move_arr.swap(best_i, self.noisy_cur);
+                        let mov: u32 = pos.move_arr[self.noisy_cur];
+                        if self.is_special(mov) {
+                            self.noisy_cur += 1;
+                        } else if _move::is_eating(mov)
+                            && !search_data.see_helper.see_ge(mov, 0, &pos.board, move_gen)
+                        {
+                            self.bad_s -= 1;
+                            pos.move_arr.swap(self.noisy_cur, self.bad_s);
+                        } else {
+                            self.noisy_cur += 1;
+                            return Some(mov);
+                        }
+                    }
+                    self.stage = if !self.noisy_only {
+                        Stage::Killers
+                    } else if self.quiet_checks {
+                        Stage::GenerateQuietChecks
+                    } else {
+                        Stage::BadNoisy
+                    };
+                }
+                Stage::Killers => {
+                    while self.special_idx < self.killers.len() {
+                        let mov: u32 = self.killers[self.special_idx];
+                        self.special_idx += 1;
+                        if mov != NULL_MOVE
+                            && !_move::is_noisy(mov)
+                            && !self.is_special(mov)
+                            && move_gen.is_pseudolegal(&pos.board, mov)
+                            && move_gen.pseudolegal_is_legal(mov, &pos.board)
+                        {
+                            self.add_special(mov);
+                            return Some(mov);
+                        }
+                    }
+                    self.stage = if self.pregenerated { Stage::Quiets } else { Stage::GenerateQuiets };
+                }
+                Stage::GenerateQuiets => {
+                    let (s, e) = pos.generate_moves(GenType::Quiet, true, false, move_gen);
+                    self.set_quiet_bounds(s, e);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::GenerateQuietChecks => {
+                    let (s, e) = pos.generate_moves(GenType::QuietChecks, true, false, move_gen);
+                    self.set_quiet_bounds(s, e);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    while self.quiet_cur < self.quiets_e {
+                        let best_i: usize = (self.quiet_cur..self.quiets_e)
+                            .max_by_key(|i| search_data.get_history_entry(pos.board.turn, pos.move_arr[*i]))
+                            .expect("quiet range was empty");
+                        pos.move_arr.swap(best_i, self.quiet_cur);
+                        let mov: u32 = pos.move_arr[self.quiet_cur];
+                        self.quiet_cur += 1;
+                        if !self.is_special(mov) {
+                            return Some(mov);
+                        }
+                    }
+                    self.stage = Stage::BadNoisy;
+                }
+                Stage::BadNoisy => {
+                    //collected from the back in picking order, so walk them backwards
+                    while self.bad_cur > self.bad_s {
+                        self.bad_cur -= 1;
+                        let mov: u32 = pos.move_arr[self.bad_cur];
+                        if !self.is_special(mov) {
+                            return Some(mov);
+                        }
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    ///Quiet moves handed out so far, for the history penalty after a cutoff
+    pub fn searched_quiets<'a>(&'a self, move_arr: &'a [u32]) -> impl Iterator<Item = u32> + 'a {
+        self.specials[..self.nof_specials]
+            .iter()
+            .copied()
+            .chain(
+                move_arr[self.quiets_s..self.quiet_cur]
+                    .iter()
+                    .copied()
+                    .filter(|mov| !self.is_special(*mov)),
+            )
+            .filter(|mov| !_move::is_noisy(*mov))
+    }
+
+    ///Orders already generated moves in **s**..**e** into noisy moves followed by quiets
+    fn partition(&mut self, pos: &mut Position, s: usize, e: usize) {
+        let mut noisy_e: usize = s;
+        for i in s..e {
+            if _move::is_noisy(pos.move_arr[i]) {
+                pos.move_arr.swap(i, noisy_e);
+                noisy_e += 1;
+            }
+        }
+        self.set_noisy_bounds(s, noisy_e);
+        self.set_quiet_bounds(noisy_e, if self.noisy_only { noisy_e } else { e });
+    }
+
+    fn set_noisy_bounds(&mut self, s: usize, e: usize) {
+        self.noisy_cur = s;
+        self.bad_s = e;
+        self.bad_cur = e;
+    }
+
+    fn set_quiet_bounds(&mut self, s: usize, e: usize) {
+        self.quiets_s = s;
+        self.quiet_cur = s;
+        self.quiets_e = e;
+    }
+
+    #[inline]
+    fn is_special(&self, mov: u32) -> bool {
+        self.specials[..self.nof_specials].contains(&mov)
+    }
+
+    #[inline]
+    fn add_special(&mut self, mov: u32) {
+        self.specials[self.nof_specials] = mov;
+        self.nof_specials += 1;
+    }
+}
+
+///Most valuable victim first, promotions by the promoted piece
+#[inline]
+fn noisy_score(mov: u32) -> i32 {
+    let mut score: i32 = 0;
+    if let Some(eaten) = _move::eaten_piece(mov) {
+        score += EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[eaten as usize] as i32;
+    }
+    if _move::is_promotion(mov) {
+        score += PROMOTION_SCORE + _move::get_promotion_piece(mov) as i32;
+    }
+    score
+}
@@ -0,0 +1,342 @@
+/* Efficiently updatable neural network evaluation
+ *
+ * Architecture: (768 -> N) x 2 -> 1 with a clipped ReLU activation. Both perspectives share the
+ * feature transformer, the side to move accumulator is fed to the first N output weights.
+ *
+ * Features are relative to the perspective: (color * 6 + piece kind) * 64 + square,
+ * where color 0 is the perspective's own side and black's squares are mirrored with sqr ^ 56.
+ *
+ * Weight file format, all little endian:
+ *   magic             4 bytes   "RNUE"
+ *   version           u32       NNUE_VERSION
+ *   hidden size N     u32
+ *   feature weights   i16 x 768 * N   weights of feature f are at [f * N, f * N + N)
+ *   feature biases    i16 x N
+ *   output weights    i16 x 2 * N     side to move accumulator first, then the other side
+ *   output bias       i32
+ *
+ * Accumulators are quantized by NNUE_QA and output weights by NNUE_QB,
+ * eval = (output bias + sum(clamp(acc, 0, QA) * w)) * NNUE_SCALE / (QA * QB)
+ */
+
+use std::sync::Arc;
+
+use crate::{
+    repr::{bitboard, types::*},
+    search::eval::MATE_BOUND,
+};
+
+pub const NNUE_INPUTS: usize = 768;
+pub const NNUE_QA: i32 = 255;
+pub const NNUE_QB: i32 = 64;
+pub const NNUE_SCALE: i32 = 400;
+pub const NNUE_MAGIC: [u8; 4] = *b"RNUE";
+pub const NNUE_VERSION: u32 = 1;
+
+//small material only network, enough for tests and as a starting point
+const BUNDLED_NETWORK: &[u8] = include_bytes!("../../assets/nnue/tiny.nnue");
+
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    pub fn bundled() -> Self {
+        Self::from_bytes(BUNDLED_NETWORK).expect("bundled network must be valid")
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        let bytes: Vec<u8> = std::fs::read(path).map_err(|_| "Could not read network file")?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 12 || bytes[0..4] != NNUE_MAGIC {
+            return Err("Not a network file");
+        }
+        let version: u32 = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if version != NNUE_VERSION {
+            return Err("Unsupported network version");
+        }
+        let hidden: usize = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+        let nof_weights: usize = NNUE_INPUTS * hidden + hidden + 2 * hidden;
+        if hidden == 0 || bytes.len() != 12 + 2 * nof_weights + 4 {
+            return Err("Network file has wrong size");
+        }
+
+        let mut values = bytes[12..12 + 2 * nof_weights]
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]));
+        let feature_weights: Vec<i16> = values.by_ref().take(NNUE_INPUTS * hidden).collect();
+        let feature_bias: Vec<i16> = values.by_ref().take(hidden).collect();
+        let output_weights: Vec<i16> = values.collect();
+        let output_bias: i32 = i32::from_le_bytes(bytes[bytes.len() - 4..].try_into().unwrap());
+
+        Ok(Self {
+            hidden,
+            feature_weights,
+            feature_bias,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = NNUE_MAGIC.to_vec();
+        bytes.extend(NNUE_VERSION.to_le_bytes());
+        bytes.extend((self.hidden as u32).to_le_bytes());
+        for v in self
+            .feature_weights
+            .iter()
+            .chain(self.feature_bias.iter())
+            .chain(self.output_weights.iter())
+        {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend(self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    /// Full evaluation from scratch, negamax compliant. Search uses NnueState instead.
+    pub fn evaluate(&self, pieces: &[u64; 12], mover: u32) -> i16 {
+        let mut acc: Vec<i16> = vec![0; 2 * self.hidden];
+        self.refresh_into(pieces, &mut acc);
+        let (white, black) = acc.split_at(self.hidden);
+        if mover == WHITE {
+            self.output(white, black)
+        } else {
+            self.output(black, white)
+        }
+    }
+
+    ///acc holds white perspective then black perspective
+    fn refresh_into(&self, pieces: &[u64; 12], acc: &mut [i16]) {
+        let (white, black) = acc.split_at_mut(self.hidden);
+        white.copy_from_slice(&self.feature_bias);
+        black.copy_from_slice(&self.feature_bias);
+        for (p, piece_bb) in pieces.iter().enumerate() {
+            let mut p_bb: u64 = *piece_bb;
+            while p_bb > 0 {
+                let sqr: u32 = bitboard::pop_lsb(&mut p_bb);
+                simd::add_assign(white, self.feature_row(feature_index(WHITE, p, sqr)));
+                simd::add_assign(black, self.feature_row(feature_index(BLACK, p, sqr)));
+            }
+        }
+    }
+
+    #[inline]
+    fn feature_row(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    fn output(&self, us: &[i16], them: &[i16]) -> i16 {
+        let (us_weights, them_weights) = self.output_weights.split_at(self.hidden);
+        let sum: i32 =
+            self.output_bias + simd::crelu_dot(us, us_weights) + simd::crelu_dot(them, them_weights);
+        let bound: i32 = MATE_BOUND as i32 - 1;
+        (sum * NNUE_SCALE / (NNUE_QA * NNUE_QB)).clamp(-bound, bound) as i16
+    }
+}
+
+/// Feature of piece idx (W_PAWN_U..=B_KING_U) on sqr, seen from perspective
+#[inline]
+pub fn feature_index(perspective: u32, piece: usize, sqr: u32) -> usize {
+    if perspective == WHITE {
+        piece * 64 + sqr as usize
+    } else {
+        ((piece + NOF_PIECE_TYPES_U) % 12) * 64 + (sqr ^ 56) as usize
+    }
+}
+
+/// Incrementally updated accumulators, one per ply, kept in sync by Position::make_move and unmake_move
+#[derive(Clone)]
+pub struct NnueState {
+    network: Arc<Network>,
+    accumulators: Vec<i16>, //per ply: white perspective then black perspective
+    ply: usize,
+}
+
+impl NnueState {
+    pub fn new(network: Arc<Network>, pieces: &[u64; 12]) -> Self {
+        let mut state = Self {
+            accumulators: vec![0; 2 * network.hidden],
+            network,
+            ply: 0,
+        };
+        state.refresh(pieces);
+        state
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    /// Rebuilds the accumulator from scratch as the new root
+    pub fn refresh(&mut self, pieces: &[u64; 12]) {
+        self.ply = 0;
+        let width: usize = 2 * self.network.hidden;
+        self.network.refresh_into(pieces, &mut self.accumulators[0..width]);
+    }
+
+    /// Next ply's accumulator from the pieces that changed
+    pub fn push(&mut self, before: &[u64; 12], after: &[u64; 12]) {
+        let hidden: usize = self.network.hidden;
+        let width: usize = 2 * hidden;
+        let s: usize = self.ply * width;
+        if self.accumulators.len() < s + 2 * width {
+            self.accumulators.resize(s + 2 * width, 0);
+        }
+        self.accumulators.copy_within(s..s + width, s + width);
+        self.ply += 1;
+
+        let (white, black) = self.accumulators[s + width..s + 2 * width].split_at_mut(hidden);
+        for p in 0..12 {
+            let mut removed: u64 = before[p] & !after[p];
+            let mut added: u64 = after[p] & !before[p];
+            while removed > 0 {
+                let sqr: u32 = bitboard::pop_lsb(&mut removed);
+                simd::sub_assign(white, self.network.feature_row(feature_index(WHITE, p, sqr)));
+                simd::sub_assign(black, self.network.feature_row(feature_index(BLACK, p, sqr)));
+            }
+            while added > 0 {
+                let sqr: u32 = bitboard::pop_lsb(&mut added);
+                simd::add_assign(white, self.network.feature_row(feature_index(WHITE, p, sqr)));
+                simd::add_assign(black, self.network.feature_row(feature_index(BLACK, p, sqr)));
+            }
+        }
+    }
+
+    /// Back to previous ply, **pieces** are the restored pieces in case the root itself was unmade
+    pub fn pop(&mut self, pieces: &[u64; 12]) {
+        if self.ply == 0 {
+            self.refresh(pieces);
+        } else {
+            self.ply -= 1;
+        }
+    }
+
+    /// Negamax compliant like Evaluator::eval
+    pub fn eval(&self, mover: u32) -> i16 {
+        let hidden: usize = self.network.hidden;
+        let s: usize = self.ply * 2 * hidden;
+        let (white, black) = self.accumulators[s..s + 2 * hidden].split_at(hidden);
+        if mover == WHITE {
+            self.network.output(white, black)
+        } else {
+            self.network.output(black, white)
+        }
+    }
+}
+
+/// Vector kernels, AVX2 when the cpu supports it and plain loops the compiler can vectorize otherwise
+pub mod simd {
+    use super::NNUE_QA;
+
+    pub fn add_assign(acc: &mut [i16], weights: &[i16]) {
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            //SAFETY: avx2 support was just checked
+            return unsafe { avx2::add_assign(acc, weights) };
+        }
+        scalar::add_assign(acc, weights)
+    }
+
+    pub fn sub_assign(acc: &mut [i16], weights: &[i16]) {
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            //SAFETY: avx2 support was just checked
+            return unsafe { avx2::sub_assign(acc, weights) };
+        }
+        scalar::sub_assign(acc, weights)
+    }
+
+    /// sum(clamp(acc, 0, QA) * weights)
+    pub fn crelu_dot(acc: &[i16], weights: &[i16]) -> i32 {
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            //SAFETY: avx2 support was just checked
+            return unsafe { avx2::crelu_dot(acc, weights) };
+        }
+        scalar::crelu_dot(acc, weights)
+    }
+
+    pub mod scalar {
+        use super::NNUE_QA;
+
+        pub fn add_assign(acc: &mut [i16], weights: &[i16]) {
+            for (a, w) in acc.iter_mut().zip(weights) {
+                *a = a.wrapping_add(*w);
+            }
+        }
+
+        pub fn sub_assign(acc: &mut [i16], weights: &[i16]) {
+            for (a, w) in acc.iter_mut().zip(weights) {
+                *a = a.wrapping_sub(*w);
+            }
+        }
+
+        pub fn crelu_dot(acc: &[i16], weights: &[i16]) -> i32 {
+            acc.iter()
+                .zip(weights)
+                .map(|(a, w)| (*a as i32).clamp(0, NNUE_QA) * *w as i32)
+                .sum()
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    mod avx2 {
+        use std::arch::x86_64::*;
+
+        use super::{scalar, NNUE_QA};
+
+        const LANES: usize = 16;
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn add_assign(acc: &mut [i16], weights: &[i16]) {
+            let n: usize = acc.len().min(weights.len()) / LANES * LANES;
+            for i in (0..n).step_by(LANES) {
+                let a = _mm256_loadu_si256(acc.as_ptr().add(i) as *const __m256i);
+                let w = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                _mm256_storeu_si256(acc.as_mut_ptr().add(i) as *mut __m256i, _mm256_add_epi16(a, w));
+            }
+            scalar::add_assign(&mut acc[n..], &weights[n..]);
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn sub_assign(acc: &mut [i16], weights: &[i16]) {
+            let n: usize = acc.len().min(weights.len()) / LANES * LANES;
+            for i in (0..n).step_by(LANES) {
+                let a = _mm256_loadu_si256(acc.as_ptr().add(i) as *const __m256i);
+                let w = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                _mm256_storeu_si256(acc.as_mut_ptr().add(i) as *mut __m256i, _mm256_sub_epi16(a, w));
+            }
+            scalar::sub_assign(&mut acc[n..], &weights[n..]);
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn crelu_dot(acc: &[i16], weights: &[i16]) -> i32 {
+            let n: usize = acc.len().min(weights.len()) / LANES * LANES;
+            let zero = _mm256_setzero_si256();
+            let qa = _mm256_set1_epi16(NNUE_QA as i16);
+            let mut sum = _mm256_setzero_si256();
+            for i in (0..n).step_by(LANES) {
+                let a = _mm256_loadu_si256(acc.as_ptr().add(i) as *const __m256i);
+                let w = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                let clipped = _mm256_min_epi16(_mm256_max_epi16(a, zero), qa);
+                //pairwise i16 products summed into i32 lanes
+                sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+            }
+            let mut lanes: [i32; 8] = [0; 8];
+            _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum);
+            lanes.iter().sum::<i32>() + scalar::crelu_dot(&acc[n..], &weights[n..])
+        }
+    }
+}
@@ -2,13 +2,18 @@ const DEFAULT_STATIC_TIME: u64 = 5000;
 
 const SEARCH_RESPONSE_MARGIN: u64 = 50; //ms
 
+pub const DEFAULT_TB_PROBE_DEPTH: usize = 1;
+
 ///Config defining HOW we search. <br>
 ///Might be mutated in between searches to dynamically adjust behaviour.
+#[derive(Clone)]
 pub struct SearchConfig {
     pub search_mode: SearchMode,
     pub quiescence: bool,
     pub log_diagnostics: bool,
     pub log_uci_diagnostics: bool,
+    pub eval_kind: EvalKind,
+    pub tb_probe_depth: usize, //remaining depth a node needs for tablebase probes
 }
 
 impl Default for SearchConfig {
@@ -18,6 +23,8 @@ impl Default for SearchConfig {
             quiescence: true,
             log_diagnostics: false,
             log_uci_diagnostics: true,
+            eval_kind: EvalKind::Handcrafted,
+            tb_probe_depth: DEFAULT_TB_PROBE_DEPTH,
         }
     }
 }
@@ -30,11 +37,21 @@ impl SearchConfig {
             quiescence: true,
             log_diagnostics: false,
             log_uci_diagnostics: true,
+            eval_kind: EvalKind::Handcrafted,
+            tb_probe_depth: DEFAULT_TB_PROBE_DEPTH,
         }
     }
 
 }
 
+///Which static evaluation the search uses at leaves
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EvalKind {
+    Handcrafted,
+    Nnue, //the searcher's network, the bundled one if none was loaded
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum SearchMode {
     StaticDepth(usize),
     StaticTime(u64), //ms
@@ -55,7 +72,7 @@ impl SearchMode {
     ) -> Self {
         let time_left = if is_white_turn { wtime } else { btime };
         let inc = if is_white_turn { winc } else { binc };
-        let time_per_move = (time_left / 30).saturating_add(inc);
+        let time_per_move = (time_left / 30).saturating_add(inc).min(time_left); //the increment only comes after the move
         return Self::StaticTime(time_per_move.saturating_sub(SEARCH_RESPONSE_MARGIN));
     }
 }
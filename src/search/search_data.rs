@@ -17,14 +17,30 @@ pub struct SearchData {
     pub mate_in: Option<u32>,
     pub board_hash_history: Vec<u64>, //only relevant, i.e. since last non-reversible move
     pub history_table: [i16; 2 * 64 * 64], //history_table[side * 4096 + from_sq * 64 + to_sq]
+    pub killers: [[u32; 2]; MAX_SEARCH_DEPTH], //last two quiet moves that caused a cutoff, by ply
     pub see_helper: SeeWorker,
     //per search data
     pub positions_searched: u64,
     pub stand_pat_cutoffs: u64,
     pub ab_cutoffs: u64,
+    pub tb_hits: u64,
     pub sel_depth: usize,
     //cumulative data
     pub cumul_positions_searched: u64,
+    //completed iterations of the last search
+    pub iterations: Vec<IterationInfo>,
+}
+
+///Result of one completed iterative deepening depth
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterationInfo {
+    pub depth: usize,
+    pub sel_depth: usize,
+    pub eval: i16, //from the side to move's point of view
+    pub best_move: u32,
+    pub pv: Vec<u32>,
+    pub nodes: u64,
+    pub time_ms: u64, //since the search started
 }
 
 impl SearchData {
@@ -37,12 +53,15 @@ impl SearchData {
             mate_in: None,
             board_hash_history: board_hash_history,
             history_table: [0; 2 * 64 * 64],
+            killers: [[NULL_MOVE; 2]; MAX_SEARCH_DEPTH],
             see_helper: SeeWorker::default(),
             positions_searched: 0,
             stand_pat_cutoffs: 0,
             ab_cutoffs: 0,
+            tb_hits: 0,
             sel_depth: 0,
             cumul_positions_searched: 0,
+            iterations: Vec::new(),
         };
     }
 
@@ -74,6 +93,7 @@ impl SearchData {
     pub fn reset_temp_performance_data(&mut self) {
         self.positions_searched = 0;
         self.ab_cutoffs = 0;
+        self.tb_hits = 0;
         self.sel_depth = 0;
         self.stand_pat_cutoffs = 0;
     }
@@ -89,6 +109,19 @@ impl SearchData {
         }
     }
 
+    ///Plies are relative to the root, so killers don't carry over to a search from another root
+    pub(crate) fn clear_killers(&mut self) {
+        self.killers = [[NULL_MOVE; 2]; MAX_SEARCH_DEPTH];
+    }
+
+    #[inline]
+    pub fn store_killer(&mut self, ply: usize, mov: u32) {
+        if self.killers[ply][0] != mov {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = mov;
+        }
+    }
+
     #[inline]
     pub fn update_history_entry(&mut self, side: u32, from: u32, to: u32, bonus: i32) {
         let idx: usize = (side * 4096 + from * 64 + to) as usize;
@@ -2,9 +2,9 @@ use std::{cmp::{max, min}, sync::{Arc, atomic::{AtomicBool, Ordering::Relaxed}},
 
 use crate::{
     repr::{
-        _move::{self, *}, board::Board, move_gen::MoveGen, position::Position,
+        _move::{self, *}, board::Board, move_gen::{GenType, MoveGen}, position::Position,
     }, search::{
-        eval::{Evaluator, MATE_EVAL, PIECE_MATERIAL_VALUE}, search_config::*, search_data::{SearchData, get_triang_pv_ply_idx_table}, tt::{TTEntry, TTEntryType, TranspositionTable},
+        endgame, eval::{Evaluator, MATE_EVAL}, move_picker::MovePicker, nnue::Network, search_config::*, search_data::{IterationInfo, SearchData, get_triang_pv_ply_idx_table}, tablebase::{self, Tablebase}, tt::{TTEntry, TTEntryType, TranspositionTable},
     }, utils::zobrist::Zobrist,
 };
 
@@ -16,21 +16,27 @@ const BETA_INIT: i16 = i16::MAX;
 const EVAL_INIT: i16 = -i16::MAX;
 const EVAL_QUIT: i16 = 31111;
 
-const PROMOTION_SCORE: i32 = 1_000;
-const EATING_MULTIPLIER: i32 = 7;
-const NON_CAPTURE_BONUS: i32 = 10_000;
-const GOOD_CAPTURE_BONUS: i32 = 100_000;
+pub type IterationCallback = Box<dyn FnMut(&IterationInfo) + Send>;
 
 struct SearchControl<'a> {
     time_limit: Option<(Instant, u64)>,
     kill_switch: Option<&'a AtomicBool>,
+    tablebase: Option<&'a dyn Tablebase>,
+    tb_probe_depth: usize,
 }
 
 impl<'a> SearchControl<'a> {
-    fn new(target_time: Option<u64>, kill_switch: Option<&'a AtomicBool>) -> Self {
+    fn new(
+        target_time: Option<u64>,
+        kill_switch: Option<&'a AtomicBool>,
+        tablebase: Option<&'a dyn Tablebase>,
+        tb_probe_depth: usize,
+    ) -> Self {
         Self {
             time_limit: target_time.map(|target_time| (Instant::now(), target_time)),
             kill_switch,
+            tablebase,
+            tb_probe_depth,
         }
     }
 
@@ -55,7 +61,10 @@ pub struct Searcher {
     pub multithreaded: bool,
     pub search_config: SearchConfig,
     pub evaluator: Evaluator,
+    pub network: Option<Arc<Network>>, //used with EvalKind::Nnue, bundled network is loaded if None
     pub tt: TranspositionTable,
+    pub tablebase: Option<Arc<dyn Tablebase>>, //probed at the root and after zeroing moves
+    pub on_iteration: Option<IterationCallback>, //called after every completed depth, e.g. to show a running search
     last_sync_deviates_from_pv: bool,
 }
 
@@ -132,7 +141,10 @@ impl Searcher {
             multithreaded: multithreaded,
             search_config,
             evaluator: Evaluator::default(),
+            network: None,
             tt: TranspositionTable::default(),
+            tablebase: None,
+            on_iteration: None,
             last_sync_deviates_from_pv: true,
         };
     }
@@ -152,6 +164,19 @@ impl Searcher {
 
     fn start_search_node(&mut self, idx: usize, move_gen: &MoveGen, zobrist: &Zobrist, kill_switch: Option<&AtomicBool>) {
         self.search_data[idx].age_history();
+        self.search_data[idx].clear_killers();
+        let network: Option<Arc<Network>> = match self.search_config.eval_kind {
+            EvalKind::Handcrafted => None,
+            EvalKind::Nnue => Some(
+                self.network
+                    .get_or_insert_with(|| Arc::new(Network::bundled()))
+                    .clone(),
+            ),
+        };
+        self.positions[idx].set_network(network);
+        if let Some(tablebase) = &self.tablebase {
+            tablebase::filter_root_moves(tablebase.as_ref(), &mut self.positions[idx], move_gen, zobrist);
+        }
         let (target_depth, target_time) = match self.search_config.search_mode {
             SearchMode::StaticDepth(d) => {
                 assert!(
@@ -182,7 +207,7 @@ impl Searcher {
         zobrist: &Zobrist,
         kill_switch: Option<&AtomicBool>,
     ) {
-        let control = SearchControl::new(target_time, kill_switch);
+        let control = SearchControl::new(target_time, kill_switch, self.tablebase.as_deref(), self.search_config.tb_probe_depth);
 
         fn inner(
             d: usize,
@@ -215,8 +240,8 @@ impl Searcher {
 
             let mut eval: i16 = EVAL_INIT;
             let is_three_fold: bool = search_data.in_three_fold(pos);
-            let (s, e) = pos.search_move_bounds();
-            
+            let noisy_only: bool = in_quiescence && pos.board.nof_checkers == 0;
+
             let tte: Option<TTEntry> = tt.probe(pos.board.zhash).map(|entry| {
                 TTEntry {
                     score: TranspositionTable::score_from_tt(entry.score, d as i16),
@@ -224,7 +249,11 @@ impl Searcher {
                 }
             });
             let key_collision: bool = tte.is_some_and(|entry| {
-                (entry.best_move == NULL_MOVE && entry.depth() > 0) || !pos.move_arr[s..e].contains(&entry.best_move) //first term for quiescence case where stand-pat is best and NULL_MOVE is stored
+                if entry.best_move == NULL_MOVE {
+                    entry.depth() > 0 //quiescence stores NULL_MOVE when stand-pat is best
+                } else {
+                    !is_searchable(entry.best_move, noisy_only, &pos.board, move_gen)
+                }
             });
             //TT cutoff?
             if let Some(tt_entry) = tte {
@@ -260,41 +289,39 @@ impl Searcher {
             
             let old_alpha: i16 = alpha;
             let old_beta: i16 = beta;
-            //terminal node?
-            if s == e {
-                if pos.board.nof_checkers > 0 {
-                    return -MATE_EVAL + d as i16; //sooner mate is better
-                } else if in_quiescence {
-                    return evaluator.eval(
-                        pos.board.pieces,
-                        pos.board.turn,
-                        pos.board.late_game_phase,
-                    ); //might miss stalemate but not worth it to check for performance reasons
-                } else {
-                    return 0; //stalemate
+            //terminal node? Moves are generated lazily, so mate and stalemate are mostly found after the move loop
+            if is_three_fold
+                || pos.board.is_fifty_move_draw()
+                //the root still needs a move to report
+                || (d > 0 && pos.board.is_insufficient_material())
+            {
+                if pos.board.nof_checkers > 0 && !has_legal_moves(pos, move_gen) {
+                    return -MATE_EVAL + d as i16; //mate takes precedence over the fifty move rule
                 }
-            } else if is_three_fold || pos.board.is_fifty_move_draw() {
                 return 0;
+            } else if let Some(wdl) = control.tablebase.filter(|tb| {
+                //tables assume the counter was just reset, the root is covered by filter_root_moves
+                d > 0
+                    && target_d.saturating_sub(d) >= control.tb_probe_depth
+                    && pos.board.half_move_clock == 0
+                    && tablebase::is_probeable(*tb, &pos.board)
+            }).and_then(|tb| tb.probe_wdl(&pos.board, move_gen)) {
+                search_data.tb_hits += 1;
+                return wdl.to_score(d);
             } else if d >= target_d {
                 if use_quiescence {
                     if pos.board.nof_checkers == 0 {
-                        eval = evaluator.eval(
-                            pos.board.pieces,
-                            pos.board.turn,
-                            pos.board.late_game_phase,
-                        );
+                        eval = static_eval(pos, evaluator);
                         if eval >= beta {
                             search_data.stand_pat_cutoffs += 1;
                             return eval;
                         }
                         alpha = max(alpha, eval);
                     }
+                } else if !has_legal_moves(pos, move_gen) {
+                    return if pos.board.nof_checkers > 0 { -MATE_EVAL + d as i16 } else { 0 };
                 } else {
-                    return evaluator.eval(
-                        pos.board.pieces,
-                        pos.board.turn,
-                        pos.board.late_game_phase,
-                    );
+                    return static_eval(pos, evaluator);
                 }
             }
 
@@ -303,9 +330,13 @@ impl Searcher {
             }
 
             let mut best_move: u32 = NULL_MOVE;
-            let mut only_bad_captures_left: Option<bool> = None;
 
-            let prev_pv_mv: u32 = if follows_prev_pv && d < prev_pv.len() { prev_pv[d] } else { NULL_MOVE };
+            let prev_pv_mv: u32 =
+                if follows_prev_pv && d < prev_pv.len() && is_searchable(prev_pv[d], noisy_only, &pos.board, move_gen) {
+                    prev_pv[d]
+                } else {
+                    NULL_MOVE
+                };
             let mut primary_selection: u32;
             let mut secondary_selection: u32;
             if tte.is_some() && !key_collision {
@@ -326,24 +357,26 @@ impl Searcher {
                 secondary_selection = NULL_MOVE;
             }
 
+            if d == 0 { //root moves may have been filtered by the tablebase
+                for selection in [&mut primary_selection, &mut secondary_selection] {
+                    if !pos.legal_moves().contains(selection) {
+                        *selection = NULL_MOVE;
+                    }
+                }
+            }
             if primary_selection == secondary_selection {
                 secondary_selection = NULL_MOVE;
             }
+            let killers: [u32; 2] = if d < target_d { search_data.killers[d] } else { [NULL_MOVE; 2] };
+            let quiet_checks: bool = noisy_only && d == target_d; //only the first quiescence ply, so the checks can't go on forever
+            let mut picker = MovePicker::new(pos, [primary_selection, secondary_selection], killers, noisy_only, quiet_checks);
+            let mut searched_any: bool = false;
             //TODO use low depth TT hit to order moves, maybe also give history bonus
-            //TODO i == s condition
-            for i in s..e {
-                let mov: u32 =
-                    Searcher::partial_selection_sort(&mut pos.move_arr[i..e], primary_selection, secondary_selection, &mut only_bad_captures_left, move_gen, search_data, &pos.board);
-
-                if mov == primary_selection {
-                    primary_selection = NULL_MOVE;
-                } else if mov == secondary_selection {
-                    secondary_selection = NULL_MOVE;
-                }
-
+            while let Some(mov) = picker.next(pos, move_gen, search_data) {
+                searched_any = true;
                 let child_follows_prev_pv = follows_prev_pv && mov == prev_pv_mv;
 
-                pos.make_move(mov, true, false, in_quiescence, move_gen, zobrist);
+                pos.make_move_lazy(mov, true, move_gen, zobrist);
                 search_data.board_hash_history.push(pos.board.zhash);
                 let child_eval: i16 = inner(
                     d + 1,
@@ -388,17 +421,29 @@ impl Searcher {
                 if alpha >= beta {
                     search_data.ab_cutoffs += 1;
                     if d < target_d {
+                        if !_move::is_noisy(mov) {
+                            search_data.store_killer(d, mov);
+                        }
                         Searcher::update_quiet_history_after_cutoff(
                             search_data,
                             pos.board.turn,
                             mov,
-                            &pos.move_arr[s..i],
+                            picker.searched_quiets(&pos.move_arr),
                             target_d - d,
                         );
                     }
                     break; //i.e. return alpha
                 }
             }
+            if !searched_any {
+                if pos.board.nof_checkers > 0 {
+                    return -MATE_EVAL + d as i16; //sooner mate is better
+                } else if noisy_only {
+                    return eval; //stand-pat, might miss stalemate but not worth it to check for performance reasons
+                } else {
+                    return 0; //stalemate
+                }
+            }
             //add TT entry
             let tte: TTEntry = TTEntry::new_packed(
                 pos.board.zhash,
@@ -424,6 +469,8 @@ impl Searcher {
         let log_uci_diagnostics = self.search_config.log_uci_diagnostics;
         let pos: &mut Position = &mut self.positions[idx];
         let search_data: &mut SearchData = &mut self.search_data[idx];
+        let started: Instant = Instant::now();
+        search_data.iterations.clear();
         for d in (synced_pv_depth + 1)..=target_depth {
             let mut prev_pv = vec![NULL_MOVE; d];
             prev_pv[..completed_pv_len]
@@ -459,11 +506,23 @@ impl Searcher {
                 .iter()
                 .position(|mov| *mov == NULL_MOVE)
                 .unwrap_or(d);
+            search_data.iterations.push(IterationInfo {
+                depth: d,
+                sel_depth: search_data.sel_depth,
+                eval,
+                best_move: search_data.pv[0],
+                pv: search_data.pv[..completed_pv_len].to_vec(),
+                nodes: search_data.positions_searched,
+                time_ms: started.elapsed().as_millis() as u64,
+            });
+            if let Some(on_iteration) = &mut self.on_iteration {
+                on_iteration(search_data.iterations.last().expect("Was just pushed"));
+            }
 
             if log_uci_diagnostics {
                 println!(
-                    "info depth {d} seldepth {} score cp {eval} nodes {} ab-cutoffs {} stand-pat-cutoffs {} pv {}", 
-                    search_data.sel_depth, search_data.positions_searched, search_data.ab_cutoffs, search_data.stand_pat_cutoffs, search_data.pv[0..completed_pv_len].iter().map(|m| _move::to_string(*m, true)).collect::<Vec<String>>().join(" ")
+                    "info depth {d} seldepth {} score cp {eval} nodes {} tbhits {} ab-cutoffs {} stand-pat-cutoffs {} pv {}", 
+                    search_data.sel_depth, search_data.positions_searched, search_data.tb_hits, search_data.ab_cutoffs, search_data.stand_pat_cutoffs, search_data.pv[0..completed_pv_len].iter().map(|m| _move::to_uci(*m, &pos.board)).collect::<Vec<String>>().join(" ")
                 );
             }
             search_data.reset_temp_performance_data();
@@ -520,10 +579,10 @@ impl Searcher {
         search_data: &mut SearchData,
         side: u32,
         cutoff_move: u32,
-        previously_searched_moves: &[u32],
+        searched_quiets: impl Iterator<Item = u32>,
         remaining_depth: usize,
     ) {
-        if _move::is_eating(cutoff_move) || _move::is_promotion(cutoff_move) {
+        if _move::is_noisy(cutoff_move) {
             return;
         }
 
@@ -535,104 +594,39 @@ impl Searcher {
             bonus,
         );
 
-        for &previous_move in previously_searched_moves {
-            if !_move::is_eating(previous_move) && !_move::is_promotion(previous_move) {
-                search_data.update_history_entry(
-                    side,
-                    _move::get_init(previous_move),
-                    _move::get_target(previous_move),
-                    -bonus,
-                );
-            }
+        for previous_move in searched_quiets.filter(|mov| *mov != cutoff_move) {
+            search_data.update_history_entry(
+                side,
+                _move::get_init(previous_move),
+                _move::get_target(previous_move),
+                -bonus,
+            );
         }
     }
 
-    ///k == 1, so "selection pick", in place <br>
-    ///primary selection and secondary selection are for possible prev pv move and tt move, order depending on tt move depth
-    fn partial_selection_sort(
-        move_arr_s: &mut [u32],
-        primary_selection: u32,
-        secondary_selection: u32,
-        only_bad_captures_left: &mut Option<bool>,
-        move_gen: &MoveGen,
-        search_data: &mut SearchData,
-        board: &Board
-    ) -> u32 {
-        let mut best_i: usize = usize::MAX;
-        if primary_selection != NULL_MOVE {
-            best_i = move_arr_s.iter().position(|m| *m == primary_selection).expect("primary selection was some but not generated");
-        } else if secondary_selection != NULL_MOVE {
-            best_i = move_arr_s.iter().position(|m| *m == secondary_selection).expect("secondary selection was some but not generated");
-        } else { //no priority move available
-            //non-dominating is non-captures if !*only_bad_captures_left, else if *only_bad_captures_left then only bad captures.
-            //If only_bad_captures_left == None, then non-dominating are both non-captures and bad captures
-            let mut best_v: i32 = i32::MIN;
-            let mut found_dominating: bool = false;
-            let mut non_capture_count: usize = 0;
-
-            for i in 0..move_arr_s.len() {
-                let mov: u32 = move_arr_s[i];
-                let mut cur_v: i32 = 0;
-                if _move::is_eating(mov) {
-                    if _move::is_negative_see(mov) {
-                        if let Some(only_bad_left) = only_bad_captures_left {
-                            if *only_bad_left {
-                                cur_v = EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                            } //else quiets left so ignore bad capture
-                        } else { //quiets may remain but we don't know yet
-                            cur_v = EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                        }
-                    } else if _move::is_positive_see(mov) {
-                        cur_v = GOOD_CAPTURE_BONUS + EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                        found_dominating = true;
-                    } else { //unevaluated capture
-                        let is_good_capture: bool =
-                            search_data.see_helper.see_positive(mov, board, move_gen);
-
-                        if is_good_capture {
-                            move_arr_s[i] = _move::with_positive_see(mov);
-                            cur_v = GOOD_CAPTURE_BONUS + EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                            found_dominating = true;
-                        } else {
-                            move_arr_s[i] = _move::with_negative_see(mov);
-                            if let Some(only_bad_left) = only_bad_captures_left {
-                                if *only_bad_left {
-                                    cur_v = EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                                } //else quiets left so ignore bad capture
-                            } else { //quiets may remain but we don't know yet
-                                cur_v = EATING_MULTIPLIER * PIECE_MATERIAL_VALUE[_move::eaten_piece(mov).unwrap() as usize] as i32;
-                            }
-                        }
-                    }
-                    if _move::is_promotion(mov) {
-                        cur_v += PROMOTION_SCORE + _move::get_promotion_piece(mov) as i32;
-                    }
-                } else if !found_dominating { //non-capture still candidate
-                    if _move::is_promotion(mov) {
-                        cur_v += PROMOTION_SCORE + _move::get_promotion_piece(mov) as i32;
-                    } else {
-                        cur_v += search_data.get_history_entry(board.turn, mov)
-                    }
-                    cur_v += NON_CAPTURE_BONUS; //give edge over bad captures
-                    non_capture_count += 1;
-                } else {
-                    non_capture_count += 1;
-                }
-
-                if cur_v > best_v {
-                    best_v = cur_v;
-                    best_i = i;
-                }
-            }
+}
 
-            if non_capture_count == 0 {
-                *only_bad_captures_left = Some(true);
-            }
-        }
+///TT and previous PV moves are validated without generating, which also catches TT key collisions
+#[inline]
+fn is_searchable(mov: u32, noisy_only: bool, board: &Board, move_gen: &MoveGen) -> bool {
+    (!noisy_only || _move::is_noisy(mov)) && move_gen.is_pseudolegal(board, mov) && move_gen.pseudolegal_is_legal(mov, board)
+}
 
-        move_arr_s.swap(0, best_i);
-        move_arr_s[0] = _move::with_see_cleared(move_arr_s[0]);
-        return move_arr_s[0];
+///Generates the node's moves if they weren't yet, to tell a mate or stalemate apart
+fn has_legal_moves(pos: &mut Position, move_gen: &MoveGen) -> bool {
+    let (s, e) = pos.search_move_bounds();
+    s != e || {
+        let (s, e) = pos.generate_moves(GenType::All, true, false, move_gen);
+        s != e
     }
+}
 
+///Network evaluation if one is attached to the position, recognised endgames still use their own knowledge
+#[inline]
+fn static_eval(pos: &Position, evaluator: &Evaluator) -> i16 {
+    match &pos.nnue {
+        Some(nnue) => endgame::probe(&pos.board.pieces, pos.board.turn)
+            .unwrap_or_else(|| nnue.eval(pos.board.turn)),
+        None => evaluator.eval(pos.board.pieces, pos.board.turn, pos.board.late_game_phase),
+    }
 }
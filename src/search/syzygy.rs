@@ -0,0 +1,908 @@
+/*
+ * Syzygy tablebases: probing the WDL (.rtbw) and DTZ (.rtbz) files of the common Syzygy format.
+ *
+ * A file holds one material, e.g. KQvKR, with the first side of its name as white. It is split into sub-tables by
+ * side to move and, with pawns, by the file of the leading pawn. A position is mapped to an index by mirroring it into
+ * a canonical orientation and placing the pieces group by group on the squares the earlier groups left free.
+ * Values are compressed in blocks by recursive pairing and canonical Huffman codes, a sparse index finds the block.
+ * WDL files don't care about positions where a capture is best and DTZ files only store one side to move, so probes
+ * search the captures and, if needed, one ply before reading a table. Files are read into memory on their first probe.
+ */
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    repr::{
+        _move::{self, NULL_MOVE},
+        board::Board,
+        move_gen::{GenType, MoveGen},
+        position::MOVE_ARR_SIZE,
+        types::*,
+    },
+    search::tablebase::{self, Tablebase, Wdl},
+    utils::zobrist::Zobrist,
+};
+
+pub const WDL_SUFFIX: &str = "rtbw";
+pub const DTZ_SUFFIX: &str = "rtbz";
+
+const TB_PIECES: usize = 7;
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+//file flags
+const SPLIT: u8 = 1; //both sides to move are stored, the material isn't symmetric
+const HAS_PAWNS: u8 = 2;
+//sub-table flags
+const STM: u8 = 1; //side to move of a DTZ sub-table
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+const LEAF: usize = 0xFFF; //right child of a symbol that isn't a pair
+//stored WDL values for the side to move
+const LOSS: i32 = -2;
+const BLESSED_LOSS: i32 = -1;
+const DRAW: i32 = 0;
+const CURSED_WIN: i32 = 1;
+const WIN: i32 = 2;
+const DTZ_MAP_BY_WDL: [usize; 5] = [1, 3, 0, 2, 0];
+
+/// Syzygy tables found in a set of directories
+pub struct SyzygyTablebase {
+    materials: Vec<Material>,
+    by_key: HashMap<u64, usize>, //both color assignments of a material point to it
+    zobrist: Zobrist,            //for the boards of the probe searches
+}
+
+/// One material, e.g. KQvKR, and its files
+struct Material {
+    name: String,
+    key: u64,  //piece counts with the first side of the name as white
+    key2: u64, //with the colors swapped, equal to key if symmetric
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool, //a side has exactly one of some piece type besides the king
+    pawn_count: [u32; 2],    //leading color first: white, unless only black has pawns or black has fewer
+    wdl_path: PathBuf,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+/// A WDL or DTZ file in memory
+struct Table {
+    bytes: Vec<u8>,
+    pairs: Vec<Vec<Pairs>>, //by file of the leading pawn (a-d, just one without pawns), then by side to move
+    map: usize,             //start of the DTZ value maps
+}
+
+/// One sub-table: its piece order, index groups and compressed values. Offsets point into the file
+#[derive(Clone, Default)]
+struct Pairs {
+    flags: u8,
+    pieces: [u8; TB_PIECES], //Syzygy piece codes, pawn 1 to king 6, black + 8
+    group_len: [usize; TB_PIECES + 1], //zero terminated
+    group_idx: [u64; TB_PIECES + 1],   //index factor of every group, the last one is the table size
+    block_size: u64,
+    span: u64, //indices per sparse index entry
+    sparse_index_size: u64,
+    num_blocks: u64,
+    block_length_size: u64,
+    min_sym_len: u8, //the value of single value tables
+    lowest_sym: usize,
+    base64: Vec<u64>, //lowest code of every symbol length, left aligned
+    symlen: Vec<u8>,  //values a symbol expands to, minus one
+    btree: usize,
+    sparse_index: usize,
+    block_length: usize,
+    data: usize,
+    map_idx: [u16; 4],
+}
+
+/// What a DTZ sub-table says about a position
+enum Stored {
+    Value(i32),
+    OtherSide, //the table stores the other side to move
+}
+
+/// Index lookups shared by all tables
+struct Encoding {
+    binomial: [[u64; 64]; TB_PIECES - 1],
+    map_pawns: [usize; 64], //a2-h7 to 0..47, the leading pawn has the highest value
+    lead_pawn_idx: [[u64; 64]; TB_PIECES - 1],
+    lead_pawns_size: [[u64; 4]; TB_PIECES - 1],
+    map_b1h1h7: [u64; 64], //squares below the a1-h8 diagonal to 0..27
+    map_a1d1d4: [usize; 64], //the a1-d1-d4 triangle to 0..9, diagonal last
+    map_kk: [[u64; 64]; 10], //the 462 king placements with the first king in the triangle
+}
+
+impl SyzygyTablebase {
+    /// Finds the tables in **paths**, directories separated as in the PATH variable
+    pub fn open(paths: &str) -> Result<Self, &'static str> {
+        let mut wdl_paths: HashMap<String, PathBuf> = HashMap::new();
+        let mut dtz_paths: HashMap<String, PathBuf> = HashMap::new();
+        for dir in std::env::split_paths(paths) {
+            let entries = fs::read_dir(&dir).map_err(|_| "Could not read tablebase directory")?;
+            for path in entries.flatten().map(|entry| entry.path()) {
+                let stem: Option<String> = path.file_stem().and_then(|stem| stem.to_str()).map(String::from);
+                let (Some(stem), Some(extension)) = (stem, path.extension().and_then(|ext| ext.to_str())) else {
+                    continue;
+                };
+                match extension {
+                    WDL_SUFFIX => wdl_paths.entry(stem).or_insert(path),
+                    DTZ_SUFFIX => dtz_paths.entry(stem).or_insert(path),
+                    _ => continue,
+                };
+            }
+        }
+        let mut names: Vec<String> = wdl_paths.keys().cloned().collect();
+        names.sort();
+        let mut tablebase: SyzygyTablebase = Self { materials: vec![], by_key: HashMap::new(), zobrist: Zobrist::default() };
+        for name in names {
+            let wdl_path: PathBuf = wdl_paths.remove(&name).expect("name is a key");
+            if !has_magic(&wdl_path, &WDL_MAGIC) {
+                continue;
+            }
+            let Some(material) = Material::new(&name, wdl_path, dtz_paths.remove(&name)) else {
+                continue;
+            };
+            tablebase.by_key.insert(material.key, tablebase.materials.len());
+            tablebase.by_key.insert(material.key2, tablebase.materials.len());
+            tablebase.materials.push(material);
+        }
+        if tablebase.materials.is_empty() {
+            return Err("No Syzygy tables found");
+        }
+        Ok(tablebase)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    /// Materials with a WDL file, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.materials.iter().map(|material| material.name.clone()).collect()
+    }
+
+    /// WDL of the side to move and whether a capture, or with **pawn_moves** a pawn move, achieves it
+    fn search(&self, board: &Board, move_gen: &MoveGen, pawn_moves: bool) -> Option<(i32, bool)> {
+        let moves: Vec<u32> = legal_moves(board, move_gen);
+        let mut best: i32 = LOSS;
+        let mut searched: usize = 0;
+        for mov in moves.iter().copied() {
+            if !(_move::is_eating(mov) || (pawn_moves && is_pawn_move(mov))) {
+                continue;
+            }
+            searched += 1;
+            let value: i32 = -self.search(&self.child(board, mov, move_gen), move_gen, false)?.0;
+            if value > best {
+                best = value;
+                if value >= WIN {
+                    return Some((value, true));
+                }
+            }
+        }
+        //the stored value may be wrong if every move was searched, e.g. with en passant rights
+        let no_more_moves: bool = searched > 0 && searched == moves.len();
+        let value: i32 = if no_more_moves {
+            best
+        } else {
+            match self.probe_table(board, false, DRAW)? {
+                Stored::Value(value) => value,
+                Stored::OtherSide => return None,
+            }
+        };
+        if best >= value {
+            return Some((best, best > DRAW || no_more_moves));
+        }
+        Some((value, false))
+    }
+
+    /// WDL and the signed plies to the next zeroing move, cursed results are 100 plies further
+    fn dtz(&self, board: &Board, move_gen: &MoveGen) -> Option<(i32, i32)> {
+        let (wdl, zeroing) = self.search(board, move_gen, true)?;
+        if wdl == DRAW {
+            return Some((wdl, 0));
+        }
+        if zeroing {
+            return Some((wdl, dtz_before_zeroing(wdl)));
+        }
+        if let Stored::Value(dtz) = self.probe_table(board, true, wdl)? {
+            let cursed: i32 = if wdl == CURSED_WIN || wdl == BLESSED_LOSS { 100 } else { 0 };
+            return Some((wdl, (dtz + cursed) * wdl.signum()));
+        }
+        //stored for the other side, take the best move of a one ply search
+        let mut min_dtz: i32 = i32::MAX;
+        for mov in legal_moves(board, move_gen) {
+            let zeroing: bool = _move::is_eating(mov) || is_pawn_move(mov);
+            let child: Board = self.child(board, mov, move_gen);
+            //zeroing moves count from before the move, the search only gives the sign
+            let mut dtz: i32 = if zeroing {
+                -dtz_before_zeroing(self.search(&child, move_gen, false)?.0)
+            } else {
+                -self.dtz(&child, move_gen)?.1
+            };
+            if dtz == 1 && child.nof_checkers > 0 && legal_moves(&child, move_gen).is_empty() {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        Some((wdl, if min_dtz == i32::MAX { -1 } else { min_dtz }))
+    }
+
+    /// Reads the stored value of **board**, ignoring captures and en passant. **wdl** selects the DTZ value map
+    fn probe_table(&self, board: &Board, dtz: bool, wdl: i32) -> Option<Stored> {
+        if board.total_occupation().count_ones() == 2 {
+            return Some(Stored::Value(DRAW));
+        }
+        let counts: [u32; 12] = std::array::from_fn(|p| board.pieces[p].count_ones());
+        let material: &Material = &self.materials[*self.by_key.get(&tablebase::material_key(&counts))?];
+        let table: &Table = material.table(dtz)?;
+        let (tb_file, stm, idx) = table.encode(material, board)?;
+        let d: &Pairs = &table.pairs[tb_file][stm % table.pairs[tb_file].len()];
+        let one_sided: bool = material.key != material.key2 || material.has_pawns; //else either side maps to white
+        if dtz && one_sided && (d.flags & STM) as usize != stm {
+            return Some(Stored::OtherSide);
+        }
+        let value: u16 = d.decompress(&table.bytes, idx)?;
+        if dtz {
+            table.map_score(tb_file, value, wdl).map(Stored::Value)
+        } else {
+            Some(Stored::Value(value as i32 - 2))
+        }
+    }
+
+    fn child(&self, board: &Board, mov: u32, move_gen: &MoveGen) -> Board {
+        let ep_square: Option<u32> =
+            _move::is_double_push(mov).then(|| (_move::get_init(mov) + _move::get_target(mov)) / 2);
+        let pieces: [u64; 12] = tablebase::pieces_after(&board.pieces, mov);
+        tablebase::board_from_pieces(pieces, board.turn ^ 1, ep_square, move_gen, &self.zobrist)
+    }
+}
+
+impl Tablebase for SyzygyTablebase {
+    fn max_pieces(&self) -> u32 {
+        self.materials.iter().map(|material| material.piece_count as u32).max().unwrap_or(0)
+    }
+
+    fn probe_wdl(&self, board: &Board, move_gen: &MoveGen) -> Option<Wdl> {
+        if !tablebase::is_probeable(self, board) {
+            return None;
+        }
+        self.search(board, move_gen, false).map(|(wdl, _)| to_wdl(wdl))
+    }
+
+    fn probe_dtz(&self, board: &Board, move_gen: &MoveGen) -> Option<(Wdl, u32)> {
+        if !tablebase::is_probeable(self, board) {
+            return None;
+        }
+        let (wdl, dtz) = self.dtz(board, move_gen)?;
+        //Syzygy counts the mate itself, the interface has 0 once mated
+        let mated: bool = wdl == LOSS && legal_moves(board, move_gen).is_empty();
+        Some((to_wdl(wdl), if mated { 0 } else { dtz.unsigned_abs() }))
+    }
+}
+
+impl Material {
+    fn new(name: &str, wdl_path: PathBuf, dtz_path: Option<PathBuf>) -> Option<Self> {
+        let counts: [u32; 12] = tablebase::parse_counts(name)?;
+        let piece_count: usize = counts.iter().sum::<u32>() as usize;
+        if piece_count <= 2 || piece_count > TB_PIECES {
+            return None;
+        }
+        let swapped: [u32; 12] = std::array::from_fn(|p| counts[(p + NOF_PIECE_TYPES_U) % 12]);
+        let (white_pawns, black_pawns) = (counts[W_PAWN_U], counts[B_PAWN_U]);
+        let white_leads: bool = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Self {
+            name: name.to_string(),
+            key: tablebase::material_key(&counts),
+            key2: tablebase::material_key(&swapped),
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: (W_PAWN_U..W_KING_U).any(|kind| counts[kind] == 1 || counts[kind + NOF_PIECE_TYPES_U] == 1),
+            pawn_count: if white_leads { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] },
+            wdl_path,
+            dtz_path,
+            wdl: OnceLock::new(),
+            dtz: OnceLock::new(),
+        })
+    }
+
+    /// The WDL or DTZ table, read on first use. None if missing or corrupt
+    fn table(&self, dtz: bool) -> Option<&Table> {
+        let (table, path) = if dtz { (&self.dtz, self.dtz_path.as_ref()?) } else { (&self.wdl, &self.wdl_path) };
+        table
+            .get_or_init(|| fs::read(path).ok().and_then(|bytes| Table::parse(bytes, self, dtz)))
+            .as_ref()
+    }
+}
+
+impl Table {
+    fn parse(bytes: Vec<u8>, material: &Material, dtz: bool) -> Option<Self> {
+        if bytes.get(..4)? != if dtz { DTZ_MAGIC } else { WDL_MAGIC } {
+            return None;
+        }
+        let flags: u8 = *bytes.get(4)?;
+        let split: bool = material.key != material.key2;
+        if (flags & HAS_PAWNS != 0) != material.has_pawns || (flags & SPLIT != 0) != split {
+            return None;
+        }
+        let sides: usize = if !dtz && split { 2 } else { 1 };
+        let files: usize = if material.has_pawns { 4 } else { 1 };
+        let both_pawns: bool = material.has_pawns && material.pawn_count[1] > 0;
+        let mut pairs: Vec<Vec<Pairs>> = vec![vec![Pairs::default(); sides]; files];
+        let mut pos: usize = 5;
+
+        //group orders and piece sequences, one nibble per side
+        for (file, file_pairs) in pairs.iter_mut().enumerate() {
+            let first: u8 = *bytes.get(pos)?;
+            let second: u8 = if both_pawns { *bytes.get(pos + 1)? } else { 0xFF };
+            let orders: [[u8; 2]; 2] = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            pos += 1 + both_pawns as usize;
+            for k in 0..material.piece_count {
+                let byte: u8 = *bytes.get(pos + k)?;
+                for (side, d) in file_pairs.iter_mut().enumerate() {
+                    d.pieces[k] = if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+            }
+            pos += material.piece_count;
+            for (side, d) in file_pairs.iter_mut().enumerate() {
+                d.set_groups(material, orders[side], file)?;
+            }
+        }
+        pos += pos & 1;
+        for d in pairs.iter_mut().flatten() {
+            pos = d.set_sizes(&bytes, pos)?;
+        }
+
+        let map: usize = pos;
+        if dtz {
+            for file_pairs in pairs.iter_mut() {
+                let d: &mut Pairs = &mut file_pairs[0];
+                if d.flags & MAPPED == 0 {
+                    continue;
+                }
+                //the maps of wins, losses, cursed wins and blessed losses
+                if d.flags & WIDE != 0 {
+                    pos += pos & 1;
+                    for i in 0..4 {
+                        d.map_idx[i] = ((pos - map) / 2 + 1) as u16;
+                        pos += 2 * read_u16(&bytes, pos)? as usize + 2;
+                    }
+                } else {
+                    for i in 0..4 {
+                        d.map_idx[i] = (pos - map + 1) as u16;
+                        pos += *bytes.get(pos)? as usize + 1;
+                    }
+                }
+            }
+            pos += pos & 1;
+        }
+
+        for d in pairs.iter_mut().flatten() {
+            d.sparse_index = pos;
+            pos += d.sparse_index_size as usize * 6;
+        }
+        for d in pairs.iter_mut().flatten() {
+            d.block_length = pos;
+            pos += d.block_length_size as usize * 2;
+        }
+        for d in pairs.iter_mut().flatten() {
+            pos = (pos + 0x3F) & !0x3F;
+            d.data = pos;
+            pos += (d.num_blocks * d.block_size) as usize;
+        }
+        if pos > bytes.len() {
+            return None;
+        }
+        Some(Self { bytes, pairs, map })
+    }
+
+    /// Sub-table file, side to move and index of **board**, a position of the material
+    fn encode(&self, material: &Material, board: &Board) -> Option<(usize, usize, u64)> {
+        let encoding: &Encoding = encoding();
+        let counts: [u32; 12] = std::array::from_fn(|p| board.pieces[p].count_ones());
+        let key: u64 = tablebase::material_key(&counts);
+
+        //tables have the first side of the name as white and symmetric ones only white to move
+        let flip: bool = key != material.key || (board.turn == BLACK && material.key == material.key2);
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let stm: usize = (flip as u32 ^ board.turn) as usize;
+        let mut squares: [usize; TB_PIECES] = [0; TB_PIECES];
+        let mut pieces: [u8; TB_PIECES] = [0; TB_PIECES];
+        let mut size: usize = 0;
+        let mut lead_pawns: u64 = 0;
+        let mut tb_file: usize = 0;
+        if material.has_pawns {
+            let lead: u8 = self.pairs[0][0].pieces[0] ^ flip_color;
+            lead_pawns = *board.pieces.get(W_PAWN_U + NOF_PIECE_TYPES_U * (lead >> 3) as usize)?;
+            for sqr in squares_of(lead_pawns) {
+                squares[size] = sqr ^ flip_squares;
+                size += 1;
+            }
+            //the leading pawn is the one nearest to the edge, then the lowest
+            let mut leading: usize = 0;
+            for i in 1..size {
+                if encoding.map_pawns[squares[i]] > encoding.map_pawns[squares[leading]] {
+                    leading = i;
+                }
+            }
+            squares.swap(0, leading);
+            tb_file = (squares[0] & 7).min(7 - (squares[0] & 7));
+        }
+        let lead_count: usize = size;
+        let sides: &Vec<Pairs> = self.pairs.get(tb_file)?;
+        let d: &Pairs = &sides[stm % sides.len()];
+
+        for sqr in squares_of(board.total_occupation() ^ lead_pawns) {
+            squares[size] = sqr ^ flip_squares;
+            pieces[size] = piece_code(piece_on(board, sqr)) ^ flip_color;
+            size += 1;
+        }
+        //same piece order as the sub-table
+        for i in lead_count..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|j| d.pieces[i] == pieces[*j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        if squares[0] & 7 > 3 {
+            squares[..size].iter_mut().for_each(|sqr| *sqr ^= 7);
+        }
+
+        let mut idx: u64 = if material.has_pawns {
+            let mut idx: u64 = encoding.lead_pawn_idx[lead_count][squares[0]];
+            squares[1..lead_count].sort_by_key(|sqr| encoding.map_pawns[*sqr]);
+            for (i, sqr) in squares[1..lead_count].iter().enumerate() {
+                idx += encoding.binomial[i + 1][encoding.map_pawns[*sqr]];
+            }
+            idx
+        } else {
+            if squares[0] >> 3 > 3 {
+                squares[..size].iter_mut().for_each(|sqr| *sqr ^= 56);
+            }
+            //the first piece of the leading group off the a1-h8 diagonal goes below it
+            if let Some(i) = (0..d.group_len[0].min(size)).find(|i| off_diagonal(squares[*i]) != 0) {
+                if off_diagonal(squares[i]) > 0 {
+                    squares[i..size].iter_mut().for_each(|sqr| *sqr = ((*sqr >> 3) | (*sqr << 3)) & 63);
+                }
+            }
+            if material.has_unique_pieces {
+                encoding.unique_idx(squares[0], squares[1], squares[2])
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]]][squares[1]]
+            }
+        };
+
+        //remaining groups by ascending square, on the squares left by the earlier groups
+        idx *= d.group_idx[0];
+        let mut group_start: usize = d.group_len[0];
+        let mut remaining_pawns: bool = material.has_pawns && material.pawn_count[1] > 0;
+        for next in 1..=TB_PIECES {
+            let len: usize = d.group_len[next];
+            if len == 0 {
+                break;
+            }
+            let group: &mut [usize] = squares.get_mut(group_start..group_start + len)?;
+            group.sort_unstable();
+            let mut n: u64 = 0;
+            for i in 0..len {
+                let sqr: usize = squares[group_start + i];
+                let adjust: usize = squares[..group_start].iter().filter(|s| sqr > **s).count();
+                n += encoding.binomial.get(i + 1)?[(sqr - adjust).checked_sub(8 * remaining_pawns as usize)?];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start += len;
+        }
+
+        Some((tb_file, stm, idx))
+    }
+
+
+    /// DTZ in plies from the stored **value** of a position with result **wdl**
+    fn map_score(&self, tb_file: usize, value: u16, wdl: i32) -> Option<i32> {
+        let d: &Pairs = &self.pairs[tb_file][0];
+        let mut value: i32 = value as i32;
+        if d.flags & MAPPED != 0 {
+            let idx: usize = d.map_idx[DTZ_MAP_BY_WDL[(wdl + 2) as usize]] as usize + value as usize;
+            value = if d.flags & WIDE != 0 {
+                read_u16(&self.bytes, self.map + 2 * idx)? as i32
+            } else {
+                *self.bytes.get(self.map + idx)? as i32
+            };
+        }
+        //values are moves unless the flags say plies
+        if (wdl == WIN && d.flags & WIN_PLIES == 0)
+            || (wdl == LOSS && d.flags & LOSS_PLIES == 0)
+            || wdl == CURSED_WIN
+            || wdl == BLESSED_LOSS
+        {
+            value *= 2;
+        }
+        Some(value + 1)
+    }
+}
+
+impl Pairs {
+    /// Splits the piece sequence into groups and sets their index factors in the stored **order**
+    fn set_groups(&mut self, material: &Material, order: [u8; 2], file: usize) -> Option<()> {
+        let encoding: &Encoding = encoding();
+        //leading pawns, three unique pieces or the two kings are encoded together
+        let mut first_len: i32 = if material.has_pawns { 0 } else if material.has_unique_pieces { 3 } else { 2 };
+        let mut n: usize = 0;
+        self.group_len[0] = 1;
+        for i in 1..material.piece_count {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                self.group_len[n] += 1;
+            } else {
+                n += 1;
+                self.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        self.group_len[n] = 0;
+
+        let both_pawns: bool = material.has_pawns && material.pawn_count[1] > 0;
+        let mut next: usize = if both_pawns { 2 } else { 1 };
+        let mut free_squares: usize = 64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        let mut k: u8 = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                self.group_idx[0] = idx;
+                idx *= if material.has_pawns {
+                    encoding.lead_pawns_size.get(self.group_len[0])?[file]
+                } else if material.has_unique_pieces {
+                    31_332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                self.group_idx[1] = idx;
+                idx *= encoding.binomial.get(self.group_len[1])?[48 - self.group_len[0]];
+            } else {
+                self.group_idx[next] = idx;
+                idx *= encoding.binomial.get(self.group_len[next])?[free_squares];
+                free_squares -= self.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        self.group_idx[n] = idx;
+        Some(())
+    }
+
+    /// Reads the block sizes and the symbol tables, returns the position after them
+    fn set_sizes(&mut self, bytes: &[u8], mut pos: usize) -> Option<usize> {
+        self.flags = *bytes.get(pos)?;
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_sym_len = *bytes.get(pos + 1)?;
+            return Some(pos + 2);
+        }
+        let tb_size: u64 = self.group_idx[self.group_len.iter().position(|len| *len == 0)?];
+        self.block_size = 1u64.checked_shl(*bytes.get(pos + 1)? as u32)?;
+        self.span = 1u64.checked_shl(*bytes.get(pos + 2)? as u32)?;
+        self.sparse_index_size = tb_size.div_ceil(self.span);
+        let padding: u64 = *bytes.get(pos + 3)? as u64;
+        self.num_blocks = read_u32(bytes, pos + 4)? as u64;
+        self.block_length_size = self.num_blocks + padding; //keeps the sparse index in range
+        let max_sym_len: u8 = *bytes.get(pos + 8)?;
+        self.min_sym_len = *bytes.get(pos + 9)?;
+        if max_sym_len < self.min_sym_len || max_sym_len > 64 {
+            return None;
+        }
+        pos += 10;
+
+        //canonical Huffman codes: longer codes have lower values, so base64 decreases with the length
+        self.lowest_sym = pos;
+        let lengths: usize = (max_sym_len - self.min_sym_len) as usize + 1;
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest: u64 = read_u16(bytes, self.lowest_sym + 2 * i)? as u64;
+            let next_lowest: u64 = read_u16(bytes, self.lowest_sym + 2 * (i + 1))? as u64;
+            self.base64[i] = self.base64[i + 1].wrapping_add(lowest).wrapping_sub(next_lowest) / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - i - self.min_sym_len as usize) as u32).unwrap_or(0);
+        }
+        pos += lengths * 2;
+
+        //recursive pairing: every symbol is a value or a pair of symbols
+        let symbols: usize = read_u16(bytes, pos)? as usize;
+        pos += 2;
+        self.btree = pos;
+        self.symlen = vec![0; symbols];
+        let mut visited: Vec<bool> = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.symlen[sym] = self.set_symlen(bytes, sym, &mut visited)?;
+            }
+        }
+        Some(pos + symbols * 3 + (symbols & 1))
+    }
+
+    fn set_symlen(&mut self, bytes: &[u8], sym: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[sym] = true;
+        let (left, right) = self.pair(bytes, sym)?;
+        if right == LEAF {
+            return Some(0);
+        }
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symlen[child] = self.set_symlen(bytes, child, visited)?;
+            }
+        }
+        Some(self.symlen[left].wrapping_add(self.symlen[right]).wrapping_add(1))
+    }
+
+    /// Left and right child of **sym**, packed into 12 bits each
+    fn pair(&self, bytes: &[u8], sym: usize) -> Option<(usize, usize)> {
+        let lr: &[u8] = bytes.get(self.btree + 3 * sym..self.btree + 3 * sym + 3)?;
+        let left: usize = ((lr[1] as usize & 0xF) << 8) | lr[0] as usize;
+        let right: usize = ((lr[2] as usize) << 4) | (lr[1] as usize >> 4);
+        Some((left, right))
+    }
+
+    /// Stored value at **idx**, None if the data is corrupt
+    fn decompress(&self, bytes: &[u8], idx: u64) -> Option<u16> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Some(self.min_sym_len as u16);
+        }
+        //the sparse entry k points to the block and offset of index k * span + span / 2
+        let k: usize = (idx / self.span) as usize;
+        let mut block: u64 = read_u32(bytes, self.sparse_index + 6 * k)? as u64;
+        let mut offset: i64 = read_u16(bytes, self.sparse_index + 6 * k + 4)? as i64;
+        offset += (idx % self.span) as i64 - (self.span / 2) as i64;
+        let block_length = |block: u64| read_u16(bytes, self.block_length + 2 * block as usize).map(|len| len as i64);
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        //walk the symbols of the block until the one covering the offset
+        let mut ptr: usize = self.data + (block * self.block_size) as usize;
+        let mut buf: u64 = u64::from_be_bytes(bytes.get(ptr..ptr + 8)?.try_into().ok()?);
+        ptr += 8;
+        let mut buf_size: i32 = 64;
+        let mut sym: usize;
+        loop {
+            let mut len: usize = 0;
+            while buf < *self.base64.get(len)? {
+                len += 1;
+            }
+            let bits: usize = len + self.min_sym_len as usize;
+            sym = (buf - self.base64[len]).checked_shr(64 - bits as u32).unwrap_or(0) as usize;
+            sym += read_u16(bytes, self.lowest_sym + 2 * len)? as usize;
+            let expands: i64 = *self.symlen.get(sym)? as i64 + 1;
+            if offset < expands {
+                break;
+            }
+            offset -= expands;
+            buf = buf.checked_shl(bits as u32).unwrap_or(0);
+            buf_size -= bits as i32;
+            if buf_size <= 32 {
+                buf_size += 32;
+                buf |= (u32::from_be_bytes(bytes.get(ptr..ptr + 4)?.try_into().ok()?) as u64) << (64 - buf_size);
+                ptr += 4;
+            }
+        }
+
+        //expand the pairs down to the value at the offset
+        while self.symlen[sym] != 0 {
+            let (left, right) = self.pair(bytes, sym)?;
+            let left_expands: i64 = *self.symlen.get(left)? as i64 + 1;
+            if offset < left_expands {
+                sym = left;
+            } else {
+                offset -= left_expands;
+                sym = right;
+            }
+            self.symlen.get(sym)?;
+        }
+        Some(self.pair(bytes, sym)?.0 as u16)
+    }
+}
+
+impl Encoding {
+    fn new() -> Self {
+        let mut encoding: Encoding = Self {
+            binomial: [[0; 64]; TB_PIECES - 1],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; TB_PIECES - 1],
+            lead_pawns_size: [[0; 4]; TB_PIECES - 1],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+        };
+        for (code, sqr) in (0..).zip((0..64).filter(|sqr| off_diagonal(*sqr) < 0)) {
+            encoding.map_b1h1h7[sqr] = code;
+        }
+
+        let mut code: usize = 0;
+        let mut diagonal: Vec<usize> = vec![];
+        for sqr in (0..=27).filter(|sqr| sqr & 7 <= 3) {
+            match off_diagonal(sqr) {
+                0 => diagonal.push(sqr),
+                off if off < 0 => {
+                    encoding.map_a1d1d4[sqr] = code;
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+        for sqr in diagonal {
+            encoding.map_a1d1d4[sqr] = code;
+            code += 1;
+        }
+
+        //kings on the a1-d4 diagonal keep the other one on or below the a1-h8 diagonal, both on it come last
+        let mut both_on_diagonal: Vec<(usize, usize)> = vec![];
+        let mut code: u64 = 0;
+        for idx in 0..10 {
+            for first in (0..=27).filter(|sqr| encoding.map_a1d1d4[*sqr] == idx && (idx != 0 || *sqr == 1)) {
+                for second in 0..64 {
+                    let distance: usize = (first & 7).abs_diff(second & 7).max((first >> 3).abs_diff(second >> 3));
+                    if distance <= 1 || (off_diagonal(first) == 0 && off_diagonal(second) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((idx, second));
+                    } else {
+                        encoding.map_kk[idx][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, second) in both_on_diagonal {
+            encoding.map_kk[idx][second] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..(TB_PIECES - 1).min(n + 1) {
+                encoding.binomial[k][n] = if k > 0 { encoding.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { encoding.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        //a leading pawn on a square leaves the squares nearer to the center and higher up for the others
+        let mut available: usize = 48;
+        for lead_count in 1..TB_PIECES - 1 {
+            for file in 0..4 {
+                let mut idx: u64 = 0;
+                for rank in 1..7 {
+                    let sqr: usize = rank * 8 + file;
+                    if lead_count == 1 {
+                        encoding.map_pawns[sqr] = available - 1;
+                        encoding.map_pawns[sqr ^ 7] = available - 2;
+                        available -= 2;
+                    }
+                    encoding.lead_pawn_idx[lead_count][sqr] = idx;
+                    idx += encoding.binomial[lead_count - 1][encoding.map_pawns[sqr]];
+                }
+                encoding.lead_pawns_size[lead_count][file] = idx;
+            }
+        }
+        encoding
+    }
+
+    /// Index of three unique leading pieces, the first in the a1-d1-d4 triangle
+    fn unique_idx(&self, first: usize, second: usize, third: usize) -> u64 {
+        let adjust1: u64 = (second > first) as u64;
+        let adjust2: u64 = (third > first) as u64 + (third > second) as u64;
+        let rank = |sqr: usize| (sqr >> 3) as u64;
+        if off_diagonal(first) != 0 {
+            (self.map_a1d1d4[first] as u64 * 63 + (second as u64 - adjust1)) * 62 + third as u64 - adjust2
+        } else if off_diagonal(second) != 0 {
+            (6 * 63 + rank(first) * 28 + self.map_b1h1h7[second]) * 62 + third as u64 - adjust2
+        } else if off_diagonal(third) != 0 {
+            6 * 63 * 62 + 4 * 28 * 62 + rank(first) * 7 * 28 + (rank(second) - adjust1) * 28 + self.map_b1h1h7[third]
+        } else {
+            6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(first) * 7 * 6 + (rank(second) - adjust1) * 6 + (rank(third) - adjust2)
+        }
+    }
+}
+
+fn encoding() -> &'static Encoding {
+    static ENCODING: OnceLock<Encoding> = OnceLock::new();
+    ENCODING.get_or_init(Encoding::new)
+}
+
+/// Rank minus file, negative below the a1-h8 diagonal
+fn off_diagonal(sqr: usize) -> i32 {
+    (sqr >> 3) as i32 - (sqr & 7) as i32
+}
+
+fn squares_of(mut bb: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (bb != 0).then(|| {
+            let sqr: usize = bb.trailing_zeros() as usize;
+            bb &= bb - 1;
+            sqr
+        })
+    })
+}
+
+fn piece_on(board: &Board, sqr: usize) -> usize {
+    (0..12).find(|p| board.pieces[*p] & (1 << sqr) != 0).expect("occupied square")
+}
+
+/// Syzygy code of a piece: pawn 1 to king 6, black pieces + 8
+fn piece_code(piece: usize) -> u8 {
+    (piece % NOF_PIECE_TYPES_U + 1) as u8 | ((piece / NOF_PIECE_TYPES_U) << 3) as u8
+}
+
+fn has_magic(path: &Path, magic: &[u8; 4]) -> bool {
+    let mut read: [u8; 4] = [0; 4];
+    fs::File::open(path).and_then(|mut file| file.read_exact(&mut read)).is_ok() && read == *magic
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn legal_moves(board: &Board, move_gen: &MoveGen) -> Vec<u32> {
+    let mut moves: [u32; MOVE_ARR_SIZE] = [NULL_MOVE; MOVE_ARR_SIZE];
+    let mut pseudo_moves: Vec<u32> = vec![NULL_MOVE; MAX_PSEUDO_MOVES_IN_POS];
+    let generated: usize =
+        move_gen.generate_legal(board, board.turn, &mut moves, &mut pseudo_moves, 0, false, false, GenType::All);
+    moves[..generated].to_vec()
+}
+
+fn is_pawn_move(mov: u32) -> bool {
+    _move::get_moved_piece(mov) as usize % NOF_PIECE_TYPES_U == W_PAWN_U
+}
+
+/// DTZ of a position with result **wdl** whose best move is a zeroing one
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        WIN => 1,
+        CURSED_WIN => 101,
+        BLESSED_LOSS => -101,
+        LOSS => -1,
+        _ => 0,
+    }
+}
+
+fn to_wdl(wdl: i32) -> Wdl {
+    match wdl {
+        i32::MIN..=LOSS => Wdl::Loss,
+        BLESSED_LOSS => Wdl::BlessedLoss,
+        DRAW => Wdl::Draw,
+        CURSED_WIN => Wdl::CursedWin,
+        _ => Wdl::Win,
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/syzygy_tests.rs"]
+mod tests;
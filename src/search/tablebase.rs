@@ -0,0 +1,821 @@
+/*
+ * Endgame tablebases: the probing interface of the search and a local format generated by retrograde analysis.
+ *
+ * Local tables cover up to 4 pieces, one file per material (e.g. KQvKR.rtb). Every position takes a byte holding the
+ * result for the side to move and its distance to zeroing (DTZ), the plies until the winning side captures or moves a
+ * pawn with best play. Wins that need more than 100 plies to the next zeroing move are cursed, the fifty move rule draws them.
+ * The stronger side is always stored as white, pawnless tables are mirrored so the white king is on files a-d and pawn
+ * tables so their first pawn is.
+ */
+
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    repr::{
+        _move::{self, NULL_MOVE},
+        board::{Board, RANKS},
+        move_gen::{add_en_passant, GenType, MoveGen},
+        position::{Position, MOVE_ARR_SIZE},
+        types::*,
+    },
+    search::eval::PIECE_MATERIAL_VALUE,
+    utils::zobrist::Zobrist,
+};
+
+pub const MAX_LOCAL_PIECES: u32 = 4;
+/// Score of a tablebase win at the root, the ply is subtracted as with mates. <br>
+/// Between KNOWN_WIN and MATE_BOUND, so real mates are preferred and the TT doesn't adjust it.
+pub const TB_WIN: i16 = 20_000;
+pub const FILE_EXTENSION: &str = "rtb";
+
+const FIFTY_MOVE_PLIES: u32 = 100;
+const MAGIC: &[u8; 4] = b"RTB1";
+const KEY_SQUARES: usize = 32; //files a-d of the mirrored key piece
+const MAX_STORED_DTZ: u32 = 127;
+//outcome scores, max is best for the side to move
+const WIN_BASE: i32 = 1_000;
+const NO_TERMINAL: i32 = i32::MIN;
+//generation states
+const INVALID: u8 = 0;
+const UNRESOLVED: u8 = 1;
+const DRAWN: u8 = 2;
+const RESOLVED: u8 = 3;
+const PROPAGATED: u8 = 4;
+
+/// Result for the side to move assuming the fifty move counter was just reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss, //lost, but the fifty move rule saves it
+    Draw,
+    CursedWin, //won, but the fifty move rule draws it
+    Win,
+}
+
+impl Wdl {
+    /// Search score **ply** plies from the root, cursed and blessed results are draws nudged towards the better side
+    pub fn to_score(self, ply: usize) -> i16 {
+        match self {
+            Wdl::Loss => -TB_WIN + ply as i16,
+            Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 1,
+            Wdl::Win => TB_WIN - ply as i16,
+        }
+    }
+
+    /// Same result with the fifty move rule deciding at **plies** to the next zeroing move
+    fn with_dtz(self, plies: u32) -> Self {
+        match self {
+            Wdl::Win | Wdl::CursedWin if plies <= FIFTY_MOVE_PLIES => Wdl::Win,
+            Wdl::Win | Wdl::CursedWin => Wdl::CursedWin,
+            Wdl::Loss | Wdl::BlessedLoss if plies <= FIFTY_MOVE_PLIES => Wdl::Loss,
+            Wdl::Loss | Wdl::BlessedLoss => Wdl::BlessedLoss,
+            Wdl::Draw => Wdl::Draw,
+        }
+    }
+}
+
+/// Probing interface of the search, positions with castling rights are never probed
+pub trait Tablebase: Send + Sync {
+    /// Most pieces, kings included, of the available tables
+    fn max_pieces(&self) -> u32;
+    /// None if the position isn't covered
+    fn probe_wdl(&self, board: &Board, move_gen: &MoveGen) -> Option<Wdl>;
+    /// Result and the plies to the next zeroing move with best play, mated positions have 0
+    fn probe_dtz(&self, board: &Board, move_gen: &MoveGen) -> Option<(Wdl, u32)>;
+}
+
+/// Could **tablebase** have the position? Castling rights are not part of any table
+pub fn is_probeable(tablebase: &dyn Tablebase, board: &Board) -> bool {
+    !(board.ws() || board.wl() || board.bs() || board.bl())
+        && board.total_occupation().count_ones() <= tablebase.max_pieces()
+}
+
+/// Restricts the root moves of **pos** to the ones keeping the best result under the fifty move rule,
+/// wins have to reach the next zeroing move before the counter runs out. <br>
+/// Returns the root result, None if a move isn't covered and the moves were left alone
+pub fn filter_root_moves(
+    tablebase: &dyn Tablebase,
+    pos: &mut Position,
+    move_gen: &MoveGen,
+    zobrist: &Zobrist,
+) -> Option<Wdl> {
+    if !is_probeable(tablebase, &pos.board) {
+        return None;
+    }
+    let mut results: Vec<(u32, Wdl)> = Vec::with_capacity(pos.legal_moves().len());
+    for mov in pos.legal_moves().to_vec() {
+        pos.make_move_lazy(mov, true, move_gen, zobrist);
+        let probe: Option<(Wdl, u32)> = tablebase.probe_dtz(&pos.board, move_gen);
+        let counter: u32 = pos.board.half_move_clock;
+        pos.unmake_move(mov, zobrist);
+        let (wdl, dtz) = probe?;
+        let wdl: Wdl = match wdl {
+            Wdl::Loss | Wdl::BlessedLoss => Wdl::Win,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::Win | Wdl::CursedWin => Wdl::Loss,
+        };
+        results.push((mov, wdl.with_dtz(counter + dtz)));
+    }
+    let best: Wdl = results.iter().map(|(_, wdl)| *wdl).max()?;
+    pos.retain_legal_moves(|mov| results.iter().any(|(m, wdl)| *m == mov && same_outcome(*wdl, best)));
+    Some(best)
+}
+
+///Cursed wins, draws and blessed losses are all draws under the fifty move rule
+fn same_outcome(a: Wdl, b: Wdl) -> bool {
+    let outcome = |wdl: Wdl| match wdl {
+        Wdl::Loss => -1,
+        Wdl::BlessedLoss | Wdl::Draw | Wdl::CursedWin => 0,
+        Wdl::Win => 1,
+    };
+    outcome(a) == outcome(b)
+}
+
+/// One material, e.g. KQvKR, stored with the stronger side as white
+struct LocalTable {
+    name: String,
+    slots: Vec<usize>,        //piece of every slot: kings, then white and black pieces by descending kind
+    key_slot: usize,          //first pawn, or the white king without pawns. Mirrored to files a-d
+    pawn_slots: Vec<usize>,   //other pawns, after the key in the index
+    piece_slots: Vec<usize>,  //other pieces, after the side to move in the index
+    data: Vec<u8>,
+}
+
+/// Local tables of up to MAX_LOCAL_PIECES pieces, generated with generate or loaded from a directory
+#[derive(Default)]
+pub struct LocalTablebase {
+    tables: HashMap<u64, LocalTable>,
+}
+
+impl LocalTablebase {
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Material names of the tables, e.g. KQvKR
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tables.values().map(|table| table.name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    /// Loads every table file in **dir**
+    pub fn load(dir: &str) -> Result<Self, &'static str> {
+        let entries = fs::read_dir(dir).map_err(|_| "Could not read tablebase directory")?;
+        let mut tablebase: LocalTablebase = LocalTablebase::default();
+        for entry in entries {
+            let path = entry.map_err(|_| "Could not read tablebase directory")?.path();
+            if path.extension().is_some_and(|ext| ext == FILE_EXTENSION) {
+                let bytes: Vec<u8> = fs::read(&path).map_err(|_| "Could not read tablebase file")?;
+                let table: LocalTable = LocalTable::from_bytes(&bytes)?;
+                tablebase.tables.insert(material_key(&table.counts()), table);
+            }
+        }
+        Ok(tablebase)
+    }
+
+    /// Writes every table to its own file in **dir**
+    pub fn save(&self, dir: &str) -> Result<(), &'static str> {
+        fs::create_dir_all(dir).map_err(|_| "Could not create tablebase directory")?;
+        for table in self.tables.values() {
+            let path = Path::new(dir).join(format!("{}.{}", table.name, FILE_EXTENSION));
+            fs::write(path, table.to_bytes()).map_err(|_| "Could not write tablebase file")?;
+        }
+        Ok(())
+    }
+
+    /// Generates the tables of every material of up to **max_pieces** pieces that is still missing
+    pub fn generate_all(&mut self, max_pieces: u32, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<(), &'static str> {
+        if max_pieces > MAX_LOCAL_PIECES {
+            return Err("Local tablebases have at most 4 pieces");
+        }
+        for name in all_materials(max_pieces) {
+            self.generate(&name, move_gen, zobrist)?;
+        }
+        Ok(())
+    }
+
+    /// Generates the table of material **name**, e.g. KRvKP, and first the missing tables it converts to
+    pub fn generate(&mut self, name: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<(), &'static str> {
+        let counts: [u32; 12] = parse_material(name).ok_or("Invalid material")?;
+        let counts: [u32; 12] = canonical_counts(&counts);
+        self.generate_counts(&counts, move_gen, zobrist);
+        Ok(())
+    }
+
+    fn generate_counts(&mut self, counts: &[u32; 12], move_gen: &MoveGen, zobrist: &Zobrist) {
+        if only_kings(counts) || self.tables.contains_key(&material_key(counts)) {
+            return;
+        }
+        for dependency in conversions(counts) {
+            self.generate_counts(&dependency, move_gen, zobrist);
+        }
+        let table: LocalTable = Generator::new(self, move_gen, zobrist).generate(counts);
+        self.tables.insert(material_key(counts), table);
+    }
+
+    /// Outcome score for the side to move of a position without en passant, None if its table is missing. <br>
+    /// **current** is a table still being generated
+    fn lookup(&self, pieces: &[u64; 12], turn: u32, current: Option<&LocalTable>) -> Option<i32> {
+        let counts: [u32; 12] = std::array::from_fn(|p| pieces[p].count_ones());
+        if only_kings(&counts) {
+            return Some(0);
+        }
+        let canonical: [u32; 12] = canonical_counts(&counts);
+        let (pieces, turn) = if canonical != counts { (flip(pieces), turn ^ 1) } else { (*pieces, turn) };
+        let key: u64 = material_key(&canonical);
+        let table: &LocalTable = match current {
+            Some(table) if material_key(&table.counts()) == key => table,
+            _ => self.tables.get(&key)?,
+        };
+        let mut remaining: [u64; 12] = pieces;
+        let mut squares: [u32; MAX_LOCAL_PIECES as usize] = [0; MAX_LOCAL_PIECES as usize];
+        for (slot, piece) in table.slots.iter().enumerate() {
+            squares[slot] = remaining[*piece].trailing_zeros();
+            remaining[*piece] &= remaining[*piece] - 1;
+        }
+        Some(decode(table.data[table.index(&squares, turn)]))
+    }
+
+    /// Outcome score of **board**, taking en passant captures into account
+    fn probe_score(&self, board: &Board, move_gen: &MoveGen, current: Option<&LocalTable>) -> Option<i32> {
+        let mut score: i32 = self.lookup(&board.pieces, board.turn, current)?;
+        if board.ep_square.is_some() {
+            let mut eps: [u32; 2] = [NULL_MOVE; 2];
+            let generated: usize = add_en_passant(board, board.turn, &mut eps, 0);
+            for ep in eps[..generated].iter().filter(|ep| move_gen.pseudolegal_is_legal(**ep, board)) {
+                let child: i32 = self.lookup(&pieces_after(&board.pieces, *ep), board.turn ^ 1, current)?;
+                score = score.max(after_zeroing(child));
+            }
+        }
+        Some(score)
+    }
+}
+
+impl Tablebase for LocalTablebase {
+    fn max_pieces(&self) -> u32 {
+        self.tables.values().map(|table| table.slots.len() as u32).max().unwrap_or(0)
+    }
+
+    fn probe_wdl(&self, board: &Board, move_gen: &MoveGen) -> Option<Wdl> {
+        self.probe_dtz(board, move_gen).map(|(wdl, _)| wdl)
+    }
+
+    fn probe_dtz(&self, board: &Board, move_gen: &MoveGen) -> Option<(Wdl, u32)> {
+        if !is_probeable(self, board) {
+            return None;
+        }
+        self.probe_score(board, move_gen, None).map(outcome)
+    }
+}
+
+impl LocalTable {
+    fn new(counts: &[u32; 12]) -> Self {
+        let mut slots: Vec<usize> = vec![W_KING_U, B_KING_U];
+        for offset in [W_PAWN_U, B_PAWN_U] {
+            for kind in (W_PAWN_U..W_KING_U).rev() {
+                slots.extend((0..counts[offset + kind]).map(|_| offset + kind));
+            }
+        }
+        let is_pawn = |slot: &usize| slots[*slot] % NOF_PIECE_TYPES_U == W_PAWN_U;
+        let mut pawn_slots: Vec<usize> = (0..slots.len()).filter(is_pawn).collect();
+        let key_slot: usize = if pawn_slots.is_empty() { 0 } else { pawn_slots.remove(0) };
+        let piece_slots: Vec<usize> =
+            (0..slots.len()).filter(|slot| !is_pawn(slot) && *slot != key_slot).collect();
+        let size: usize = KEY_SQUARES * 64usize.pow((slots.len() - 1) as u32) * 2;
+        Self {
+            name: material_name(counts),
+            slots,
+            key_slot,
+            pawn_slots,
+            piece_slots,
+            data: vec![0; size],
+        }
+    }
+
+    fn counts(&self) -> [u32; 12] {
+        let mut counts: [u32; 12] = [0; 12];
+        for piece in self.slots.iter() {
+            counts[*piece] += 1;
+        }
+        counts
+    }
+
+    fn has_pawns(&self) -> bool {
+        self.slots[self.key_slot] % NOF_PIECE_TYPES_U == W_PAWN_U
+    }
+
+    /// Positions sharing the pawns, the unit of generation
+    fn block_size(&self) -> usize {
+        2 * 64usize.pow(self.piece_slots.len() as u32)
+    }
+
+    /// **squares** by slot, mirrored first if the key piece is on files e-h
+    fn index(&self, squares: &[u32], turn: u32) -> usize {
+        let mirror: u32 = if squares[self.key_slot] % 8 >= 4 { 7 } else { 0 };
+        let key_sqr: u32 = squares[self.key_slot] ^ mirror;
+        let mut idx: usize = (key_sqr / 8 * 4 + key_sqr % 8) as usize;
+        for slot in self.pawn_slots.iter() {
+            idx = idx * 64 + (squares[*slot] ^ mirror) as usize;
+        }
+        idx = idx * 2 + turn as usize;
+        for slot in self.piece_slots.iter() {
+            idx = idx * 64 + (squares[*slot] ^ mirror) as usize;
+        }
+        idx
+    }
+
+    /// Squares by slot and the side to move of **idx**
+    fn decode_index(&self, mut idx: usize) -> ([u32; MAX_LOCAL_PIECES as usize], u32) {
+        let mut squares: [u32; MAX_LOCAL_PIECES as usize] = [0; MAX_LOCAL_PIECES as usize];
+        for slot in self.piece_slots.iter().rev() {
+            squares[*slot] = (idx % 64) as u32;
+            idx /= 64;
+        }
+        let turn: u32 = (idx % 2) as u32;
+        idx /= 2;
+        for slot in self.pawn_slots.iter().rev() {
+            squares[*slot] = (idx % 64) as u32;
+            idx /= 64;
+        }
+        squares[self.key_slot] = (idx / 4 * 8 + idx % 4) as u32;
+        (squares, turn)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.push(self.name.len() as u8);
+        bytes.extend_from_slice(self.name.as_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("Not a tablebase file");
+        }
+        let name_end: usize = MAGIC.len() + 1 + bytes[MAGIC.len()] as usize;
+        let name: &str = bytes
+            .get(MAGIC.len() + 1..name_end)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .ok_or("Truncated tablebase file")?;
+        let counts: [u32; 12] = parse_material(name).ok_or("Invalid material in tablebase file")?;
+        if canonical_counts(&counts) != counts {
+            return Err("Invalid material in tablebase file");
+        }
+        let mut table: LocalTable = LocalTable::new(&counts);
+        if bytes.len() - name_end != table.data.len() {
+            return Err("Tablebase file has the wrong size");
+        }
+        table.data.copy_from_slice(&bytes[name_end..]);
+        Ok(table)
+    }
+}
+
+/// Retrograde analysis of one table, the tables it converts to have to exist. <br>
+/// Pawn tables are solved pawn configuration by configuration, most advanced first, so pawn moves lead to solved positions.
+/// Every position first generates its moves: captures and pawn moves are terminal and valued from the solved tables,
+/// the other moves are counted. Results then spread backwards by unmoving pieces, nearest zeroing first.
+struct Generator<'a> {
+    tablebase: &'a LocalTablebase,
+    move_gen: &'a MoveGen,
+    zobrist: &'a Zobrist,
+    move_arr: Box<[u32; MOVE_ARR_SIZE]>,
+    pseudo_move_arr: Vec<u32>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(tablebase: &'a LocalTablebase, move_gen: &'a MoveGen, zobrist: &'a Zobrist) -> Self {
+        Self {
+            tablebase,
+            move_gen,
+            zobrist,
+            move_arr: Box::new([NULL_MOVE; MOVE_ARR_SIZE]),
+            pseudo_move_arr: vec![NULL_MOVE; MAX_PSEUDO_MOVES_IN_POS],
+        }
+    }
+
+    fn generate(&mut self, counts: &[u32; 12]) -> LocalTable {
+        let mut table: LocalTable = LocalTable::new(counts);
+        if !table.has_pawns() {
+            let size: usize = table.data.len();
+            self.solve_block(&mut table, 0, size);
+            return table;
+        }
+        let block_size: usize = table.block_size();
+        let mut blocks: Vec<(u32, usize)> = (0..table.data.len() / block_size)
+            .filter_map(|block| {
+                let (squares, _) = table.decode_index(block * block_size);
+                pawn_potential(&table, &squares).map(|potential| (potential, block * block_size))
+            })
+            .collect();
+        blocks.sort_by_key(|(potential, _)| std::cmp::Reverse(*potential));
+        for (_, start) in blocks {
+            self.solve_block(&mut table, start, block_size);
+        }
+        table
+    }
+
+    fn solve_block(&mut self, table: &mut LocalTable, start: usize, len: usize) {
+        let mut state: Vec<u8> = vec![INVALID; len];
+        let mut score: Vec<i32> = vec![0; len];
+        let mut terminal: Vec<i32> = vec![NO_TERMINAL; len];
+        let mut nof_quiet: Vec<u8> = vec![0; len];
+        let mut levels: Vec<Vec<u32>> = vec![];
+        let push = |levels: &mut Vec<Vec<u32>>, level: u32, i: usize| {
+            if levels.len() <= level as usize {
+                levels.resize(level as usize + 1, vec![]);
+            }
+            levels[level as usize].push(i as u32);
+        };
+
+        for i in 0..len {
+            let (squares, turn) = table.decode_index(start + i);
+            let Some(board) = self.board_at(table, &squares, turn) else {
+                continue;
+            };
+            let (quiet, best_terminal, has_moves) = self.classify_moves(table, &board);
+            nof_quiet[i] = quiet;
+            terminal[i] = best_terminal;
+            state[i] = UNRESOLVED;
+            if !has_moves {
+                if board.nof_checkers > 0 {
+                    score[i] = loss(0);
+                    state[i] = RESOLVED;
+                    push(&mut levels, 0, i);
+                } else {
+                    state[i] = DRAWN; //stalemate
+                }
+            } else if best_terminal > 0 {
+                push(&mut levels, dtz(best_terminal), i); //unless a quiet move wins sooner
+            } else if quiet == 0 && best_terminal < 0 {
+                score[i] = best_terminal;
+                state[i] = RESOLVED;
+                push(&mut levels, dtz(best_terminal), i);
+            } else if quiet == 0 {
+                state[i] = DRAWN;
+            }
+        }
+
+        let mut level: usize = 0;
+        while level < levels.len() {
+            let current: Vec<u32> = std::mem::take(&mut levels[level]);
+            for i in current.into_iter().map(|i| i as usize) {
+                match state[i] {
+                    UNRESOLVED => {
+                        score[i] = win(level as u32);
+                        state[i] = RESOLVED;
+                    }
+                    RESOLVED if dtz(score[i]) == level as u32 => {}
+                    _ => continue,
+                }
+                state[i] = PROPAGATED;
+                let d: u32 = level as u32;
+                let (squares, turn) = table.decode_index(start + i);
+                for pred in self.unmoves(table, &squares, turn) {
+                    let p: usize = pred - start;
+                    if state[p] != UNRESOLVED {
+                        continue;
+                    }
+                    if score[i] < 0 {
+                        score[p] = win(d + 1);
+                        state[p] = RESOLVED;
+                        push(&mut levels, d + 1, p);
+                        continue;
+                    }
+                    nof_quiet[p] -= 1;
+                    if nof_quiet[p] > 0 || terminal[p] > 0 {
+                        continue; //a pending terminal win is resolved at its own level
+                    }
+                    if terminal[p] == 0 {
+                        state[p] = DRAWN;
+                    } else {
+                        let dist: u32 = if terminal[p] == NO_TERMINAL { d + 1 } else { (d + 1).max(dtz(terminal[p])) };
+                        score[p] = loss(dist);
+                        state[p] = RESOLVED;
+                        push(&mut levels, dist, p);
+                    }
+                }
+            }
+            level += 1;
+        }
+
+        for i in 0..len {
+            table.data[start + i] = if state[i] >= RESOLVED { encode(score[i]) } else { 0 };
+        }
+    }
+
+    /// Legal position of the table, None if squares are shared, pawns are on the first or last rank or the side not
+    /// to move is in check
+    fn board_at(&self, table: &LocalTable, squares: &[u32], turn: u32) -> Option<Board> {
+        let mut pieces: [u64; 12] = [0; 12];
+        for (slot, piece) in table.slots.iter().enumerate() {
+            let sqr_bb: u64 = 1 << squares[slot];
+            if pieces.iter().any(|bb| bb & sqr_bb != 0) {
+                return None;
+            }
+            if piece % NOF_PIECE_TYPES_U == W_PAWN_U && sqr_bb & (RANKS[0] | RANKS[7]) != 0 {
+                return None;
+            }
+            pieces[*piece] |= sqr_bb;
+        }
+        let board: Board = board_from_pieces(pieces, turn, None, self.move_gen, self.zobrist);
+        let opp_king: usize = if turn == WHITE { B_KING_U } else { W_KING_U };
+        let mover_occupation: u64 = if turn == WHITE { board.white_occupation } else { board.black_occupation };
+        let checkers: u64 = self.move_gen.attackers_to(&board, board.pieces[opp_king].trailing_zeros() as usize, board.total_occupation());
+        (checkers & mover_occupation == 0).then_some(board)
+    }
+
+    /// (quiet moves, best terminal score, has legal moves)
+    fn classify_moves(&mut self, table: &LocalTable, board: &Board) -> (u8, i32, bool) {
+        let generated: usize = self.move_gen.generate_legal(
+            board,
+            board.turn,
+            &mut self.move_arr,
+            &mut self.pseudo_move_arr,
+            0,
+            false,
+            false,
+            GenType::All,
+        );
+        let mut quiet: u8 = 0;
+        let mut best_terminal: i32 = NO_TERMINAL;
+        for mov in self.move_arr[..generated].iter().copied() {
+            let moved: usize = _move::get_moved_piece(mov) as usize;
+            if !_move::is_eating(mov) && moved % NOF_PIECE_TYPES_U != W_PAWN_U {
+                quiet += 1;
+                continue;
+            }
+            let child: [u64; 12] = pieces_after(&board.pieces, mov);
+            let child_score: i32 = if _move::is_double_push(mov) {
+                let ep_square: u32 = (_move::get_init(mov) + _move::get_target(mov)) / 2;
+                let child_board: Board = board_from_pieces(child, board.turn ^ 1, Some(ep_square), self.move_gen, self.zobrist);
+                self.tablebase.probe_score(&child_board, self.move_gen, Some(table))
+            } else {
+                self.tablebase.lookup(&child, board.turn ^ 1, Some(table))
+            }
+            .expect("tables the generated one converts to exist");
+            best_terminal = best_terminal.max(after_zeroing(child_score));
+        }
+        (quiet, best_terminal, generated > 0)
+    }
+
+    /// Indices of the positions where the side not to move just made a quiet move to reach **squares**
+    fn unmoves(&self, table: &LocalTable, squares: &[u32; MAX_LOCAL_PIECES as usize], turn: u32) -> Vec<usize> {
+        let mover: u32 = turn ^ 1;
+        let n: usize = table.slots.len();
+        let occupied: u64 = squares[..n].iter().fold(0, |occ, sqr| occ | 1 << sqr);
+        let mut preds: Vec<usize> = vec![];
+        for (slot, piece) in table.slots.iter().enumerate() {
+            let kind: usize = piece % NOF_PIECE_TYPES_U;
+            if (*piece >= B_PAWN_U) as u32 != mover || kind == W_PAWN_U {
+                continue;
+            }
+            let mut from_sqrs: u64 = piece_attacks(kind, squares[slot] as usize, occupied, self.move_gen) & !occupied;
+            while from_sqrs != 0 {
+                let mut pred: [u32; MAX_LOCAL_PIECES as usize] = *squares;
+                pred[slot] = from_sqrs.trailing_zeros();
+                from_sqrs &= from_sqrs - 1;
+                preds.push(table.index(&pred[..n], mover));
+            }
+        }
+        preds
+    }
+}
+
+pub(crate) fn board_from_pieces(pieces: [u64; 12], turn: u32, ep_square: Option<u32>, move_gen: &MoveGen, zobrist: &Zobrist) -> Board {
+    let white_occupation: u64 = pieces[W_PAWN_U..=W_KING_U].iter().fold(0, |occ, bb| occ | bb);
+    let black_occupation: u64 = pieces[B_PAWN_U..=B_KING_U].iter().fold(0, |occ, bb| occ | bb);
+    let major_minor_count: u32 = (W_KNIGHT_U..W_KING_U)
+        .map(|kind| (pieces[kind] | pieces[kind + NOF_PIECE_TYPES_U]).count_ones())
+        .sum();
+    Board::board_with(
+        pieces,
+        white_occupation,
+        black_occupation,
+        turn,
+        1,
+        1,
+        1,
+        1,
+        ep_square,
+        major_minor_count,
+        move_gen,
+        zobrist,
+        0,
+    )
+}
+
+fn piece_attacks(kind: usize, sqr: usize, occupied: u64, move_gen: &MoveGen) -> u64 {
+    let slide = |cardinal: bool| move_gen.get_sliding_for(sqr, move_gen.get_relevant_blockers(sqr, occupied, cardinal), cardinal);
+    match kind {
+        W_BISHOP_U => slide(false),
+        W_ROOK_U => slide(true),
+        W_QUEEN_U => slide(false) | slide(true),
+        _ => move_gen.attack_bbs[kind][sqr],
+    }
+}
+
+pub(crate) fn pieces_after(pieces: &[u64; 12], mov: u32) -> [u64; 12] {
+    let mut after: [u64; 12] = *pieces;
+    let moved: usize = _move::get_moved_piece(mov) as usize;
+    let to: u32 = _move::get_target(mov);
+    after[moved] ^= 1 << _move::get_init(mov);
+    if _move::is_en_passant(mov) {
+        let (victim, victim_sqr) = if moved == W_PAWN_U { (B_PAWN_U, to - 8) } else { (W_PAWN_U, to + 8) };
+        after[victim] ^= 1 << victim_sqr;
+    } else if let Some(eaten) = _move::eaten_piece(mov) {
+        after[eaten as usize] ^= 1 << to;
+    }
+    let placed: usize = if _move::is_promotion(mov) { _move::get_promotion_piece(mov) as usize } else { moved };
+    after[placed] |= 1 << to;
+    after
+}
+
+/// Sum of the pawns' advancement, pawn moves always increase it
+fn pawn_potential(table: &LocalTable, squares: &[u32]) -> Option<u32> {
+    let mut occupied: u64 = 0;
+    let mut potential: u32 = 0;
+    for slot in std::iter::once(&table.key_slot).chain(table.pawn_slots.iter()) {
+        let sqr: u32 = squares[*slot];
+        if occupied & (1 << sqr) != 0 || (1u64 << sqr) & (RANKS[0] | RANKS[7]) != 0 {
+            return None;
+        }
+        occupied |= 1 << sqr;
+        potential += if table.slots[*slot] == W_PAWN_U { sqr / 8 } else { 7 - sqr / 8 };
+    }
+    Some(potential)
+}
+
+/// Colors swapped and the board mirrored vertically
+fn flip(pieces: &[u64; 12]) -> [u64; 12] {
+    std::array::from_fn(|p| pieces[(p + NOF_PIECE_TYPES_U) % 12].swap_bytes())
+}
+
+fn only_kings(counts: &[u32; 12]) -> bool {
+    (W_PAWN_U..W_KING_U).all(|kind| counts[kind] == 0 && counts[kind + NOF_PIECE_TYPES_U] == 0)
+}
+
+pub(crate) fn material_key(counts: &[u32; 12]) -> u64 {
+    counts.iter().enumerate().fold(0, |key, (p, count)| key | (*count as u64) << (4 * p))
+}
+
+/// Counts with the stronger side as white: more material, ties by the more valuable pieces
+fn canonical_counts(counts: &[u32; 12]) -> [u32; 12] {
+    let strength = |offset: usize| {
+        let value: i32 = (W_PAWN_U..W_KING_U)
+            .map(|kind| counts[offset + kind] as i32 * PIECE_MATERIAL_VALUE[kind] as i32)
+            .sum();
+        let pieces: Vec<u32> = (W_PAWN_U..W_KING_U).rev().map(|kind| counts[offset + kind]).collect();
+        (value, pieces)
+    };
+    if strength(B_PAWN_U) > strength(W_PAWN_U) {
+        std::array::from_fn(|p| counts[(p + NOF_PIECE_TYPES_U) % 12])
+    } else {
+        *counts
+    }
+}
+
+const PIECE_LETTERS: [char; 5] = ['P', 'N', 'B', 'R', 'Q'];
+
+fn material_name(counts: &[u32; 12]) -> String {
+    let side = |offset: usize| -> String {
+        std::iter::once('K')
+            .chain((W_PAWN_U..W_KING_U).rev().flat_map(|kind| (0..counts[offset + kind]).map(move |_| PIECE_LETTERS[kind])))
+            .collect()
+    };
+    format!("{}v{}", side(W_PAWN_U), side(B_PAWN_U))
+}
+
+/// Piece counts of e.g. KQvKR, None if malformed or over MAX_LOCAL_PIECES pieces
+fn parse_material(name: &str) -> Option<[u32; 12]> {
+    parse_counts(name).filter(|counts| counts.iter().sum::<u32>() <= MAX_LOCAL_PIECES)
+}
+
+/// Piece counts of a material name of any size, the first side as white
+pub(crate) fn parse_counts(name: &str) -> Option<[u32; 12]> {
+    let (white, black) = name.split_once('v')?;
+    let mut counts: [u32; 12] = [0; 12];
+    for (side, offset) in [(white, W_PAWN_U), (black, B_PAWN_U)] {
+        let pieces: &str = side.strip_prefix('K')?;
+        counts[offset + W_KING_U] = 1;
+        for letter in pieces.chars() {
+            let kind: usize = PIECE_LETTERS.iter().position(|l| *l == letter)?;
+            counts[offset + kind] += 1;
+        }
+    }
+    Some(counts)
+}
+
+/// Canonical materials reachable by one capture or promotion
+fn conversions(counts: &[u32; 12]) -> Vec<[u32; 12]> {
+    let mut converted: Vec<[u32; 12]> = vec![];
+    for offset in [W_PAWN_U, B_PAWN_U] {
+        for kind in W_PAWN_U..W_KING_U {
+            if counts[offset + kind] == 0 {
+                continue;
+            }
+            let mut captured: [u32; 12] = *counts;
+            captured[offset + kind] -= 1;
+            converted.push(canonical_counts(&captured));
+            if kind == W_PAWN_U {
+                for promoted in W_KNIGHT_U..W_KING_U {
+                    let mut promotion: [u32; 12] = captured;
+                    promotion[offset + promoted] += 1;
+                    converted.push(canonical_counts(&promotion));
+                }
+            }
+        }
+    }
+    converted
+}
+
+/// Canonical materials of 3 to **max_pieces** pieces, fewer pieces and pawns first so conversions are generated before
+fn all_materials(max_pieces: u32) -> Vec<String> {
+    let mut materials: Vec<[u32; 12]> = vec![];
+    let mut add = |counts: [u32; 12]| {
+        let canonical: [u32; 12] = canonical_counts(&counts);
+        if !materials.contains(&canonical) {
+            materials.push(canonical);
+        }
+    };
+    let mut base: [u32; 12] = [0; 12];
+    base[W_KING_U] = 1;
+    base[B_KING_U] = 1;
+    let non_kings: Vec<usize> = (W_PAWN_U..W_KING_U).chain(B_PAWN_U..B_KING_U).collect();
+    for first in non_kings.iter() {
+        let mut one: [u32; 12] = base;
+        one[*first] += 1;
+        if max_pieces >= 3 {
+            add(one);
+        }
+        for second in non_kings.iter().filter(|second| *second >= first) {
+            let mut two: [u32; 12] = one;
+            two[*second] += 1;
+            if max_pieces >= 4 {
+                add(two);
+            }
+        }
+    }
+    materials.sort_by_key(|counts| (counts.iter().sum::<u32>(), counts[W_PAWN_U] + counts[B_PAWN_U]));
+    materials.iter().map(material_name).collect()
+}
+
+fn win(dtz: u32) -> i32 {
+    WIN_BASE - dtz as i32
+}
+
+fn loss(dtz: u32) -> i32 {
+    -WIN_BASE + dtz as i32
+}
+
+fn dtz(score: i32) -> u32 {
+    (WIN_BASE - score.abs()) as u32
+}
+
+/// Score of a zeroing move into a position scored **child** for its side to move, the counter starts over there
+fn after_zeroing(child: i32) -> i32 {
+    match child.cmp(&0) {
+        std::cmp::Ordering::Less if dtz(child) <= FIFTY_MOVE_PLIES => win(1),
+        std::cmp::Ordering::Less => win(FIFTY_MOVE_PLIES + 1),
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Greater if dtz(child) <= FIFTY_MOVE_PLIES => loss(1),
+        std::cmp::Ordering::Greater => loss(FIFTY_MOVE_PLIES + 1),
+    }
+}
+
+fn outcome(score: i32) -> (Wdl, u32) {
+    match score.cmp(&0) {
+        std::cmp::Ordering::Less => (Wdl::Loss.with_dtz(dtz(score)), dtz(score)),
+        std::cmp::Ordering::Equal => (Wdl::Draw, 0),
+        std::cmp::Ordering::Greater => (Wdl::Win.with_dtz(dtz(score)), dtz(score)),
+    }
+}
+
+/// Wins as their DTZ, losses as -DTZ - 1 and draws as 0 in a signed byte, long cursed distances are capped
+fn encode(score: i32) -> u8 {
+    let stored: i8 = match score.cmp(&0) {
+        std::cmp::Ordering::Less => -(dtz(score).min(MAX_STORED_DTZ) as i8) - 1,
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Greater => dtz(score).min(MAX_STORED_DTZ) as i8,
+    };
+    stored as u8
+}
+
+fn decode(byte: u8) -> i32 {
+    match byte as i8 {
+        0 => 0,
+        stored if stored > 0 => win(stored as u32),
+        stored => loss((-(stored as i32) - 1) as u32),
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/tablebase_tests.rs"]
+mod tests;
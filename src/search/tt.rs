@@ -97,6 +97,14 @@ impl TranspositionTable {
         }
     }
 
+    ///Forgets every entry, e.g. for a new game
+    pub fn clear(&mut self) {
+        self.clusters.fill(TTCluster {
+            entries: [NULL_ENTRY; CLUSTER_SIZE],
+        });
+        self.generation = 0;
+    }
+
     /// If hit, returns (true, entry) else returns (false, entry_to_replace)
     pub fn probe(&self, key: u64) -> Option<TTEntry> {
         let cluster_index: usize = self.get_cluster_idx(key);
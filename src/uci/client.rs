@@ -0,0 +1,285 @@
+/* Client side of UCI: drives an external engine process */
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::uci::uci_command::{EngineCommand, GoCommand, InfoCommand, OptionDeclaration, PositionCommand, Score};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+///Keywords that end the value of a preceding multi word info field
+const INFO_KEYWORDS: [&str; 16] = [
+    "depth", "seldepth", "multipv", "score", "nodes", "nps", "time", "tbhits", "hashfull", "currmove",
+    "currmovenumber", "cpuload", "pv", "string", "refutation", "currline",
+];
+const OPTION_KEYWORDS: [&str; 5] = ["type", "default", "min", "max", "var"];
+
+///Answer to a go command
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchReply {
+    pub best_move: String,
+    pub ponder: Option<String>,
+    pub info: Vec<InfoCommand>, //every info line sent during the search, in order
+}
+
+impl SearchReply {
+    ///Score of the deepest info line that has one
+    pub fn score(&self) -> Option<Score> {
+        self.info.iter().rev().find_map(|info| info.score)
+    }
+}
+
+///UCI engine launched as a local child process. <br>
+///Lines are read on a separate thread, so every read can time out. The engine is sent quit when dropped.
+pub struct UciClient {
+    pub name: String, //id name, the command until the engine sends one
+    pub author: Option<String>,
+    pub options: Vec<OptionDeclaration>, //as declared in reply to uci
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl UciClient {
+    ///Spawns **command** and waits for uciok
+    pub fn launch(command: &str, args: &[String]) -> Result<Self, String> {
+        let mut child: Child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("Could not launch {}: {}", command, err))?;
+        let stdin: ChildStdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client: Self = Self {
+            name: command.to_string(),
+            author: None,
+            options: vec![],
+            child,
+            stdin,
+            lines,
+        };
+        client.send("uci")?;
+        let deadline: Instant = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            match client.read(deadline)? {
+                Some(EngineCommand::ID(name)) => client.name = name,
+                Some(EngineCommand::Author(author)) => client.author = Some(author),
+                Some(EngineCommand::Option(option)) => client.options.push(option),
+                Some(EngineCommand::UCIOk) => return Ok(client),
+                _ => {}
+            }
+        }
+    }
+
+    ///Writes one raw command line
+    pub fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("{} stopped reading: {}", self.name, err))
+    }
+
+    ///Next line from the engine, None if it is not a known engine command
+    pub fn read(&self, deadline: Instant) -> Result<Option<EngineCommand>, String> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(parse_engine_command(&line)),
+            Err(RecvTimeoutError::Timeout) => Err(format!("{} did not answer in time", self.name)),
+            Err(RecvTimeoutError::Disconnected) => Err(format!("{} exited", self.name)),
+        }
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.send(&format!("setoption name {} value {}", name, value))
+    }
+
+    ///Sends isready and waits for readyok
+    pub fn is_ready(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        let deadline: Instant = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.read(deadline)? != Some(EngineCommand::ReadyOk) {}
+        Ok(())
+    }
+
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        self.is_ready()
+    }
+
+    pub fn position(&mut self, position: &PositionCommand) -> Result<(), String> {
+        self.send(&position.to_string())
+    }
+
+    pub fn go(&mut self, go: &GoCommand) -> Result<(), String> {
+        self.send(&go.to_string())
+    }
+
+    pub fn stop(&mut self) -> Result<(), String> {
+        self.send("stop")
+    }
+
+    ///Collects info lines until bestmove, the engine is sent stop if it does not answer within **timeout**
+    pub fn wait_best_move(&mut self, timeout: Duration) -> Result<SearchReply, String> {
+        let mut deadline: Instant = Instant::now() + timeout;
+        let mut stopped: bool = false;
+        let mut info: Vec<InfoCommand> = vec![];
+        loop {
+            match self.read(deadline) {
+                Ok(Some(EngineCommand::Info(line))) => info.push(line),
+                Ok(Some(EngineCommand::BestMove(best_move, ponder))) => {
+                    return Ok(SearchReply { best_move, ponder, info });
+                }
+                Ok(_) => {}
+                Err(_) if !stopped && self.child.try_wait().is_ok_and(|status| status.is_none()) => {
+                    self.stop()?;
+                    stopped = true;
+                    deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    ///position then go, and the reply
+    pub fn search(&mut self, position: &PositionCommand, go: &GoCommand, timeout: Duration) -> Result<SearchReply, String> {
+        self.position(position)?;
+        self.go(go)?;
+        self.wait_best_move(timeout)
+    }
+}
+
+impl Drop for UciClient {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline: Instant = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+///One line sent by an engine, unknown commands and malformed known ones give None
+pub fn parse_engine_command(line: &str) -> Option<EngineCommand> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match *parts.first()? {
+        "id" => match *parts.get(1)? {
+            "name" => Some(EngineCommand::ID(parts[2..].join(" "))),
+            "author" => Some(EngineCommand::Author(parts[2..].join(" "))),
+            _ => None,
+        },
+        "uciok" => Some(EngineCommand::UCIOk),
+        "readyok" => Some(EngineCommand::ReadyOk),
+        "bestmove" => {
+            let best_move: String = parts.get(1)?.to_string();
+            let ponder: Option<String> = match parts.get(2) {
+                Some(&"ponder") => Some(parts.get(3)?.to_string()),
+                _ => None,
+            };
+            Some(EngineCommand::BestMove(best_move, ponder))
+        }
+        "info" => parse_info(&parts[1..]).map(EngineCommand::Info),
+        "option" => parse_option(&parts[1..]).map(EngineCommand::Option),
+        _ => None,
+    }
+}
+
+///Unknown fields are skipped, so engine specific ones such as our cutoff counts do no harm
+fn parse_info(parts: &[&str]) -> Option<InfoCommand> {
+    let mut info: InfoCommand = InfoCommand::default();
+    let mut i: usize = 0;
+    while i < parts.len() {
+        let value: Option<&str> = parts.get(i + 1).copied();
+        match parts[i] {
+            "depth" => info.depth = Some(value?.parse().ok()?),
+            "seldepth" => info.seldepth = Some(value?.parse().ok()?),
+            "multipv" => info.multipv = Some(value?.parse().ok()?),
+            "nodes" => info.nodes = Some(value?.parse().ok()?),
+            "nps" => info.nps = Some(value?.parse().ok()?),
+            "time" => info.time = Some(value?.parse().ok()?),
+            "tbhits" => info.tbhits = Some(value?.parse().ok()?),
+            "hashfull" => info.hashfull = Some(value?.parse().ok()?),
+            "currmove" => info.currmove = Some(value?.to_string()),
+            "score" => {
+                let amount: i32 = parts.get(i + 2)?.parse().ok()?;
+                info.score = match value? {
+                    "cp" => Some(Score::Cp(amount)),
+                    "mate" => Some(Score::Mate(amount)),
+                    _ => return None,
+                };
+                i += 1;
+            }
+            "lowerbound" | "upperbound" => {
+                info.lowerbound |= parts[i] == "lowerbound";
+                info.upperbound |= parts[i] == "upperbound";
+                i += 1;
+                continue;
+            }
+            "string" => {
+                info.string = Some(parts[i + 1..].join(" "));
+                break;
+            }
+            "pv" => {
+                let end: usize = parts[i + 1..]
+                    .iter()
+                    .position(|part| INFO_KEYWORDS.contains(part))
+                    .map_or(parts.len(), |len| i + 1 + len);
+                info.pv = parts[i + 1..end].iter().map(|mov| mov.to_string()).collect();
+                i = end;
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    Some(info)
+}
+
+///Names and values may contain spaces, they run until the next keyword
+fn parse_option(parts: &[&str]) -> Option<OptionDeclaration> {
+    if parts.first() != Some(&"name") {
+        return None;
+    }
+    let mut option: OptionDeclaration = OptionDeclaration::default();
+    let mut i: usize = 0;
+    while i < parts.len() {
+        let end: usize = parts[i + 1..]
+            .iter()
+            .position(|part| OPTION_KEYWORDS.contains(part))
+            .map_or(parts.len(), |len| i + 1 + len);
+        let value: String = parts[i + 1..end].join(" ");
+        match parts[i] {
+            "name" => option.name = value,
+            "type" => option.option_type = value,
+            "default" => option.default = Some(if value == "<empty>" { String::new() } else { value }),
+            "min" => option.min = Some(value.parse().ok()?),
+            "max" => option.max = Some(value.parse().ok()?),
+            "var" => option.vars.push(value),
+            _ => return None,
+        }
+        i = end;
+    }
+    (!option.name.is_empty() && !option.option_type.is_empty()).then_some(option)
+}
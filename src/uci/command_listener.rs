@@ -12,11 +12,11 @@ use iced::futures::lock::Mutex;
 use crate::{
     game::cpu_game::CpuGame,
     repr::{
-        _move::{self, NULL_MOVE}, types::WHITE,
+        _move::{self, NULL_MOVE}, board::Board, perft, position::Position, types::WHITE,
     },
-    search::search_config::SearchMode,
-    uci::uci_command::{_Option::Ponder, ArbiterCommand, GoCommand, PositionCommand},
-    utils::fen_tool::is_valid_fen,
+    search::{nnue::Network, search_config::{EvalKind, SearchMode, DEFAULT_TB_PROBE_DEPTH}, searcher::MAX_SEARCH_DEPTH, syzygy::SyzygyTablebase, tablebase::LocalTablebase},
+    uci::uci_command::{_Option::{self, BookFile, Chess960, EvalFile, OwnBook, Ponder, SyzygyPath, SyzygyProbeDepth, TablebasePath, UseNNUE}, ArbiterCommand, GoCommand, PositionCommand},
+    utils::{fen_tool::is_valid_fen, polyglot::PolyglotBook},
 };
 
 pub async fn listen(cpu_game: CpuGame) {
@@ -36,24 +36,131 @@ pub async fn listen(cpu_game: CpuGame) {
                     ArbiterCommand::UCI => {
                         println!("id name Rusty");
                         //println!("option name Ponder type check default true");
+                        println!("option name UseNNUE type check default false");
+                        println!("option name EvalFile type string default <empty>");
+                        println!("option name UCI_Chess960 type check default false");
+                        println!("option name OwnBook type check default false");
+                        println!("option name BookFile type string default <empty>");
+                        println!("option name TablebasePath type string default <empty>");
+                        println!("option name SyzygyPath type string default <empty>");
+                        println!(
+                            "option name SyzygyProbeDepth type spin default {} min 1 max {}",
+                            DEFAULT_TB_PROBE_DEPTH, MAX_SEARCH_DEPTH
+                        );
                         println!("uciok");
                     }
                     ArbiterCommand::Display => {
                         println!("{}", display_board.to_string());
                     }
+                    ArbiterCommand::Eval => {
+                        if let Some(handle) = active_search_thread.take() {
+                            cpu_game = Some(handle.join().unwrap());
+                        }
+                        let searcher = &cpu_game.as_ref().unwrap().searcher;
+                        if searcher.search_config.eval_kind == EvalKind::Nnue {
+                            println!("info string UseNNUE is on, the trace shows the handcrafted eval");
+                        }
+                        println!(
+                            "{}",
+                            searcher.evaluator.trace(display_board.pieces, display_board.turn, display_board.late_game_phase)
+                        );
+                    }
                     ArbiterCommand::IsReady => {
                         println!("readyok");
                     }
                     ArbiterCommand::SetOption(o) => {
+                        if let Some(handle) = active_search_thread.take() {
+                            cpu_game = Some(handle.join().unwrap());
+                        }
+                        let cpu_g: &mut CpuGame = cpu_game.as_mut().unwrap();
                         match o {
                             Ponder(_) => {
                                 //can ignore safely, ponder if get "go ponder" else don't, no need for engine to know if ponder is enabled or not
                             }
+                            UseNNUE(use_nnue) => {
+                                cpu_g.searcher.search_config.eval_kind =
+                                    if use_nnue { EvalKind::Nnue } else { EvalKind::Handcrafted };
+                            }
+                            Chess960(chess960) => {
+                                //takes effect with the next position command
+                                cpu_g.chess960 = chess960;
+                            }
+                            EvalFile(path) => match Network::load(&path) {
+                                Ok(network) => {
+                                    cpu_g.searcher.network = Some(Arc::new(network));
+                                }
+                                Err(err) => {
+                                    println!("info string Error loading network: {}", err);
+                                }
+                            },
+                            OwnBook(own_book) => {
+                                cpu_g.own_book = own_book;
+                            }
+                            BookFile(path) => match PolyglotBook::load(&path) {
+                                Ok(book) => {
+                                    cpu_g.book = Some(book);
+                                }
+                                Err(err) => {
+                                    println!("info string Error loading book: {}", err);
+                                }
+                            },
+                            //the search holds one tablebase, the last path set wins and an unset path clears it
+                            TablebasePath(None) | SyzygyPath(None) => {
+                                cpu_g.searcher.tablebase = None;
+                            }
+                            TablebasePath(Some(path)) => match LocalTablebase::load(&path) {
+                                Ok(tablebase) => {
+                                    cpu_g.searcher.tablebase = Some(Arc::new(tablebase));
+                                }
+                                Err(err) => {
+                                    println!("info string Error loading tablebases: {}", err);
+                                }
+                            },
+                            SyzygyPath(Some(path)) => match SyzygyTablebase::open(&path) {
+                                Ok(tablebase) => {
+                                    cpu_g.searcher.tablebase = Some(Arc::new(tablebase));
+                                }
+                                Err(err) => {
+                                    println!("info string Error loading Syzygy tablebases: {}", err);
+                                }
+                            },
+                            SyzygyProbeDepth(depth) => {
+                                cpu_g.searcher.search_config.tb_probe_depth = depth;
+                            }
                         }
                     }
                     ArbiterCommand::UCINewGame => {
                         //can ignore safely
                     }
+                    ArbiterCommand::Go(gc) if gc.is_valid() && gc.perft.is_some() => {
+                        //counted on the search thread, so stop and quit are still read
+                        if let Some(handle) = active_search_thread.take() {
+                            cpu_game = Some(handle.join().unwrap());
+                        }
+
+                        let game: Box<CpuGame> = cpu_game.take().unwrap();
+                        let kill_switch_clone = search_kill_switch.clone();
+                        kill_switch_clone.store(false, Relaxed);
+                        active_search_thread = Some(std::thread::Builder::new()
+                                .name("uci-perft-thread".into())
+                                .spawn(move || {
+                            let mut pos: Position = game.position.clone();
+                            pos.nnue = None;
+                            match perft::perft_divide_until(gc.perft.unwrap(), &mut pos, &game.move_gen, &game.zobrist, &kill_switch_clone) {
+                                Some(divide) => {
+                                    for (mov, nodes) in divide.iter() {
+                                        println!("{}: {}", _move::to_uci(*mov, &pos.board), nodes);
+                                    }
+                                    println!();
+                                    println!("Nodes searched: {}", divide.iter().map(|(_, nodes)| nodes).sum::<u64>());
+                                }
+                                None => {
+                                    println!("info string Perft stopped");
+                                }
+                            }
+                            game
+                        }).unwrap());
+                    }
                     ArbiterCommand::Go(gc) if gc.is_valid() => {
                         //TODO add ponder case
                         //join possible previous search thread before starting a new one
@@ -68,6 +175,10 @@ pub async fn listen(cpu_game: CpuGame) {
                                 .name("uci-search-thread".into())
                                 .stack_size(32 * 1024 * 1024)
                                 .spawn(move || {
+                            if let Some(book_move) = game.book_move() {
+                                println!("bestmove {}", _move::to_uci(book_move, &game.position.board));
+                                return game;
+                            }
                             if let Some(movetime) = gc.movetime {
                                 game.searcher.search_config.search_mode =
                                     SearchMode::static_time_with_margin(movetime);
@@ -90,12 +201,13 @@ pub async fn listen(cpu_game: CpuGame) {
                                 Some(kill_switch_clone),
                             );
                             let best_move: u32 = game.searcher.collect_best_move().unwrap_or(NULL_MOVE);
+                            let board: &Board = &game.position.board;
                             match game.searcher.collect_ponder_move() {
                                 Some(pm) => {
-                                    println!("bestmove {} ponder {}", _move::to_string(best_move, true), _move::to_string(pm, true))
+                                    println!("bestmove {} ponder {}", _move::to_uci(best_move, board), _move::to_uci(pm, board))
                                 }
                                 None => {
-                                    println!("bestmove {}", _move::to_string(best_move, true))
+                                    println!("bestmove {}", _move::to_uci(best_move, board))
                                 }
                             }
                             return game;
@@ -173,6 +285,7 @@ pub(super) fn parse_command(line: &str) -> Option<ArbiterCommand> {
     match parts[0] {
         "uci" => Some(ArbiterCommand::UCI),
         "d" => Some(ArbiterCommand::Display),
+        "eval" => Some(ArbiterCommand::Eval),
         "isready" => Some(ArbiterCommand::IsReady),
         "position" if !is_invalid_pos_command(&parts) => {
             let moves_idx: Option<usize> = parts.iter().position(|&x| x == "moves");
@@ -206,6 +319,7 @@ pub(super) fn parse_command(line: &str) -> Option<ArbiterCommand> {
             }
         }
         "go" => parse_go_command(&parts).map(ArbiterCommand::Go),
+        "setoption" => parse_setoption_command(&parts).map(ArbiterCommand::SetOption),
         "ucinewgame" => Some(ArbiterCommand::UCINewGame),
         "stop" => Some(ArbiterCommand::Stop),
         "quit" => Some(ArbiterCommand::Quit),
@@ -216,6 +330,10 @@ pub(super) fn parse_command(line: &str) -> Option<ArbiterCommand> {
 fn parse_go_command(parts: &[&str]) -> Option<GoCommand> {
     let ponder = parts.contains(&"ponder");
 
+    //perft counts instead of searching, whatever else is given
+    if let Some(depth) = parse_go_value(parts, "perft")? {
+        return Some(GoCommand::new_perft(depth));
+    }
     if let Some(movetime) = parse_go_value(parts, "movetime")? {
         return Some(GoCommand::new_movetime_tc(ponder, movetime));
     }
@@ -231,9 +349,41 @@ fn parse_go_command(parts: &[&str]) -> Option<GoCommand> {
         binc: parse_go_value(parts, "binc")?,
         movetime: None,
         depth: None,
+        perft: None,
     })
 }
 
+/// Tablebase directories from an option **value**, None if it is empty or the `<empty>` default
+fn tablebase_path(value: String) -> Option<String> {
+    Some(value).filter(|path| !path.is_empty() && path != "<empty>")
+}
+
+/// setoption name <id> [value <x>], names are case insensitive and values may contain spaces
+fn parse_setoption_command(parts: &[&str]) -> Option<_Option> {
+    let name_idx: usize = parts.iter().position(|&part| part == "name")?;
+    let value_idx: Option<usize> = parts.iter().position(|&part| part == "value");
+    //None if value comes before name
+    let name: String = parts.get(name_idx + 1..value_idx.unwrap_or(parts.len()))?.join(" ");
+    let value: Option<String> = value_idx.map(|idx| parts[idx + 1..].join(" "));
+
+    match name.to_lowercase().as_str() {
+        "ponder" => Some(Ponder(name)),
+        "usennue" => value?.parse::<bool>().ok().map(UseNNUE),
+        "evalfile" => value.filter(|path| !path.is_empty()).map(EvalFile),
+        "uci_chess960" => value?.parse::<bool>().ok().map(Chess960),
+        "ownbook" => value?.parse::<bool>().ok().map(OwnBook),
+        "bookfile" => value.filter(|path| !path.is_empty()).map(BookFile),
+        "tablebasepath" => value.map(tablebase_path).map(TablebasePath),
+        "syzygypath" => value.map(tablebase_path).map(SyzygyPath),
+        "syzygyprobedepth" => value?
+            .parse::<usize>()
+            .ok()
+            .filter(|depth| (1..=MAX_SEARCH_DEPTH).contains(depth))
+            .map(SyzygyProbeDepth),
+        _ => None,
+    }
+}
+
 fn parse_go_value<T: FromStr>(parts: &[&str], name: &str) -> Option<Option<T>> {
     let Some(idx) = parts.iter().position(|&part| part == name) else {
         return Some(None);
@@ -1,3 +1,4 @@
+pub mod client;
 pub mod command_listener;
 pub mod uci_command;
 
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::{
     repr::_move, search::searcher::MAX_SEARCH_DEPTH, utils::fen_tool::DEFAULT_FEN,
 };
@@ -5,6 +7,7 @@ use crate::{
 pub enum ArbiterCommand {
     UCI,
     Display,
+    Eval,
     IsReady,
     SetOption(_Option),
     UCINewGame,
@@ -15,8 +18,15 @@ pub enum ArbiterCommand {
     Stop,
 }
 
+///Sent by the engine, what UciClient reads back
+#[derive(Clone, Debug, PartialEq)]
 pub enum EngineCommand {
     ID(String),
+    Author(String),
+    Option(OptionDeclaration),
+    UCIOk,
+    ReadyOk,
+    Info(InfoCommand),
     BestMove(String, Option<String>),
 }
 
@@ -38,12 +48,55 @@ impl EngineCommand {
 #[derive(Debug)]
 pub enum _Option {
     Ponder(String), //option name
+    UseNNUE(bool),
+    EvalFile(String), //path to network file
+    Chess960(bool),
+    OwnBook(bool),
+    BookFile(String), //path to Polyglot book
+    TablebasePath(Option<String>), //directory of local tablebase files, replaces Syzygy tables, None clears them
+    SyzygyPath(Option<String>), //directories of Syzygy files, separated as in PATH, replaces local tables, None clears them
+    SyzygyProbeDepth(usize),
 }
 
 pub enum OptionType {
     Check(String), //type name
 }
 
+///option name <id> type <t> [default <x>] [min <x>] [max <x>] [var <x>]*
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptionDeclaration {
+    pub name: String,
+    pub option_type: String, //check, spin, combo, button or string
+    pub default: Option<String>, //"<empty>" is read as an empty string
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub vars: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    Cp(i32),
+    Mate(i32), //in moves, negative if the engine gets mated
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InfoCommand {
+    pub depth: Option<usize>,
+    pub seldepth: Option<usize>,
+    pub multipv: Option<usize>,
+    pub score: Option<Score>,
+    pub lowerbound: bool,
+    pub upperbound: bool,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<u64>, //ms
+    pub tbhits: Option<u64>,
+    pub hashfull: Option<u32>, //permill
+    pub currmove: Option<String>,
+    pub pv: Vec<String>,
+    pub string: Option<String>, //free text, runs to the end of the line
+}
+
 #[derive(Clone)]
 pub struct GoCommand {
     pub ponder: bool,
@@ -53,20 +106,24 @@ pub struct GoCommand {
     pub binc: Option<u64>,
     pub movetime: Option<u64>,
     pub depth: Option<usize>,
+    pub perft: Option<usize>, //divide to this depth instead of searching
 }
 
 impl GoCommand {
     pub fn is_valid(&self) -> bool {
         let has_clock = self.wtime.is_some() && self.btime.is_some();
         let has_partial_clock = self.wtime.is_some() != self.btime.is_some();
-        let mode_count =
-            has_clock as u8 + self.movetime.is_some() as u8 + self.depth.is_some() as u8;
+        let mode_count = has_clock as u8
+            + self.movetime.is_some() as u8
+            + self.depth.is_some() as u8
+            + self.perft.is_some() as u8;
 
         !has_partial_clock
             && mode_count == 1
             && self
                 .depth
                 .map_or(true, |depth| depth <= MAX_SEARCH_DEPTH)
+            && self.perft.is_none_or(|depth| (1..=MAX_SEARCH_DEPTH).contains(&depth))
     }
 
     pub fn new_clock_tc(ponder: bool, wtime: u64, btime: u64, winc: u64, binc: u64) -> Self {
@@ -78,6 +135,7 @@ impl GoCommand {
             binc: Some(binc),
             movetime: None,
             depth: None,
+            perft: None,
         }
     }
 
@@ -90,6 +148,7 @@ impl GoCommand {
             binc: None,
             movetime: Some(movetime),
             depth: None,
+            perft: None,
         }
     }
 
@@ -102,8 +161,46 @@ impl GoCommand {
             binc: None,
             movetime: None,
             depth: Some(depth),
+            perft: None,
         }
     }
+
+    pub fn new_perft(depth: usize) -> Self {
+        Self {
+            ponder: false,
+            wtime: None,
+            btime: None,
+            winc: None,
+            binc: None,
+            movetime: None,
+            depth: None,
+            perft: Some(depth),
+        }
+    }
+}
+
+impl fmt::Display for GoCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "go")?;
+        if self.ponder {
+            write!(f, " ponder")?;
+        }
+        let values = [
+            ("wtime", self.wtime),
+            ("btime", self.btime),
+            ("winc", self.winc),
+            ("binc", self.binc),
+            ("movetime", self.movetime),
+            ("depth", self.depth.map(|d| d as u64)),
+            ("perft", self.perft.map(|d| d as u64)),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                write!(f, " {} {}", name, value)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -136,3 +233,17 @@ impl PositionCommand {
     }
 
 }
+
+impl fmt::Display for PositionCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fen == DEFAULT_FEN {
+            write!(f, "position startpos")?;
+        } else {
+            write!(f, "position fen {}", self.fen)?;
+        }
+        if !self.moves.is_empty() {
+            write!(f, " moves {}", self.moves.join(" "))?;
+        }
+        Ok(())
+    }
+}
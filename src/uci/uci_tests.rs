@@ -1,8 +1,9 @@
 use super::{
+    client::parse_engine_command,
     command_listener::parse_command,
-    uci_command::{ArbiterCommand, GoCommand},
+    uci_command::{ArbiterCommand, EngineCommand, GoCommand, InfoCommand, OptionDeclaration, PositionCommand, Score, _Option},
 };
-use crate::search::searcher::MAX_SEARCH_DEPTH;
+use crate::{search::searcher::MAX_SEARCH_DEPTH, utils::fen_tool::DEFAULT_FEN};
 
 fn parse_go(line: &str) -> Option<GoCommand> {
     match parse_command(line) {
@@ -16,6 +17,11 @@ fn parses_display_command() {
     assert!(matches!(parse_command("d"), Some(ArbiterCommand::Display)));
 }
 
+#[test]
+fn parses_eval_command() {
+    assert!(matches!(parse_command("eval"), Some(ArbiterCommand::Eval)));
+}
+
 #[test]
 fn parses_static_depth() {
     let command = parse_go("go depth 7").expect("valid go command");
@@ -52,3 +58,213 @@ fn rejects_depth_above_search_capacity() {
 
     assert!(!command.is_valid());
 }
+
+#[test]
+fn parses_perft_as_its_own_mode() {
+    let command = parse_go("go perft 5").expect("valid go command");
+
+    assert_eq!(command.perft, Some(5));
+    assert_eq!(command.depth, None);
+    assert!(command.is_valid());
+    assert!(!parse_go("go perft 0").unwrap().is_valid());
+    assert!(parse_command("go perft").is_none());
+    //perft takes precedence over search limits
+    for line in ["go depth 3 perft 2", "go perft 2 movetime 100", "go wtime 1000 btime 1000 perft 2"] {
+        let command = parse_go(line).expect("valid go command");
+        assert_eq!((command.perft, command.depth, command.movetime, command.wtime), (Some(2), None, None, None), "{line}");
+    }
+}
+
+#[test]
+fn parses_nnue_options() {
+    assert!(matches!(
+        parse_command("setoption name UseNNUE value true"),
+        Some(ArbiterCommand::SetOption(_Option::UseNNUE(true)))
+    ));
+    assert!(matches!(
+        parse_command("setoption name usennue value false"),
+        Some(ArbiterCommand::SetOption(_Option::UseNNUE(false)))
+    ));
+    match parse_command("setoption name EvalFile value nets/my net.nnue") {
+        Some(ArbiterCommand::SetOption(_Option::EvalFile(path))) => assert_eq!(path, "nets/my net.nnue"),
+        _ => panic!("expected EvalFile option"),
+    }
+}
+
+#[test]
+fn parses_chess960_option() {
+    assert!(matches!(
+        parse_command("setoption name UCI_Chess960 value true"),
+        Some(ArbiterCommand::SetOption(_Option::Chess960(true)))
+    ));
+    assert!(matches!(
+        parse_command("setoption name uci_chess960 value false"),
+        Some(ArbiterCommand::SetOption(_Option::Chess960(false)))
+    ));
+}
+
+#[test]
+fn parses_book_options() {
+    assert!(matches!(
+        parse_command("setoption name OwnBook value true"),
+        Some(ArbiterCommand::SetOption(_Option::OwnBook(true)))
+    ));
+    match parse_command("setoption name BookFile value books/my book.bin") {
+        Some(ArbiterCommand::SetOption(_Option::BookFile(path))) => assert_eq!(path, "books/my book.bin"),
+        _ => panic!("BookFile should parse"),
+    }
+}
+
+#[test]
+fn parses_tablebase_path() {
+    match parse_command("setoption name TablebasePath value /data/tb") {
+        Some(ArbiterCommand::SetOption(_Option::TablebasePath(path))) => assert_eq!(path.as_deref(), Some("/data/tb")),
+        _ => panic!("TablebasePath should parse"),
+    }
+    for line in ["setoption name TablebasePath value", "setoption name TablebasePath value <empty>"] {
+        assert!(matches!(parse_command(line), Some(ArbiterCommand::SetOption(_Option::TablebasePath(None)))), "{line}");
+    }
+}
+
+#[test]
+fn parses_syzygy_options() {
+    match parse_command("setoption name SyzygyPath value /data/syzygy/345:/data/extra") {
+        Some(ArbiterCommand::SetOption(_Option::SyzygyPath(path))) => assert_eq!(path.as_deref(), Some("/data/syzygy/345:/data/extra")),
+        _ => panic!("SyzygyPath should parse"),
+    }
+    for line in ["setoption name SyzygyPath value", "setoption name SyzygyPath value <empty>"] {
+        assert!(matches!(parse_command(line), Some(ArbiterCommand::SetOption(_Option::SyzygyPath(None)))), "{line}");
+    }
+    assert!(matches!(
+        parse_command("setoption name SyzygyProbeDepth value 4"),
+        Some(ArbiterCommand::SetOption(_Option::SyzygyProbeDepth(4)))
+    ));
+}
+
+#[test]
+fn rejects_malformed_setoption() {
+    for line in [
+        "setoption",
+        "setoption name UseNNUE",
+        "setoption name UseNNUE value maybe",
+        "setoption name EvalFile value",
+        "setoption name UCI_Chess960",
+        "setoption name OwnBook value yes",
+        "setoption name BookFile value",
+        "setoption name TablebasePath",
+        "setoption name SyzygyPath",
+        "setoption name SyzygyProbeDepth value 0",
+        "setoption value 1 name Hash",
+        "setoption value true name UseNNUE",
+        "setoption name SyzygyProbeDepth value deep",
+        "setoption name Hash value 16",
+    ] {
+        assert!(parse_command(line).is_none(), "{line} should be invalid");
+    }
+}
+
+fn parse_info(line: &str) -> InfoCommand {
+    match parse_engine_command(line) {
+        Some(EngineCommand::Info(info)) => info,
+        _ => panic!("{line} should parse as info"),
+    }
+}
+
+#[test]
+fn client_parses_handshake_replies() {
+    assert_eq!(parse_engine_command("id name Rusty 2"), Some(EngineCommand::ID("Rusty 2".into())));
+    assert_eq!(parse_engine_command("id author A. Author"), Some(EngineCommand::Author("A. Author".into())));
+    assert_eq!(parse_engine_command("uciok"), Some(EngineCommand::UCIOk));
+    assert_eq!(parse_engine_command("  readyok "), Some(EngineCommand::ReadyOk));
+    assert_eq!(
+        parse_engine_command("option name SyzygyProbeDepth type spin default 1 min 1 max 50"),
+        Some(EngineCommand::Option(OptionDeclaration {
+            name: "SyzygyProbeDepth".into(),
+            option_type: "spin".into(),
+            default: Some("1".into()),
+            min: Some(1),
+            max: Some(50),
+            vars: vec![],
+        }))
+    );
+    match parse_engine_command("option name Book File type string default <empty>") {
+        Some(EngineCommand::Option(option)) => {
+            assert_eq!(option.name, "Book File");
+            assert_eq!(option.default.as_deref(), Some(""));
+        }
+        _ => panic!("string option should parse"),
+    }
+    match parse_engine_command("option name Style type combo default Normal var Solid var Normal var Risky") {
+        Some(EngineCommand::Option(option)) => assert_eq!(option.vars, ["Solid", "Normal", "Risky"]),
+        _ => panic!("combo option should parse"),
+    }
+    for line in ["", "id", "option type spin", "option name Hash type spin min x", "hello"] {
+        assert!(parse_engine_command(line).is_none(), "{line} should be ignored");
+    }
+}
+
+#[test]
+fn client_parses_info_and_bestmove() {
+    let info = parse_info(
+        "info depth 7 seldepth 12 score cp -35 nodes 40213 tbhits 0 ab-cutoffs 311 stand-pat-cutoffs 97 pv e2e4 e7e5 g1f3",
+    );
+    assert_eq!(info.depth, Some(7));
+    assert_eq!(info.seldepth, Some(12));
+    assert_eq!(info.score, Some(Score::Cp(-35)));
+    assert_eq!(info.nodes, Some(40213));
+    assert_eq!(info.tbhits, Some(0));
+    assert_eq!(info.pv, ["e2e4", "e7e5", "g1f3"]);
+
+    let info = parse_info("info multipv 2 score mate -3 upperbound pv h7h8q nps 1000 time 15 hashfull 20");
+    assert_eq!(info.multipv, Some(2));
+    assert_eq!(info.score, Some(Score::Mate(-3)));
+    assert!(info.upperbound && !info.lowerbound);
+    assert_eq!(info.pv, ["h7h8q"]);
+    assert_eq!((info.nps, info.time, info.hashfull), (Some(1000), Some(15), Some(20)));
+
+    let info = parse_info("info string Error loading book: depth 3 pv");
+    assert_eq!(info.string.as_deref(), Some("Error loading book: depth 3 pv"));
+    assert_eq!(info.depth, None);
+    assert!(parse_engine_command("info depth x").is_none());
+    assert!(parse_engine_command("info score wdl 1").is_none());
+
+    assert_eq!(
+        parse_engine_command("bestmove e2e4 ponder e7e5"),
+        Some(EngineCommand::BestMove("e2e4".into(), Some("e7e5".into())))
+    );
+    assert_eq!(parse_engine_command("bestmove a7a8q"), Some(EngineCommand::BestMove("a7a8q".into(), None)));
+    assert!(parse_engine_command("bestmove").is_none());
+    assert!(parse_engine_command("bestmove e2e4 ponder").is_none());
+}
+
+#[test]
+fn client_commands_round_trip_through_the_listener() {
+    for go in [
+        GoCommand::new_depth_tc(false, 9),
+        GoCommand::new_movetime_tc(true, 250),
+        GoCommand::new_clock_tc(false, 60000, 59000, 1000, 1000),
+        GoCommand::new_perft(4),
+    ] {
+        let line: String = go.to_string();
+        let parsed: GoCommand = parse_go(&line).expect("formatted go command parses");
+        assert_eq!(parsed.to_string(), line);
+        assert_eq!(parsed.ponder, go.ponder);
+    }
+    assert_eq!(GoCommand::new_movetime_tc(true, 250).to_string(), "go ponder movetime 250");
+
+    let startpos = PositionCommand::new(None, vec!["e2e4".into(), "c7c5".into()]);
+    assert_eq!(startpos.to_string(), "position startpos moves e2e4 c7c5");
+    let fen = "8/8/8/8/8/4k3/8/4K2R w K - 0 1";
+    let custom = PositionCommand::new(Some(fen.into()), vec![]);
+    assert_eq!(custom.to_string(), format!("position fen {}", fen));
+    for position in [startpos, custom] {
+        match parse_command(&position.to_string()) {
+            Some(ArbiterCommand::Position(parsed)) => {
+                assert_eq!(parsed.fen, position.fen);
+                assert_eq!(parsed.moves, position.moves);
+            }
+            _ => panic!("formatted position command parses"),
+        }
+    }
+    assert_eq!(PositionCommand::new(None, vec![]).fen, DEFAULT_FEN);
+}
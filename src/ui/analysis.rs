@@ -0,0 +1,152 @@
+/* Background engine analysis for the GUI: a searcher on its own thread streaming every completed depth */
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+};
+
+use iced::futures::{
+    channel::{mpsc, oneshot},
+    Stream,
+};
+
+use crate::{
+    game::pgn::{format_eval, PgnGame},
+    repr::{move_gen::MoveGen, position::Position, types::WHITE},
+    search::{
+        eval::MATE_BOUND,
+        nnue::Network,
+        search_config::{SearchConfig, SearchMode},
+        search_data::IterationInfo,
+        searcher::{Searcher, MAX_SEARCH_DEPTH},
+        tablebase::Tablebase,
+    },
+    ui::messages::Message,
+    utils::{fen_tool::board_to_fen, zobrist::Zobrist},
+};
+
+const SEARCH_THREAD_STACK_SIZE: usize = 32 * 1024 * 1024;
+const EVAL_BAR_SCALE: f32 = 400.0; //centipawns at which white's share of the bar is 10 / 11
+
+///Position to analyse. Subscriptions are told apart by id only, a new id starts a new search.
+#[derive(Clone)]
+pub struct AnalysisRequest {
+    pub id: u64,
+    pub position: Position,
+    pub board_hash_history: Vec<u64>, //as for Searcher::import_position
+    pub search_config: SearchConfig,  //searched to MAX_SEARCH_DEPTH without logging
+    pub network: Option<Arc<Network>>,
+    pub tablebase: Option<Arc<dyn Tablebase>>,
+}
+
+impl AnalysisRequest {
+    ///Analysis of **searcher**'s root with its history, evaluation and tablebase
+    pub fn new(id: u64, searcher: &Searcher) -> Self {
+        Self {
+            id,
+            position: searcher.positions[0].clone(),
+            board_hash_history: searcher.search_data[0].board_hash_history.clone(),
+            search_config: searcher.search_config.clone(),
+            network: searcher.network.clone(),
+            tablebase: searcher.tablebase.clone(),
+        }
+    }
+}
+
+impl Hash for AnalysisRequest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+///One completed depth, readable for the analysed position
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisLine {
+    pub depth: usize,
+    pub sel_depth: usize,
+    pub white_eval: i16, //centipawns from white's point of view
+    pub nodes: u64,
+    pub pv: Vec<String>, //SAN
+}
+
+impl AnalysisLine {
+    pub fn new(info: &IterationInfo, root: &Position, move_gen: &MoveGen, zobrist: &Zobrist) -> Self {
+        let pv: Vec<String> = PgnGame::from_moves(&[], &board_to_fen(&root.board), &info.pv, None, "*", move_gen, zobrist)
+            .map(|line| line.moves.into_iter().map(|mov| mov.san).collect())
+            .unwrap_or_default();
+        Self {
+            depth: info.depth,
+            sel_depth: info.sel_depth,
+            white_eval: if root.board.turn == WHITE { info.eval } else { -info.eval },
+            nodes: info.nodes,
+            pv,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Depth {}/{}  {}  {} nodes",
+            self.depth,
+            self.sel_depth,
+            format_eval(self.white_eval),
+            self.nodes
+        )
+    }
+
+    ///Part of the evaluation bar that is white's, between 0 and 1
+    pub fn white_share(&self) -> f32 {
+        if self.white_eval.abs() >= MATE_BOUND {
+            return if self.white_eval > 0 { 1.0 } else { 0.0 };
+        }
+        1.0 / (1.0 + 10f32.powf(-self.white_eval as f32 / EVAL_BAR_SCALE))
+    }
+}
+
+///Searches the request's position on its own thread until the stream is dropped. <br>
+///Every completed depth is sent as Message::AnalysisUpdate.
+pub fn stream(request: &AnalysisRequest) -> impl Stream<Item = Message> {
+    let request: AnalysisRequest = request.clone();
+    iced::stream::channel(MAX_SEARCH_DEPTH, async move |mut output: mpsc::Sender<Message>| {
+        let kill_switch: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let _stop_on_drop: StopOnDrop = StopOnDrop(kill_switch.clone());
+        let (finished, search_finished) = oneshot::channel::<()>();
+        let id: u64 = request.id;
+        thread::Builder::new()
+            .name("gui-analysis-thread".into())
+            .stack_size(SEARCH_THREAD_STACK_SIZE)
+            .spawn(move || {
+                let move_gen: MoveGen = MoveGen::init();
+                let zobrist: Zobrist = Zobrist::default(); //fixed seed, the same keys as the game's
+                let mut searcher: Searcher = Searcher::from(&request.position, false);
+                searcher.import_position(&request.position, Some(request.board_hash_history));
+                searcher.search_config = SearchConfig {
+                    search_mode: SearchMode::StaticDepth(MAX_SEARCH_DEPTH),
+                    log_diagnostics: false,
+                    log_uci_diagnostics: false,
+                    ..request.search_config
+                };
+                searcher.network = request.network;
+                searcher.tablebase = request.tablebase;
+                searcher.on_iteration = Some(Box::new(move |info: &IterationInfo| {
+                    let _ = output.try_send(Message::AnalysisUpdate(id, info.clone()));
+                }));
+                searcher.start_search(&move_gen, &zobrist, Some(kill_switch));
+                let _ = finished.send(());
+            })
+            .expect("Could not spawn the analysis thread");
+        //the stop guard lives as long as the search, or until the subscription drops this future
+        let _ = search_finished.await;
+    })
+}
+
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Relaxed);
+    }
+}
@@ -1,27 +1,30 @@
-use iced::event::{self};
+use iced::keyboard::{self, key::Named, Key};
 use iced::widget::image::Handle;
 
-use iced::widget::{button, column, container, row, stack, text, Button, Image, TextInput, pick_list};
-use iced::{Border, Color, Element, Shadow, Size, Subscription};
+use iced::widget::{button, column, container, row, scrollable, stack, text, Button, Column, Image, TextInput, pick_list};
+use iced::{Border, Color, Element, Shadow, Size, Subscription, Task};
 
 use iced_aw::{menu::*, Menu, MenuBar};
 
 use iced_dialog::{button as dialog_button, dialog, Dialog};
 
+use crate::ui::analysis::{self, AnalysisLine, AnalysisRequest};
 use crate::ui::image_handle::ImageHandle;
+use crate::ui::timer;
 use crate::ui::messages::Message::{ErrorAcknowledged, GameEndAcknowledged};
 use crate::ui::messages::*;
 
+use crate::game::clock::TimeControl;
 use crate::game::game::Game;
 use crate::repr::board::{RANKS};
 use crate::repr::position::Position;
 use crate::repr::{_move, bitboard, types::*};
 
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 pub fn run_fr() -> iced::Result {
-    iced::application(|| AppState::default(), update, view)
-        //.subscription(|_| AppState::subscription())
+    iced::application(|| AppState::default(), update_with_tasks, view)
+        .subscription(AppState::subscription)
         .resizable(false)
         .window_size(Size::new(1300.0, 700.0))
         .run()
@@ -41,13 +44,24 @@ pub struct AppState {
     show_promotion_dialog: bool,
     show_game_end_dialog: bool,
     game_end_dialog_acknowledged: bool,
+    move_list: Vec<String>, //SAN of game.line()
+    reviewing: bool,        //a move list entry was selected, the board is read-only until the end of the line
+    analysis: Option<AnalysisRequest>, //follows the board while analysis is on
+    analysis_line: Option<AnalysisLine>, //deepest completed depth of the current request
+    analysis_count: u64,
+    time_control: Option<&'static str>, //one of TIME_CONTROL_OPTIONS, None for the first one
 }
 
 impl AppState {
     fn render_main_container(&self) -> Element<'_, Message, iced::Theme, iced::Renderer> {
         let main_content = column![
         self.render_menu_bar(),
-        self.render_board(),
+        row![
+            self.render_eval_bar(),
+            self.render_board(),
+            column![self.render_clocks(), self.render_analysis_panel(), self.render_move_list()].spacing(20.0),
+        ]
+        .spacing(20.0),
         ]
         .width(iced::Length::Fill)
         .height(iced::Length::Fill);
@@ -142,6 +156,96 @@ impl AppState {
         container(content).width(SQR_SIZE).height(SQR_SIZE).into()
     }
 
+    ///White's share of the bar follows the latest analysis, even without one
+    fn render_eval_bar(&self) -> Element<'static, Message, iced::Theme, iced::Renderer> {
+        let white_share: f32 = self.analysis_line.as_ref().map_or(0.5, |line| line.white_share());
+        let white_portion: u16 = (white_share * 1000.0).round() as u16;
+        let part = |color: Color, portion: u16| {
+            container(text(""))
+                .width(iced::Length::Fill)
+                .height(iced::Length::FillPortion(portion))
+                .style(move |_theme| container::Style::default().background(color))
+        };
+        column![part(Color::BLACK, 1000 - white_portion), part(Color::WHITE, white_portion)]
+            .width(20.0)
+            .height(8.0 * SQR_SIZE as f32)
+            .into()
+    }
+
+    fn render_clocks(&self) -> Element<'_, Message, iced::Theme, iced::Renderer> {
+        let Some(clock) = &self.game.clock else {
+            return column![].into();
+        };
+        let now: Instant = Instant::now();
+        let format = |ms: u64| format!("{}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms / 100 % 10);
+        text(format!(
+            "White {}   Black {}",
+            format(clock.remaining(WHITE, now)),
+            format(clock.remaining(BLACK, now))
+        ))
+        .into()
+    }
+
+    fn render_analysis_panel(&self) -> Element<'_, Message, iced::Theme, iced::Renderer> {
+        let (summary, pv): (String, String) = match &self.analysis_line {
+            Some(line) => (line.summary(), line.pv.join(" ")),
+            None if self.analysis.is_some() => ("Analysing...".to_string(), String::new()),
+            None => (String::new(), String::new()),
+        };
+        column![
+            row![
+                button("Analyse").on_press(Message::AnalysisStart),
+                button("Stop").on_press_maybe(self.analysis.is_some().then_some(Message::AnalysisStop)),
+            ]
+            .spacing(5.0),
+            text(summary).size(14),
+            text(pv).size(14).width(250.0),
+        ]
+        .spacing(5.0)
+        .into()
+    }
+
+    ///Moves in SAN with move numbers, the move that led to the board highlighted. Taken back moves are dimmed.
+    fn render_move_list(&self) -> Element<'_, Message, iced::Theme, iced::Renderer> {
+        let black_starts: bool = self.game.board_history[0].turn == BLACK;
+        let mut lines: Column<'_, Message, iced::Theme, iced::Renderer> = column![].spacing(2.0);
+        let mut line = row![].spacing(5.0);
+        for (i, san) in self.move_list.iter().enumerate() {
+            let ply: usize = i + 1;
+            let is_white_move: bool = (i + black_starts as usize).is_multiple_of(2);
+            if is_white_move || i == 0 {
+                let number: usize = (i + black_starts as usize) / 2 + 1;
+                let dots: &str = if is_white_move { "." } else { "..." };
+                line = line.push(text(format!("{}{}", number, dots)).width(40.0));
+            }
+            line = line.push(self.render_move_list_entry(san, ply));
+            if !is_white_move || ply == self.move_list.len() {
+                lines = lines.push(line);
+                line = row![].spacing(5.0);
+            }
+        }
+        column![
+            button("Copy PGN").on_press(Message::CopyPgn),
+            scrollable(lines).height(iced::Length::Fill).width(250.0),
+        ]
+        .spacing(10.0)
+        .into()
+    }
+
+    fn render_move_list_entry(&self, san: &str, ply: usize) -> Element<'_, Message, iced::Theme, iced::Renderer> {
+        let is_current: bool = ply == self.game.ply();
+        let is_taken_back: bool = ply > self.game.ply();
+        button(text(san.to_string()).size(14))
+            .width(80.0)
+            .style(move |_theme, _status| iced::widget::button::Style {
+                background: is_current.then_some(iced::Background::Color(SELECTED_SQUARE_COLOR)),
+                text_color: if is_taken_back { Color::from_rgb(0.5, 0.5, 0.5) } else { Color::BLACK },
+                ..iced::widget::button::Style::default()
+            })
+            .on_press(Message::MoveSelected(ply))
+            .into()
+    }
+
     fn render_menu_bar(&self) -> Element<'_, Message, iced::Theme, iced::Renderer> {
         let white_button: Button<'_, Message, iced::Theme, iced::Renderer> = button("")
             .style(|_theme, _status| input_button_style(true, self.input_side == WHITE))
@@ -157,7 +261,7 @@ impl AppState {
             text("Game"),
             Menu::new(
                 [
-                    Item::new(button("Search").on_press(Message::SearchStart)),
+                    Item::new(button("Analyse").on_press(Message::AnalysisStart)),
                     Item::new(button("Default Position").on_press(Message::NewDefaultPosPressed)),
                     Item::new(button("From FEN").on_press(Message::NewFenPosPressed)),
                     Item::new(
@@ -167,6 +271,20 @@ impl AppState {
                             .width(200.0),
                     ),
                     Item::new(row![white_button, black_button]),
+                    Item::new(pick_list(
+                        TIME_CONTROL_OPTIONS,
+                        Some(self.time_control.unwrap_or(TIME_CONTROL_OPTIONS[0])),
+                        Message::TimeControlSelected,
+                    )),
+                    Item::new(
+                        row![
+                            button("<<").on_press(Message::GotoPly(0)),
+                            button("<").on_press(Message::Unmake),
+                            button(">").on_press(Message::Redo),
+                            button(">>").on_press(Message::GotoPly(usize::MAX)),
+                        ]
+                        .spacing(5.0),
+                    ),
                 ]
                 .into(),
             )
@@ -204,17 +322,81 @@ impl AppState {
         }
     }
 
-    pub fn subscription() -> Subscription<Message> {
-        return event::listen().map(Message::Event);
+    ///Arrow keys step through the move history, Home and End jump to its ends. Running clocks tick. <br>
+    ///The analysis search runs as long as its subscription, dropping it stops the search.
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions: Vec<Subscription<Message>> = vec![keyboard::listen().filter_map(navigation_message)];
+        if let Some(request) = self.analysis.as_ref().filter(|_| !self.game.is_over()) {
+            subscriptions.push(Subscription::run_with(request.clone(), analysis::stream));
+        }
+        if self.game.clock.as_ref().is_some_and(|clock| clock.is_running()) {
+            subscriptions.push(Subscription::run(timer::clock_ticks));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    ///Restarts the analysis on the game's position
+    fn start_analysis(&mut self) {
+        self.analysis_count += 1;
+        self.analysis = Some(AnalysisRequest::new(self.analysis_count, &self.game.searcher));
+        self.analysis_line = None;
+    }
+
+    ///A running analysis moves along with the board
+    fn sync_analysis(&mut self) {
+        if let Some(request) = &self.analysis {
+            if request.position.board.zhash != self.game.position.board.zhash
+                || request.board_hash_history != self.game.searcher.search_data[0].board_hash_history
+            {
+                self.start_analysis();
+            }
+        }
+    }
+
+    ///In games against the CPU, navigation lands on the user's turn when the history allows it
+    fn navigate(&mut self, msg: Message) {
+        match msg {
+            Message::Unmake => {
+                self.game.undo();
+                if self.is_cpu_turn() && self.game.undo().is_none() {
+                    self.game.redo();
+                }
+            }
+            Message::Redo => {
+                self.game.redo();
+                if self.is_cpu_turn() {
+                    self.game.redo();
+                }
+            }
+            Message::GotoPly(ply) => {
+                self.game.goto_ply(ply);
+                if self.is_cpu_turn() {
+                    if self.game.can_redo() {
+                        self.game.redo();
+                    } else {
+                        self.game.undo();
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.reset_state_inputs();
+        self.reset_game_end_dialog();
     }
 
     pub fn reset_state_inputs(&mut self) {
+        self.reviewing = false;
         self.fen_input = String::new();
         self.selected_square = None;
         self.promotion_target_square = None;
         self.selection_target_sqrs.clear();
     }
 
+    ///Only the end of the line can be played on after selecting a move in the move list
+    pub fn is_read_only(&self) -> bool {
+        self.reviewing && self.game.can_redo()
+    }
+
     pub fn is_cpu_turn(&self) -> bool {
         return self.game.position.board.turn != self.user_side;
     }
@@ -224,16 +406,31 @@ impl AppState {
         self.game_end_dialog_acknowledged = false;
     }
 
+    fn sync_move_list(&mut self) {
+        self.move_list = self.game.line_san();
+    }
+
     fn sync_game_end_dialog(&mut self) {
         self.show_game_end_dialog =
             self.game.is_over() && !self.game_end_dialog_acknowledged;
     }
 }
 
+///update, plus the effects that need the runtime
+fn update_with_tasks(state: &mut AppState, msg: Message) -> Task<Message> {
+    let copy_pgn: bool = msg == Message::CopyPgn;
+    update(state, msg);
+    if copy_pgn {
+        iced::clipboard::write(state.game.to_pgn(&[], None))
+    } else {
+        Task::none()
+    }
+}
+
 pub fn update(state: &mut AppState, msg: Message) {
     match msg {
         Message::SquareClicked(sqr) => {
-            if !state.game.is_over() {
+            if !state.game.is_over() && !state.is_read_only() {
                 match state.selected_square {
                     Some(selected_sqr) => {
                         if selected_sqr == sqr {
@@ -327,17 +524,36 @@ pub fn update(state: &mut AppState, msg: Message) {
             state.show_game_end_dialog = false;
             state.game_end_dialog_acknowledged = true;
         }
-        Message::SearchStart => {
-            if !state.game.is_over() {
-                let start: Instant = Instant::now();
-                state
-                    .game
-                    .searcher
-                    .start_search(&state.game.move_gen, &state.game.zobrist, None);
-                let time_took: Duration = start.elapsed();
-                println!("Search finished in {} ms", time_took.as_millis());
-            }
+        Message::TimeControlSelected(label) => {
+            state.time_control = Some(label);
+            state.reset_game_end_dialog();
+            state.game.set_time_control(time_control_option(label));
+        }
+        Message::ClockTick => {
+            state.game.check_flag();
+        }
+        Message::AnalysisStart => {
+            state.start_analysis();
+        }
+        Message::AnalysisStop => {
+            state.analysis = None;
+        }
+        Message::AnalysisUpdate(id, info) => {
+            if let Some(request) = state.analysis.as_ref().filter(|request| request.id == id) {
+                state.analysis_line =
+                    Some(AnalysisLine::new(&info, &request.position, &state.game.move_gen, &state.game.zobrist));
+            } //else from a search that was already replaced
+        }
+        Message::Unmake | Message::Redo | Message::GotoPly(_) => {
+            state.navigate(msg);
+        }
+        Message::MoveSelected(ply) => {
+            state.game.goto_ply(ply);
+            state.reset_state_inputs();
+            state.reset_game_end_dialog();
+            state.reviewing = true;
         }
+        Message::CopyPgn => {} //written to the clipboard by update_with_tasks
         Message::PromotionSelected(piece_str) => {
             state.show_promotion_dialog = false;
 
@@ -356,12 +572,36 @@ pub fn update(state: &mut AppState, msg: Message) {
         }
     }
     state.sync_game_end_dialog();
-    if !state.game.is_over() && state.is_cpu_turn() {
+    if !state.game.is_over() && state.is_cpu_turn() && !state.is_read_only() {
         match state.game.play_cpu_move() {
             Ok(m) => {println!("CPU played move: {}", _move::to_string(m, true));}
             Err(_) => {}
         }
     }
+    state.sync_move_list();
+    state.sync_analysis();
+}
+
+///"minutes+increment in seconds" from TIME_CONTROL_OPTIONS, None for no clock
+fn time_control_option(label: &str) -> Option<TimeControl> {
+    let (minutes, increment) = label.split_once('+')?;
+    Some(TimeControl::fischer(
+        minutes.parse::<u64>().ok()? * 60_000,
+        increment.parse::<u64>().ok()? * 1000,
+    ))
+}
+
+fn navigation_message(event: keyboard::Event) -> Option<Message> {
+    match event {
+        keyboard::Event::KeyPressed { key: Key::Named(named), .. } => match named {
+            Named::ArrowLeft => Some(Message::Unmake),
+            Named::ArrowRight => Some(Message::Redo),
+            Named::Home => Some(Message::GotoPly(0)),
+            Named::End => Some(Message::GotoPly(usize::MAX)),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 pub fn view(state: &AppState) -> Element<'_, Message> {
@@ -400,11 +640,14 @@ const DARK_SQR_COLOR: iced::Color = iced::Color::from_rgb(0.47, 0.40, 0.30);
 const SELECTED_SQUARE_COLOR: iced::Color = iced::Color::from_rgb(1.0, 1.0, 0.0);
 const SQR_SIZE: u32 = 80;
 const PROMOTION_OPTIONS: [&str; 4] = ["Queen", "Rook", "Bishop", "Knight"];
+const TIME_CONTROL_OPTIONS: [&str; 6] = ["No clock", "1+0", "3+2", "5+0", "10+5", "30+0"];
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::game::game_state::GameState;
+    use crate::repr::_move::NULL_MOVE;
+    use crate::search::search_data::IterationInfo;
 
     const STALEMATE_FEN: &str = "k7/2Q5/2K5/8/8/8/8/8 b - - 0 1";
 
@@ -459,4 +702,131 @@ mod tests {
         assert!(!state.game_end_dialog_acknowledged);
         assert!(state.show_game_end_dialog);
     }
+
+    #[test]
+    fn navigation_lands_on_the_users_turn() {
+        let mut state = AppState::default();
+        for (from, to) in [(12, 28), (52, 36), (6, 21), (57, 42)] {
+            state.game.try_make_move(from, to, None).unwrap(); //e4 e5 Nf3 Nc6
+        }
+
+        update(&mut state, Message::Unmake);
+        assert_eq!(state.game.ply(), 2);
+        update(&mut state, Message::GotoPly(1));
+        assert_eq!(state.game.ply(), 2, "white never waits for the cpu's move");
+        update(&mut state, Message::GotoPly(0));
+        assert_eq!(state.game.ply(), 0);
+        update(&mut state, Message::Redo);
+        assert_eq!(state.game.ply(), 2);
+        update(&mut state, Message::GotoPly(usize::MAX));
+        assert_eq!(state.game.ply(), 4);
+    }
+
+    #[test]
+    fn selected_moves_are_shown_read_only() {
+        let mut state = AppState::default();
+        state.game.searcher.search_config = crate::search::search_config::SearchConfig::with_d(2);
+        state.game.searcher.search_config.log_uci_diagnostics = false;
+        for (from, to) in [(12, 28), (52, 36), (6, 21), (57, 42)] {
+            state.game.try_make_move(from, to, None).unwrap(); //e4 e5 Nf3 Nc6
+        }
+
+        update(&mut state, Message::MoveSelected(1));
+        assert_eq!(state.move_list, ["e4", "e5", "Nf3", "Nc6"]);
+        assert_eq!(state.game.ply(), 1, "the cpu does not move while reviewing");
+        assert!(state.is_read_only());
+        update(&mut state, Message::SquareClicked(52));
+        assert_eq!(state.selected_square, None);
+
+        update(&mut state, Message::MoveSelected(4));
+        assert!(!state.is_read_only());
+        update(&mut state, Message::SquareClicked(11));
+        assert_eq!(state.selected_square, Some(11));
+
+        //taking back with the arrows leaves review, new moves replace the line
+        update(&mut state, Message::MoveSelected(1));
+        update(&mut state, Message::Unmake);
+        assert!(!state.is_read_only());
+        assert_eq!(state.game.ply(), 0);
+        state.game.try_make_move(11, 27, None).unwrap(); //d4
+        update(&mut state, Message::FenContentChanged(String::new()));
+        assert_eq!(state.move_list.len(), 2);
+        assert_eq!(state.move_list[0], "d4");
+    }
+
+    #[test]
+    fn analysis_follows_the_board_until_stopped() {
+        let mut state = AppState::default();
+        state.game.searcher.search_config = crate::search::search_config::SearchConfig::with_d(2);
+        state.game.searcher.search_config.log_uci_diagnostics = false;
+        update(&mut state, Message::AnalysisStart);
+        let first_id: u64 = state.analysis.as_ref().unwrap().id;
+        let info = |depth: usize| IterationInfo {
+            depth,
+            sel_depth: depth,
+            eval: 30,
+            best_move: NULL_MOVE,
+            pv: vec![],
+            nodes: 100,
+            time_ms: 1,
+        };
+
+        update(&mut state, Message::AnalysisUpdate(first_id, info(3)));
+        assert_eq!(state.analysis_line.as_ref().unwrap().depth, 3);
+        assert!(state.analysis_line.as_ref().unwrap().white_share() > 0.5);
+
+        state.game.try_make_move(12, 28, None).unwrap(); //e4, the cpu replies in update
+        update(&mut state, Message::FenContentChanged(String::new()));
+        assert_eq!(state.game.ply(), 2);
+        let second_id: u64 = state.analysis.as_ref().unwrap().id;
+        assert_ne!(first_id, second_id);
+        assert_eq!(state.analysis.as_ref().unwrap().position.board.zhash, state.game.position.board.zhash);
+        assert!(state.analysis_line.is_none());
+        update(&mut state, Message::AnalysisUpdate(first_id, info(9)));
+        assert!(state.analysis_line.is_none(), "updates of a replaced search are dropped");
+        update(&mut state, Message::AnalysisUpdate(second_id, info(1)));
+        assert_eq!(state.analysis_line.as_ref().unwrap().depth, 1);
+
+        update(&mut state, Message::AnalysisStop);
+        assert!(state.analysis.is_none());
+        update(&mut state, Message::Unmake);
+        assert!(state.analysis.is_none());
+    }
+
+    #[test]
+    fn clock_ticks_end_the_game_without_a_move() {
+        let mut state = AppState::default();
+        update(&mut state, Message::TimeControlSelected("3+2"));
+        let clock = state.game.clock.as_ref().unwrap();
+        assert_eq!(clock.time_control, TimeControl::fischer(180_000, 2000));
+        assert!(clock.is_running());
+
+        state.game.set_time_control(Some(TimeControl::sudden_death(20)));
+        update(&mut state, Message::ClockTick);
+        assert!(!state.game.is_over());
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        update(&mut state, Message::ClockTick);
+        assert!(matches!(state.game.game_state, GameState::Timeout(WHITE)));
+        assert!(state.show_game_end_dialog);
+
+        update(&mut state, Message::TimeControlSelected("No clock"));
+        assert!(state.game.clock.is_none());
+    }
+
+    #[test]
+    fn arrow_keys_navigate_the_history() {
+        let press = |named: Named| keyboard::Event::KeyPressed {
+            key: Key::Named(named),
+            modified_key: Key::Named(named),
+            physical_key: keyboard::key::Physical::Unidentified(keyboard::key::NativeCode::Unidentified),
+            location: keyboard::Location::Standard,
+            modifiers: keyboard::Modifiers::empty(),
+            text: None,
+            repeat: false,
+        };
+        assert!(matches!(navigation_message(press(Named::ArrowLeft)), Some(Message::Unmake)));
+        assert!(matches!(navigation_message(press(Named::ArrowRight)), Some(Message::Redo)));
+        assert!(matches!(navigation_message(press(Named::End)), Some(Message::GotoPly(usize::MAX))));
+        assert!(navigation_message(press(Named::Enter)).is_none());
+    }
 }
@@ -1,7 +1,13 @@
+use crate::search::search_data::IterationInfo;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Message {
     SquareClicked(u32),
     Unmake,
+    Redo,
+    GotoPly(usize), //usize::MAX for the last redoable move
+    MoveSelected(usize), //move list entry, the ply after the move
+    CopyPgn,
     NewFenPosPressed, //takes fen_str and side (0 white 1 black)
     NewDefaultPosPressed,
     FenContentChanged(String),
@@ -9,7 +15,11 @@ pub enum Message {
     InputSideWhitePressed,
     InputSideBlackPressed,
     Event(iced::Event),
-    SearchStart,
+    TimeControlSelected(&'static str),
+    ClockTick,
+    AnalysisStart,
+    AnalysisStop,
+    AnalysisUpdate(u64, IterationInfo), //request id, completed depth
     PromotionSelected(&'static str),
     GameEndAcknowledged,
     ErrorAcknowledged,
@@ -1,3 +1,5 @@
+pub mod analysis;
 pub mod app;
 pub mod image_handle;
 pub mod messages;
+pub mod timer;
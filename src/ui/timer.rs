@@ -0,0 +1,26 @@
+/* Periodic ticks for the GUI, so running clocks are redrawn and flags fall without a move */
+
+use std::{thread, time::Duration};
+
+use iced::futures::{channel::mpsc, Stream};
+
+use crate::ui::messages::Message;
+
+pub const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+///Message::ClockTick every TICK_INTERVAL from a sleeping thread, which ends once the stream is dropped
+pub fn clock_ticks() -> impl Stream<Item = Message> {
+    iced::stream::channel(1, async |mut output: mpsc::Sender<Message>| {
+        thread::Builder::new()
+            .name("gui-clock-thread".into())
+            .spawn(move || loop {
+                thread::sleep(TICK_INTERVAL);
+                //a full channel only means the last tick was not handled yet
+                if output.try_send(Message::ClockTick).is_err_and(|err| err.is_disconnected()) {
+                    break;
+                }
+            })
+            .expect("Could not spawn the clock thread");
+        std::future::pending::<()>().await;
+    })
+}
@@ -0,0 +1,169 @@
+/* Builds Polyglot opening books from PGN game collections */
+
+use std::collections::BTreeMap;
+
+use crate::{
+    game::pgn::{self, PgnGame, PgnMove},
+    repr::{move_gen::MoveGen, position::Position, types::WHITE},
+    utils::{
+        polyglot::{self, BookEntry},
+        zobrist::Zobrist,
+    },
+};
+
+///Results of a move from the moving side's point of view. <br>
+///Games without a result ("*") are in count only, points() scores them as draws
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    pub count: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveStats {
+    ///Half points, unfinished games count as draws: 2 per win, 1 per draw or unfinished game
+    pub fn points(&self) -> u32 {
+        2 * self.wins + self.draws + (self.count - self.wins - self.draws - self.losses)
+    }
+
+    ///Score in 0.0..=1.0
+    pub fn performance(&self) -> f64 {
+        self.points() as f64 / (2 * self.count).max(1) as f64
+    }
+}
+
+pub struct BookBuilder {
+    pub max_ply: usize,          //moves from deeper plies are not recorded
+    pub min_count: u32,          //moves played fewer times are left out
+    pub min_performance: f64,    //moves scoring less for the mover are left out, 0.0..=1.0
+    pub include_variations: bool, //record PGN variations with the game's result as well
+    stats: BTreeMap<(u64, u16), MoveStats>, //(polyglot key, polyglot move)
+}
+
+impl BookBuilder {
+    pub fn new(max_ply: usize) -> Self {
+        Self {
+            max_ply,
+            min_count: 1,
+            min_performance: 0.0,
+            include_variations: false,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    ///Adds every game of a PGN collection, returns the number of games
+    pub fn add_pgn(&mut self, text: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<usize, String> {
+        let games: Vec<PgnGame> = pgn::parse_pgn(text, move_gen, zobrist)?;
+        for game in games.iter() {
+            self.add_game(game, move_gen, zobrist)?;
+        }
+        Ok(games.len())
+    }
+
+    ///Replays **game** from its start position and records its moves up to **self.max_ply**
+    pub fn add_game(&mut self, game: &PgnGame, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<(), String> {
+        let mut pos: Position =
+            Position::from(game.start_fen(), move_gen, zobrist).map_err(|err| format!("Invalid FEN: {}", err))?;
+        //white's half points, None for unfinished games
+        let white_points: Option<u32> = match game.result.as_str() {
+            "1-0" => Some(2),
+            "1/2-1/2" => Some(1),
+            "0-1" => Some(0),
+            _ => None,
+        };
+        self.add_line(&game.moves, 0, &mut pos, white_points, move_gen, zobrist);
+        Ok(())
+    }
+
+    fn add_line(
+        &mut self,
+        line: &[PgnMove],
+        ply: usize,
+        pos: &mut Position,
+        white_points: Option<u32>,
+        move_gen: &MoveGen,
+        zobrist: &Zobrist,
+    ) {
+        for (i, pgn_move) in line.iter().enumerate() {
+            if ply + i >= self.max_ply {
+                return;
+            }
+            if self.include_variations {
+                for variation in pgn_move.variations.iter() {
+                    let mut variation_pos: Position = pos.clone();
+                    self.add_line(variation, ply + i, &mut variation_pos, white_points, move_gen, zobrist);
+                }
+            }
+
+            let key: (u64, u16) =
+                (polyglot::polyglot_key(&pos.board), polyglot::encode_move(pgn_move.mov, &pos.board));
+            let stats: &mut MoveStats = self.stats.entry(key).or_default();
+            stats.count += 1;
+            match white_points {
+                Some(1) => stats.draws += 1,
+                Some(points) if (points == 2) == (pos.board.turn == WHITE) => stats.wins += 1,
+                Some(_) => stats.losses += 1,
+                None => {}
+            }
+            pos.make_move(pgn_move.mov, false, false, false, move_gen, zobrist);
+        }
+    }
+
+    ///Recorded stats of the position with polyglot **key**, by polyglot move
+    pub fn stats_for(&self, key: u64) -> Vec<(u16, MoveStats)> {
+        self.stats
+            .range((key, u16::MIN)..=(key, u16::MAX))
+            .map(|((_, raw_move), stats)| (*raw_move, *stats))
+            .collect()
+    }
+
+    ///Number of distinct positions recorded
+    pub fn nof_positions(&self) -> usize {
+        let mut keys: Vec<u64> = self.stats.keys().map(|(key, _)| *key).collect();
+        keys.dedup();
+        keys.len()
+    }
+
+    ///Book entries sorted by key and by weight within a position, moves that don't pass the filters or never score are left out. <br>
+    ///Weight is the move's half points, scaled down per position to fit 16 bits
+    pub fn entries(&self) -> Vec<BookEntry> {
+        let mut entries: Vec<BookEntry> = vec![];
+        let mut position_moves: Vec<(u16, u32)> = vec![]; //(polyglot move, points) of the current key
+        let mut stats = self.stats.iter().peekable();
+        while let Some(((key, raw_move), move_stats)) = stats.next() {
+            if move_stats.count >= self.min_count
+                && move_stats.performance() >= self.min_performance
+                && move_stats.points() > 0
+            {
+                position_moves.push((*raw_move, move_stats.points()));
+            }
+            if stats.peek().is_none_or(|((next_key, _), _)| next_key != key) {
+                push_position(*key, &mut position_moves, &mut entries);
+            }
+        }
+        entries
+    }
+
+    ///Polyglot .bin contents
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries().iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()).map_err(|err| format!("Could not write {}: {}", path, err))
+    }
+}
+
+///Moves **position_moves** of one position to **entries** best first
+fn push_position(key: u64, position_moves: &mut Vec<(u16, u32)>, entries: &mut Vec<BookEntry>) {
+    let max_points: u32 = position_moves.iter().map(|(_, points)| *points).max().unwrap_or(0);
+    let divisor: u32 = max_points.div_ceil(u16::MAX as u32).max(1);
+    position_moves.sort_by_key(|(_, points)| std::cmp::Reverse(*points));
+    entries.extend(position_moves.drain(..).map(|(raw_move, points)| BookEntry {
+        key,
+        raw_move,
+        weight: (points / divisor).max(1) as u16,
+        learn: 0,
+    }));
+}
@@ -0,0 +1,195 @@
+/* Extended Position Description records and a test-suite runner on top of Searcher */
+
+use std::time::Instant;
+
+use crate::{
+    repr::{_move, board::Board, move_gen::MoveGen, position::Position},
+    search::{
+        eval::{MATE_BOUND, MATE_EVAL},
+        search_config::SearchMode,
+        search_data::IterationInfo,
+        searcher::Searcher,
+    },
+    utils::{fen_tool::fen_to_board, zobrist::Zobrist},
+};
+
+pub struct EpdRecord {
+    pub fen: String, //the four EPD fields followed by hmvc and fmvn, "0 1" if not given
+    pub board: Board,
+    pub id: Option<String>,
+    pub best_moves: Vec<u32>,  //bm
+    pub avoid_moves: Vec<u32>, //am
+    pub direct_mate: Option<u32>, //dm, in full moves
+    pub comment: Option<String>, //c0
+    pub operations: Vec<(String, Vec<String>)>, //every operation in order, including unknown opcodes
+}
+
+impl EpdRecord {
+    /// One EPD line: piece placement, side to move, castling, en passant, then ';' terminated operations. <br>
+    /// bm and am may list moves in SAN or UCI notation.
+    pub fn parse(line: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Self, String> {
+        let line: &str = line.trim();
+        let mut fields: Vec<&str> = Vec::with_capacity(4);
+        let mut rest: &str = line;
+        while fields.len() < 4 {
+            rest = rest.trim_start();
+            let end: usize = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("EPD needs four position fields: {}", line));
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        let operations: Vec<(String, Vec<String>)> = parse_operations(rest)?;
+
+        let operand = |opcode: &str| {
+            operations
+                .iter()
+                .find(|(op, _)| op == opcode)
+                .and_then(|(_, operands)| operands.first().cloned())
+        };
+        let fen: String = format!(
+            "{} {} {}",
+            fields.join(" "),
+            operand("hmvc").unwrap_or("0".to_string()),
+            operand("fmvn").unwrap_or("1".to_string())
+        );
+        let board: Board = fen_to_board(fen.clone(), move_gen, zobrist).map_err(|err| format!("{}: {}", err, line))?;
+        let pos: Position = Position::from(&fen, move_gen, zobrist).map_err(|err| format!("{}: {}", err, line))?;
+        let moves = |opcode: &str| -> Result<Vec<u32>, String> {
+            operations
+                .iter()
+                .filter(|(op, _)| op == opcode)
+                .flat_map(|(_, operands)| operands.iter())
+                .map(|mov| resolve_move(&pos, mov).map_err(|err| format!("{} {}: {}", opcode, mov, err)))
+                .collect()
+        };
+        let direct_mate: Option<u32> = match operand("dm") {
+            Some(n) => Some(n.parse().map_err(|_| format!("Invalid dm operand: {}", n))?),
+            None => None,
+        };
+
+        Ok(Self {
+            best_moves: moves("bm")?,
+            avoid_moves: moves("am")?,
+            id: operand("id"),
+            comment: operand("c0"),
+            direct_mate,
+            fen,
+            board,
+            operations,
+        })
+    }
+
+    /// Whether the answer meets every bm, am and dm operation of the record
+    pub fn is_solved_by(&self, best_move: u32, eval: i16) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&best_move))
+            && !self.avoid_moves.contains(&best_move)
+            && self
+                .direct_mate
+                .is_none_or(|n| eval >= MATE_BOUND && MATE_EVAL - eval < 2 * n as i16) //mate in n is 2n - 1 plies
+    }
+}
+
+/// All records of an EPD file, blank lines and '#' comment lines are skipped
+pub fn parse_epd(text: &str, move_gen: &MoveGen, zobrist: &Zobrist) -> Result<Vec<EpdRecord>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| EpdRecord::parse(line, move_gen, zobrist).map_err(|err| format!("Line {}: {}", i + 1, err)))
+        .collect()
+}
+
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut operations: Vec<(String, Vec<String>)> = vec![];
+    let mut words: Vec<String> = vec![];
+    let mut word: String = String::new();
+    let mut in_string: bool = false;
+    let mut quoted: bool = false;
+
+    for c in text.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                quoted = true;
+            }
+            ';' if !in_string => {
+                if quoted || !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                quoted = false;
+                if words.is_empty() {
+                    return Err("Empty EPD operation".to_string());
+                }
+                let opcode: String = words.remove(0);
+                operations.push((opcode, std::mem::take(&mut words)));
+            }
+            _ if c.is_whitespace() && !in_string => {
+                if quoted || !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                quoted = false;
+            }
+            _ => word.push(c),
+        }
+    }
+    if in_string {
+        return Err("Unterminated EPD string operand".to_string());
+    }
+    if quoted || !word.is_empty() || !words.is_empty() {
+        return Err("EPD operation without terminating ';'".to_string());
+    }
+    Ok(operations)
+}
+
+fn resolve_move(pos: &Position, mov: &str) -> Result<u32, &'static str> {
+    pos.parse_san(mov).or_else(|san_err| {
+        let (from, to, promotion) = _move::from_string(mov).map_err(|_| san_err)?;
+        pos.legal_moves()
+            .iter()
+            .copied()
+            .find(|m| _move::get_init(*m) == from && _move::get_target(*m) == to && _move::promotion_matches(*m, promotion))
+            .ok_or(san_err)
+    })
+}
+
+pub struct EpdOutcome {
+    pub best_move: Option<u32>,
+    pub eval: i16,
+    pub depth: usize,
+    pub nodes: u64,
+    pub solved: bool,
+    pub time_to_solution: Option<u64>, //ms, from the first iteration after which the answer stayed correct
+    pub time_ms: u64, //whole search, including an unfinished last iteration
+}
+
+/// Searches the record's position from a fresh Searcher
+pub fn run_record(record: &EpdRecord, search_mode: SearchMode, move_gen: &MoveGen, zobrist: &Zobrist) -> EpdOutcome {
+    let pos: Position = Position::from(&record.fen, move_gen, zobrist).expect("Record FEN was validated on parse");
+    let mut searcher: Searcher = Searcher::from(&pos, false);
+    let started: Instant = Instant::now();
+    searcher.search_config.search_mode = search_mode;
+    searcher.search_config.log_uci_diagnostics = false;
+    searcher.start_search(move_gen, zobrist, None);
+    let time_ms: u64 = started.elapsed().as_millis() as u64;
+
+    let iterations: &[IterationInfo] = &searcher.search_data[0].iterations;
+    let best_move: Option<u32> = searcher.collect_best_move();
+    let last: Option<&IterationInfo> = iterations.last();
+    let eval: i16 = last.map_or(0, |it| it.eval);
+    let solved: bool = best_move.is_some_and(|mov| record.is_solved_by(mov, eval));
+    let first_stable: usize = iterations
+        .iter()
+        .rposition(|it| !record.is_solved_by(it.best_move, it.eval))
+        .map_or(0, |i| i + 1);
+
+    EpdOutcome {
+        best_move,
+        eval,
+        depth: last.map_or(0, |it| it.depth),
+        nodes: iterations.iter().map(|it| it.nodes).sum(),
+        solved,
+        time_to_solution: solved.then(|| iterations.get(first_stable).map_or(time_ms, |it| it.time_ms)),
+        time_ms,
+    }
+}
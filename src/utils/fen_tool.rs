@@ -1,5 +1,5 @@
 use crate::repr::bitboard;
-use crate::repr::board::Board;
+use crate::repr::board::{Board, STANDARD_CASTLING_ROOKS};
 use crate::repr::move_gen::MoveGen;
 use crate::repr::types::{
     BLACK, B_KING_U, B_PAWN_U, B_ROOK_U, WHITE, W_KING_U, W_PAWN_U, W_ROOK_U,
@@ -8,7 +8,7 @@ use crate::utils::zobrist::Zobrist;
 
 const VALID_PIECE_CHARS: [char; 12] = ['P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k'];
 const VALID_MOVER_CHARS: [char; 2] = ['w', 'b'];
-const VALID_CASTLING_CHARS: [char; 4] = ['K', 'Q', 'k', 'q'];
+const VALID_CASTLING_CHARS: [char; 4] = ['K', 'Q', 'k', 'q']; //or a rook file, Shredder-FEN/X-FEN for Chess960
 const FILE_CHARS: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 pub const MAJOR_MINOR_PIECES: [char; 8] = ['N', 'B', 'R', 'Q', 'n', 'b', 'r', 'q'];
 
@@ -50,7 +50,10 @@ pub fn is_valid_fen(fen: &String) -> bool {
         }
         let mut lowercase_seen: bool = false;
         for c in castling_rights.chars() {
-            if (c.is_uppercase() && lowercase_seen) || !VALID_CASTLING_CHARS.contains(&c) {
+            if (c.is_uppercase() && lowercase_seen)
+                || !(VALID_CASTLING_CHARS.contains(&c) || FILE_CHARS.contains(&c.to_ascii_lowercase()))
+                || castling_rights.matches(c).count() > 1
+            {
                 return false;
             }
             if c.is_lowercase() {
@@ -158,32 +161,37 @@ fn has_score_safe_material(piece_placement: &str) -> bool {
 }
 
 
-fn castling_pieces_on_right_squares(castling_str: &str, board: &Board) -> bool {
-    if castling_str.contains('K')
-        && (!bitboard::contains_square(board.pieces[W_KING_U], 4)
-            || !bitboard::contains_square(board.pieces[W_ROOK_U], 7))
-    {
-        return false;
-    }
-    if castling_str.contains('Q')
-        && (!bitboard::contains_square(board.pieces[W_KING_U], 4)
-            || !bitboard::contains_square(board.pieces[W_ROOK_U], 0))
-    {
-        return false;
-    }
-    if castling_str.contains('k')
-        && (!bitboard::contains_square(board.pieces[B_KING_U], 60)
-            || !bitboard::contains_square(board.pieces[B_ROOK_U], 63))
-    {
-        return false;
-    }
-    if castling_str.contains('q')
-        && (!bitboard::contains_square(board.pieces[B_KING_U], 60)
-            || !bitboard::contains_square(board.pieces[B_ROOK_U], 56))
-    {
-        return false;
+///Resolves the castling field to the rook start squares in ws, wl, bs, bl order, None where there's no right. <br>
+///K/Q/k/q pick the outermost rook on that side of the king, a file letter picks the rook on that file. <br>
+///Err if a right has no king and rook on the back rank to back it, or a side is given twice.
+fn castling_rook_squares(castling_str: &str, pieces: &[u64; 12]) -> Result<[Option<u32>; 4], &'static str> {
+    const INCONSISTENT: &str = "FEN error: castling rights not consistent with piece placement";
+    let mut rooks: [Option<u32>; 4] = [None; 4];
+    for c in castling_str.chars().filter(|c| *c != '-') {
+        let white: bool = c.is_uppercase();
+        let back_rank: u32 = if white { 0 } else { 56 };
+        let (king_bb, rook_bb) = if white {
+            (pieces[W_KING_U], pieces[W_ROOK_U])
+        } else {
+            (pieces[B_KING_U], pieces[B_ROOK_U])
+        };
+        let king_sqr: u32 = king_bb.trailing_zeros();
+        if king_sqr / 8 != back_rank / 8 {
+            return Err(INCONSISTENT);
+        }
+        let mut back_rank_rooks = (back_rank..back_rank + 8).filter(|sqr| bitboard::contains_square(rook_bb, *sqr));
+        let rook_sqr: Option<u32> = match c.to_ascii_lowercase() {
+            'k' => back_rank_rooks.rfind(|sqr| *sqr > king_sqr),
+            'q' => back_rank_rooks.find(|sqr| *sqr < king_sqr),
+            file => back_rank_rooks.find(|sqr| FILE_CHARS[(*sqr % 8) as usize] == file),
+        };
+        let rook_sqr: u32 = rook_sqr.ok_or(INCONSISTENT)?;
+        let idx: usize = 2 * (!white as usize) + (rook_sqr < king_sqr) as usize;
+        if rooks[idx].replace(rook_sqr).is_some() {
+            return Err(INCONSISTENT);
+        }
     }
-    return true;
+    Ok(rooks)
 }
 
 fn ep_square_consistent_with_pieces(ep_square: Option<u32>, board: &Board) -> bool {
@@ -280,10 +288,6 @@ pub fn fen_to_board(
         _ => panic!("Turn was not 'w' or 'b' in fen that was valid."),
     };
     let castling_string: &str = sections.next().expect("Was valid but sections ran out");
-    let ws: bool = castling_string.contains('K');
-    let wl: bool = castling_string.contains('Q');
-    let bs: bool = castling_string.contains('k');
-    let bl: bool = castling_string.contains('q');
 
     let ep_string: &str = sections.next().expect("Was valid but sections ran out");
     let ep_square: Option<u32>;
@@ -302,7 +306,9 @@ pub fn fen_to_board(
         None => 0,
     };
 
-    let board: Board = Board::board_with(
+    let castling_rooks: [Option<u32>; 4] = castling_rook_squares(castling_string, &pieces)?;
+    let [ws, wl, bs, bl] = castling_rooks.map(|rook| rook.is_some());
+    let mut board: Board = Board::board_with(
         pieces,
         white_occupation,
         black_occupation,
@@ -317,9 +323,15 @@ pub fn fen_to_board(
         zobrist,
         half_move_clock,
     );
-    if !castling_pieces_on_right_squares(castling_string, &board) {
-        return Err("FEN error: castling rights not consistent with piece placement");
+    for (i, rook) in castling_rooks.iter().enumerate() {
+        if let Some(rook_sqr) = rook {
+            board.castling_rooks[i] = *rook_sqr;
+        }
     }
+    board.chess960 = castling_string.chars().any(|c| !VALID_CASTLING_CHARS.contains(&c) && c != '-')
+        || board.castling_rooks != STANDARD_CASTLING_ROOKS
+        || (ws || wl) && !bitboard::contains_square(board.pieces[W_KING_U], 4)
+        || (bs || bl) && !bitboard::contains_square(board.pieces[B_KING_U], 60);
     if !ep_square_consistent_with_pieces(ep_square, &board) {
         return Err("FEN error: en passant square not consistent with piece placement");
     }
@@ -369,17 +381,10 @@ pub fn board_to_fen(board: &Board) -> String {
 
     let castling_start = fen.len();
 
-    if board.ws() {
-        fen.push('K');
-    }
-    if board.wl() {
-        fen.push('Q');
-    }
-    if board.bs() {
-        fen.push('k');
-    }
-    if board.bl() {
-        fen.push('q');
+    for (i, has_right) in [board.ws(), board.wl(), board.bs(), board.bl()].into_iter().enumerate() {
+        if has_right {
+            fen.push(castling_char(board, i));
+        }
     }
 
     if fen.len() == castling_start {
@@ -403,4 +408,25 @@ pub fn board_to_fen(board: &Board) -> String {
     fen
 }
 
+///X-FEN castling char for the right at **idx** (ws, wl, bs, bl order): K/Q/k/q when the rook is the outermost one on
+///its side of the king, so standard positions keep their usual FEN, else the rook's file
+fn castling_char(board: &Board, idx: usize) -> char {
+    let white: bool = idx < 2;
+    let is_short: bool = idx.is_multiple_of(2);
+    let rook_sqr: u32 = board.castling_rooks[idx];
+    let back_rank: u32 = rook_sqr / 8 * 8;
+    let rook_bb: u64 = if white { board.pieces[W_ROOK_U] } else { board.pieces[B_ROOK_U] };
+    let mut outer_squares = if is_short { rook_sqr + 1..back_rank + 8 } else { back_rank..rook_sqr };
+    let c: char = if !outer_squares.any(|sqr| bitboard::contains_square(rook_bb, sqr)) {
+        if is_short { 'k' } else { 'q' }
+    } else {
+        FILE_CHARS[(rook_sqr % 8) as usize]
+    };
+    if white {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
 pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -1,2 +1,5 @@
+pub mod book_builder;
+pub mod epd;
 pub mod fen_tool;
-pub mod zobrist;
+pub mod polyglot;
+pub mod zobrist;
\ No newline at end of file
@@ -66,8 +66,7 @@ impl Zobrist {
         promotion_piece: Option<usize>,
         is_eating: bool,
         eaten_piece: Option<usize>,
-        is_castle: bool,
-        is_short_castle: bool,
+        castling_rook: Option<(usize, usize)>, //(rook_from, rook_to)
         is_double_push: bool,
         is_en_passant: bool,
         lost_ws: bool,
@@ -92,31 +91,8 @@ impl Zobrist {
             let promotion_piece: usize =
                 promotion_piece.expect("Was promotion but no promotion piece found");
             new ^= self.piece_rands[promotion_piece * NOF_SQUARES + to];
-        } else if is_castle {
-            let rook_from: usize;
-            let rook_to: usize;
-            let rook_piece_idx: usize;
-            if is_short_castle {
-                if is_white_turn {
-                    rook_from = 7;
-                    rook_to = 5;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 63;
-                    rook_to = 61;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            } else {
-                if is_white_turn {
-                    rook_from = 0;
-                    rook_to = 3;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 56;
-                    rook_to = 59;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            }
+        } else if let Some((rook_from, rook_to)) = castling_rook {
+            let rook_piece_idx: usize = if is_white_turn { W_ROOK_U } else { B_ROOK_U };
             new ^= self.piece_rands[rook_piece_idx * NOF_SQUARES + rook_from];
             new ^= self.piece_rands[rook_piece_idx * NOF_SQUARES + rook_to];
         }
@@ -175,8 +151,7 @@ impl Zobrist {
         promotion_piece: Option<usize>,
         is_eating: bool,
         eaten_piece: Option<usize>,
-        is_castle: bool,
-        is_short_castle: bool,
+        castling_rook: Option<(usize, usize)>, //(rook_from, rook_to)
         is_double_push: bool,
         is_en_passant: bool,
         gained_ws: bool,
@@ -202,33 +177,8 @@ impl Zobrist {
             let promotion_piece: usize =
                 promotion_piece.expect("Was promotion but no promotion piece found");
             new ^= self.piece_rands[promotion_piece * NOF_SQUARES + to];
-        } else if is_castle {
-            let rook_from: usize;
-            let rook_to: usize;
-            let rook_piece_idx: usize;
-
-            if is_short_castle {
-                if is_white_turn {
-                    rook_from = 7;
-                    rook_to = 5;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 63;
-                    rook_to = 61;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            } else {
-                if is_white_turn {
-                    rook_from = 0;
-                    rook_to = 3;
-                    rook_piece_idx = W_ROOK_U;
-                } else {
-                    rook_from = 56;
-                    rook_to = 59;
-                    rook_piece_idx = B_ROOK_U;
-                }
-            }
-
+        } else if let Some((rook_from, rook_to)) = castling_rook {
+            let rook_piece_idx: usize = if is_white_turn { W_ROOK_U } else { B_ROOK_U };
             new ^= self.piece_rands[rook_piece_idx * NOF_SQUARES + rook_from];
             new ^= self.piece_rands[rook_piece_idx * NOF_SQUARES + rook_to];
         }
@@ -0,0 +1,90 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::TestEngine;
+use iced::futures::{executor::block_on, StreamExt};
+use rusty_engine::{
+    repr::_move::{self, NULL_MOVE},
+    search::{
+        eval::MATE_EVAL,
+        nnue::Network,
+        search_config::{EvalKind, SearchConfig},
+        search_data::IterationInfo,
+        searcher::Searcher,
+        tablebase::LocalTablebase,
+    },
+    ui::{
+        analysis::{self, AnalysisLine, AnalysisRequest},
+        messages::Message,
+    },
+};
+
+const MATE_IN_ONE_FEN: &str = "7k/8/5KQ1/8/8/8/8/8 w - - 0 1";
+
+#[test]
+fn analysis_streams_every_completed_depth() {
+    let engine = TestEngine::new();
+    let request = AnalysisRequest::new(7, &Searcher::from(&engine.position(MATE_IN_ONE_FEN), false));
+
+    let mut updates = Box::pin(analysis::stream(&request));
+    for depth in 1..=3 {
+        match block_on(updates.next()) {
+            Some(Message::AnalysisUpdate(id, info)) => {
+                assert_eq!((id, info.depth), (7, depth));
+                let line = AnalysisLine::new(&info, &request.position, &engine.move_gen, &engine.zobrist);
+                assert_eq!(line.pv[0], "Qg7#");
+                assert_eq!(line.white_share(), 1.0);
+            }
+            other => panic!("expected an analysis update, got {:?}", other),
+        }
+    }
+    //dropping the stream stops the search
+    drop(updates);
+}
+
+#[test]
+fn analysis_searches_like_the_game_searcher() {
+    let engine = TestEngine::new();
+    let mut searcher = Searcher::from(&engine.position(MATE_IN_ONE_FEN), false);
+    searcher.search_config = SearchConfig::with_d(4);
+    searcher.search_config.eval_kind = EvalKind::Nnue;
+    searcher.search_config.quiescence = false;
+    searcher.search_config.tb_probe_depth = 3;
+    searcher.network = Some(Arc::new(Network::bundled()));
+    searcher.tablebase = Some(Arc::new(LocalTablebase::default()));
+
+    let request = AnalysisRequest::new(3, &searcher);
+    assert_eq!(request.position.board.zhash, searcher.positions[0].board.zhash);
+    assert_eq!(request.board_hash_history, searcher.search_data[0].board_hash_history);
+    assert_eq!(request.search_config.eval_kind, EvalKind::Nnue);
+    assert!(!request.search_config.quiescence);
+    assert_eq!(request.search_config.tb_probe_depth, 3);
+    assert!(Arc::ptr_eq(request.network.as_ref().unwrap(), searcher.network.as_ref().unwrap()));
+    assert!(Arc::ptr_eq(request.tablebase.as_ref().unwrap(), searcher.tablebase.as_ref().unwrap()));
+}
+
+#[test]
+fn analysis_lines_are_shown_from_whites_point_of_view() {
+    let engine = TestEngine::new();
+    let black_to_move = engine.position("4k3/8/8/8/8/8/3q4/7K b - - 0 1");
+    let info = IterationInfo {
+        depth: 2,
+        sel_depth: 5,
+        eval: 900,
+        best_move: NULL_MOVE,
+        pv: black_to_move.legal_moves().iter().copied().filter(|mov| _move::get_target(*mov) == 4).collect(),
+        nodes: 1234,
+        time_ms: 1,
+    };
+    let line = AnalysisLine::new(&info, &black_to_move, &engine.move_gen, &engine.zobrist);
+    assert_eq!(line.white_eval, -900);
+    assert_eq!(line.pv, ["Qe1+"]);
+    assert_eq!(line.summary(), "Depth 2/5  -9.00  1234 nodes");
+    assert!(line.white_share() < 0.01);
+
+    let even = AnalysisLine { white_eval: 0, ..line.clone() };
+    assert_eq!(even.white_share(), 0.5);
+    let mated = AnalysisLine { white_eval: -(MATE_EVAL - 3), ..line };
+    assert_eq!(mated.white_share(), 0.0);
+}
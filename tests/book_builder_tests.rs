@@ -0,0 +1,138 @@
+mod common;
+
+use common::{find_uci, TestEngine};
+use rand::{rngs::StdRng, SeedableRng};
+use rusty_engine::{
+    repr::{_move, position::Position},
+    utils::{
+        book_builder::{BookBuilder, MoveStats},
+        fen_tool::DEFAULT_FEN,
+        polyglot::{self, BookSelection, PolyglotBook},
+    },
+};
+
+const GAMES: &str = r#"
+[Result "1-0"]
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result "0-1"]
+1. e4 c5 0-1
+
+[Result "1/2-1/2"]
+1. d4 d5 1/2-1/2
+
+[Result "*"]
+1. e4 e5 (1... c5 2. Nf3) 2. Bc4 *
+"#;
+
+fn played(engine: &TestEngine, ucis: &[&str]) -> Position {
+    let mut pos = engine.position(DEFAULT_FEN);
+    for uci in ucis {
+        let mov = find_uci(&pos, uci);
+        pos.make_move(mov, false, false, false, &engine.move_gen, &engine.zobrist);
+    }
+    pos
+}
+
+///(uci, stats) of the recorded moves of **pos**
+fn stats(builder: &BookBuilder, pos: &Position) -> Vec<(String, MoveStats)> {
+    builder
+        .stats_for(polyglot::polyglot_key(&pos.board))
+        .into_iter()
+        .map(|(raw_move, stats)| {
+            let mov = polyglot::decode_move(raw_move, pos).expect("recorded moves are legal");
+            (_move::to_uci(mov, &pos.board), stats)
+        })
+        .collect()
+}
+
+fn built(engine: &TestEngine, max_ply: usize, configure: impl FnOnce(&mut BookBuilder)) -> BookBuilder {
+    let mut builder = BookBuilder::new(max_ply);
+    configure(&mut builder);
+    assert_eq!(builder.add_pgn(GAMES, &engine.move_gen, &engine.zobrist), Ok(4));
+    builder
+}
+
+#[test]
+fn results_are_counted_for_the_moving_side() {
+    let engine = TestEngine::new();
+    let builder = built(&engine, 40, |_| {});
+
+    let start = stats(&builder, &played(&engine, &[]));
+    assert_eq!(
+        start,
+        [
+            ("d2d4".to_string(), MoveStats { count: 1, wins: 0, draws: 1, losses: 0 }),
+            ("e2e4".to_string(), MoveStats { count: 3, wins: 1, draws: 0, losses: 1 }),
+        ]
+    );
+    let after_e4 = stats(&builder, &played(&engine, &["e2e4"]));
+    assert_eq!(
+        after_e4,
+        [
+            ("c7c5".to_string(), MoveStats { count: 1, wins: 1, draws: 0, losses: 0 }),
+            ("e7e5".to_string(), MoveStats { count: 2, wins: 0, draws: 0, losses: 1 }),
+        ]
+    );
+    //the unfinished game counts as a draw for the weight
+    assert_eq!(start[1].1.points(), 3);
+    assert_eq!(start[1].1.performance(), 0.5);
+}
+
+#[test]
+fn moves_beyond_the_ply_limit_are_not_recorded() {
+    let engine = TestEngine::new();
+    let builder = built(&engine, 2, |_| {});
+    assert_eq!(builder.nof_positions(), 3);
+    assert!(stats(&builder, &played(&engine, &["e2e4", "e7e5"])).is_empty());
+}
+
+#[test]
+fn variations_are_recorded_only_when_asked() {
+    let engine = TestEngine::new();
+    let after_e4 = played(&engine, &["e2e4"]);
+    let after_sicilian = played(&engine, &["e2e4", "c7c5"]);
+
+    let main_lines = built(&engine, 40, |_| {});
+    assert_eq!(stats(&main_lines, &after_e4)[0].1.count, 1);
+    assert!(stats(&main_lines, &after_sicilian).is_empty());
+
+    let with_variations = built(&engine, 40, |builder| builder.include_variations = true);
+    assert_eq!(stats(&with_variations, &after_e4)[0].1.count, 2);
+    assert_eq!(stats(&with_variations, &after_sicilian)[0].0, "g1f3");
+}
+
+#[test]
+fn filters_leave_out_rare_and_poorly_scoring_moves() {
+    let engine = TestEngine::new();
+    let start_key = polyglot::polyglot_key(&played(&engine, &[]).board);
+
+    let frequent = built(&engine, 40, |builder| builder.min_count = 2);
+    let entries = frequent.entries();
+    assert_eq!(entries.len(), 2); //e2e4 and 1... e5
+    assert_eq!(entries.iter().filter(|entry| entry.key == start_key).count(), 1);
+
+    let scoring = built(&engine, 40, |builder| builder.min_performance = 0.6);
+    assert!(scoring.entries().iter().all(|entry| entry.key != start_key));
+}
+
+#[test]
+fn written_book_is_readable_and_weighted_by_points() {
+    let engine = TestEngine::new();
+    let builder = built(&engine, 40, |_| {});
+    let mut book = PolyglotBook::from_bytes(&builder.to_bytes()).expect("builder writes a valid book");
+    book.selection = BookSelection::Best;
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let start = played(&engine, &[]);
+    let weights: Vec<(String, u16)> =
+        book.moves(&start).iter().map(|(mov, weight)| (_move::to_uci(*mov, &start.board), *weight)).collect();
+    assert_eq!(weights, [("e2e4".to_string(), 3), ("d2d4".to_string(), 1)]);
+
+    let after_e4 = played(&engine, &["e2e4"]);
+    assert_eq!(book.pick(&after_e4, &mut rng), Some(find_uci(&after_e4, "c7c5")));
+
+    //black lost the only game with 2... Nc6, so it is left out
+    let after_nf3 = played(&engine, &["e2e4", "e7e5", "g1f3"]);
+    assert_eq!(book.pick(&after_nf3, &mut rng), None);
+}
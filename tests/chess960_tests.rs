@@ -0,0 +1,163 @@
+mod common;
+
+use common::TestEngine;
+use rusty_engine::{
+    game::cpu_game::CpuGame,
+    repr::{_move, position::Position},
+    utils::fen_tool,
+};
+
+//reference counts from the Chess960 perft suite, the fourth cross-checked with a naive generator
+const CHESS960_PERFT_CASES: [(&str, &[u64]); 5] = [
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12_189, 326_672],
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[21, 807, 18_002, 667_366],
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[20, 479, 10_471, 273_318],
+    ),
+    (
+        "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        &[22, 593, 13_440],
+    ),
+    (
+        "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        &[28, 1_120, 31_058, 1_171_749],
+    ),
+];
+
+fn uci_moves(pos: &mut Position) -> Vec<String> {
+    let board = pos.board.clone();
+    pos.legal_moves().iter().map(|mov| _move::to_uci(*mov, &board)).collect()
+}
+
+fn castle_matching(pos: &mut Position, is_short: bool) -> Option<u32> {
+    pos.legal_moves()
+        .iter()
+        .copied()
+        .find(|mov| _move::is_castle(*mov) && _move::is_short_castle(*mov) == is_short)
+}
+
+#[test]
+fn chess960_perft_matches_reference_counts() {
+    let engine = TestEngine::new();
+    for (fen, counts) in CHESS960_PERFT_CASES {
+        for (depth, expected) in counts.iter().enumerate() {
+            let mut pos = engine.position(fen);
+            assert_eq!(engine.perft(depth + 1, &mut pos), *expected, "{fen} depth {}", depth + 1);
+        }
+    }
+}
+
+#[test]
+fn parses_shredder_and_x_fen_castling_rights() {
+    let engine = TestEngine::new();
+    let shredder = engine.board("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+    let x_fen = engine.board("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+    assert!(shredder.chess960);
+    assert_eq!(shredder.castling_rooks, [7, 5, 63, 61]);
+    assert_eq!(x_fen.castling_rooks, shredder.castling_rooks);
+    assert_eq!(x_fen.zhash, shredder.zhash);
+
+    //K/Q are the outermost rooks, the inner one needs its file
+    let inner = engine.board("4k3/8/8/8/8/8/8/RR2K1RR w BG - 0 1");
+    assert_eq!(&inner.castling_rooks[..2], &[6, 1]);
+    assert_eq!(fen_tool::board_to_fen(&inner), "4k3/8/8/8/8/8/8/RR2K1RR w GB - 0 1");
+
+    let standard = engine.default_board();
+    assert!(!standard.chess960);
+    assert!(fen_tool::board_to_fen(&standard).contains(" KQkq "));
+
+    let invalid = |fen: &str| fen_tool::fen_to_board(fen.to_owned(), &engine.move_gen, &engine.zobrist).is_err();
+    assert!(invalid("4k3/8/8/8/8/8/8/R3K3 w C - 0 1"), "no rook on c1");
+    assert!(invalid("4k3/8/8/8/8/8/8/RR2K3 w AB - 0 1"), "two long rights");
+    assert!(invalid("4k3/8/8/8/8/8/4K3/R7 w Q - 0 1"), "king off the back rank");
+    assert!(invalid("4k3/8/8/8/8/8/8/R3K3 w QQ - 0 1"));
+    assert!(invalid("4k3/8/8/8/8/8/8/R3K3 w Z - 0 1"));
+}
+
+#[test]
+fn fen_round_trips_through_the_960_perft_positions() {
+    let engine = TestEngine::new();
+    for (fen, _) in CHESS960_PERFT_CASES {
+        let board = engine.board(fen);
+        let written = fen_tool::board_to_fen(&board);
+        let reread = engine.board(&written);
+        assert_eq!(reread.castling_rooks, board.castling_rooks, "{written}");
+        assert_eq!(reread.zhash, board.zhash, "{written}");
+        assert!(reread.chess960);
+    }
+}
+
+#[test]
+fn castling_is_king_takes_rook_in_uci() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position("r3k2r/8/8/8/8/8/8/1R2K1R1 w GBkq - 0 1");
+    let moves = uci_moves(&mut pos);
+    assert!(moves.contains(&"e1g1".to_string()), "{moves:?}");
+    assert!(moves.contains(&"e1b1".to_string()), "{moves:?}");
+
+    //the king stays on g1, only the rook moves
+    let mut pos = engine.position("4k3/8/8/8/8/8/8/6KR w H - 0 1");
+    let short = castle_matching(&mut pos, true).unwrap();
+    assert_eq!(_move::to_uci(short, &pos.board), "g1h1");
+    assert_eq!(_move::get_init(short), _move::get_target(short));
+
+    //standard chess keeps e1g1
+    let mut standard = engine.position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let short = castle_matching(&mut standard, true).unwrap();
+    assert_eq!(_move::to_uci(short, &standard.board), "e1g1");
+    standard.board.chess960 = true;
+    assert_eq!(_move::to_uci(short, &standard.board), "e1h1");
+}
+
+#[test]
+fn castling_moves_pieces_and_restores_hash() {
+    let engine = TestEngine::new();
+    //king on b1 castles long onto c1 with the a1 rook landing on d1, short swaps king and rook
+    for (fen, is_short, king_to, rook_to) in [
+        ("4k3/8/8/8/8/8/8/RK3R2 w AF - 0 1", false, 2, 3),
+        ("4k3/8/8/8/8/8/8/RK3R2 w AF - 0 1", true, 6, 5),
+        ("5k1r/8/8/8/8/8/8/4K3 b h - 0 1", true, 62, 61),
+    ] {
+        let mut pos = engine.position(fen);
+        let hash = pos.board.zhash;
+        let fen_before = fen_tool::board_to_fen(&pos.board);
+        let castle = castle_matching(&mut pos, is_short).unwrap_or_else(|| panic!("{fen} {is_short}"));
+        engine.make_search_move(&mut pos, castle);
+        let (king, rook) = if _move::is_white_move(castle) { (5, 3) } else { (11, 9) };
+        assert_eq!(pos.board.pieces[king], 1 << king_to, "{fen}");
+        assert!(pos.board.pieces[rook] & (1 << rook_to) != 0, "{fen}");
+        assert_eq!(pos.board.zhash, engine.recomputed_hash(&pos.board), "{fen}");
+        engine.unmake_move(&mut pos, castle);
+        assert_eq!(pos.board.zhash, hash);
+        assert_eq!(fen_tool::board_to_fen(&pos.board), fen_before);
+    }
+}
+
+#[test]
+fn cannot_castle_into_a_check_the_castling_rook_was_blocking() {
+    let engine = TestEngine::new();
+    //the b1 rook shields c1 from the a1 queen until it moves to d1
+    let mut pos = engine.position("4k3/8/8/8/8/8/8/qR1K4 w B - 0 1");
+    assert!(castle_matching(&mut pos, false).is_none());
+    let mut pos = engine.position("4k3/8/8/8/8/8/8/1R1K4 w B - 0 1");
+    assert!(castle_matching(&mut pos, false).is_some());
+}
+
+#[test]
+fn cpu_game_reads_king_takes_rook_with_uci_chess960() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let mut game = CpuGame::default();
+    assert!(game.import_position(fen, vec!["e1h1".to_string()]).is_err());
+
+    game.chess960 = true;
+    game.import_position(fen, vec!["e1h1".to_string(), "e8a8".to_string()]).unwrap();
+    assert_eq!(fen_tool::board_to_fen(&game.position.board), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 1");
+    assert!(game.import_position(fen, vec!["e1g1".to_string()]).is_err());
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::TestEngine;
+use iced::futures::{executor::block_on, StreamExt};
+use rusty_engine::{
+    game::{
+        clock::{Clock, TimeControl},
+        game::Game,
+        game_state::GameState,
+    },
+    repr::types::{BLACK, WHITE},
+    search::search_config::SearchMode,
+    ui::{messages::Message, timer},
+};
+
+fn ms(t0: Instant, ms: u64) -> Instant {
+    t0 + Duration::from_millis(ms)
+}
+
+#[test]
+fn only_the_side_to_move_loses_time() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::sudden_death(60_000));
+    assert!(!clock.is_running());
+    clock.start(WHITE, t0);
+    assert_eq!(clock.remaining(WHITE, ms(t0, 1500)), 58_500);
+    assert_eq!(clock.remaining(BLACK, ms(t0, 1500)), 60_000);
+
+    clock.punch(ms(t0, 2000)).unwrap();
+    assert_eq!(clock.remaining(WHITE, ms(t0, 5000)), 58_000);
+    assert_eq!(clock.remaining(BLACK, ms(t0, 5000)), 57_000);
+
+    clock.stop(ms(t0, 6000));
+    assert!(!clock.is_running());
+    assert_eq!(clock.remaining(BLACK, ms(t0, 60_000)), 56_000);
+    clock.start(BLACK, ms(t0, 60_000));
+    assert_eq!(clock.remaining(BLACK, ms(t0, 61_000)), 55_000);
+}
+
+#[test]
+fn fischer_increment_is_added_after_each_move() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::fischer(10_000, 2000));
+    clock.start(WHITE, t0);
+    clock.punch(ms(t0, 3000)).unwrap();
+    assert_eq!(clock.remaining(WHITE, ms(t0, 3000)), 9000);
+    clock.punch(ms(t0, 3500)).unwrap();
+    assert_eq!(clock.remaining(BLACK, ms(t0, 3500)), 11_500);
+}
+
+#[test]
+fn bronstein_delay_gives_back_used_time_up_to_the_delay() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::bronstein(10_000, 2000));
+    clock.start(WHITE, t0);
+    clock.punch(ms(t0, 1500)).unwrap();
+    assert_eq!(clock.remaining(WHITE, ms(t0, 1500)), 10_000);
+    clock.punch(ms(t0, 6500)).unwrap();
+    assert_eq!(clock.remaining(BLACK, ms(t0, 6500)), 7000);
+
+    //the flag still falls while the delay is running
+    let mut clock = Clock::new(TimeControl::bronstein(1000, 2000));
+    clock.start(WHITE, t0);
+    assert_eq!(clock.punch(ms(t0, 1000)), Err(WHITE));
+}
+
+#[test]
+fn sessions_add_the_base_time_again() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::sessions(2, 10_000));
+    clock.start(WHITE, t0);
+    let mut now = 0;
+    for _ in 0..3 {
+        now += 1000;
+        clock.punch(ms(t0, now)).unwrap(); //white
+        now += 1000;
+        clock.punch(ms(t0, now)).unwrap(); //black
+    }
+    //white played 3 moves of 1 s and got a second session after the 2nd
+    assert_eq!(clock.remaining(WHITE, ms(t0, now)), 17_000);
+    assert_eq!(clock.remaining(BLACK, ms(t0, now)), 17_000);
+}
+
+#[test]
+fn flag_falls_only_for_the_running_side_and_stays_down() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::sudden_death(1000));
+    clock.start(WHITE, t0);
+    assert_eq!(clock.flag(ms(t0, 999)), None);
+    clock.punch(ms(t0, 999)).unwrap();
+    assert_eq!(clock.flag(ms(t0, 1500)), None);
+    assert_eq!(clock.flag(ms(t0, 1999)), Some(BLACK));
+    assert!(!clock.is_running());
+    assert_eq!(clock.remaining(BLACK, ms(t0, 5000)), 0);
+    assert_eq!(clock.punch(ms(t0, 5000)), Err(BLACK));
+    assert_eq!(clock.flag(ms(t0, 9000)), Some(BLACK));
+}
+
+#[test]
+fn search_budget_follows_the_live_clock() {
+    let t0 = Instant::now();
+    let mut clock = Clock::new(TimeControl::fischer(60_000, 1000));
+    clock.start(WHITE, t0);
+    match clock.search_mode(WHITE, t0) {
+        SearchMode::StaticTime(t) => assert_eq!(t, 60_000 / 30 + 1000 - 50),
+        _ => panic!("clocked games search with a time budget"),
+    }
+    match clock.search_mode(WHITE, ms(t0, 30_000)) {
+        SearchMode::StaticTime(t) => assert_eq!(t, 30_000 / 30 + 1000 - 50),
+        _ => panic!("clocked games search with a time budget"),
+    }
+
+    //never more than what is left, the increment comes after the move
+    let mut clock = Clock::new(TimeControl::bronstein(500, 3000));
+    clock.start(BLACK, t0);
+    match clock.search_mode(BLACK, t0) {
+        SearchMode::StaticTime(t) => assert_eq!(t, 450),
+        _ => panic!("clocked games search with a time budget"),
+    }
+}
+
+#[test]
+fn timeouts_lose_unless_the_opponent_cannot_mate() {
+    let engine = TestEngine::new();
+    let rook_up = engine.board("4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+    assert!(matches!(GameState::flag_fall(&rook_up, BLACK), GameState::Timeout(BLACK)));
+    assert!(matches!(GameState::flag_fall(&rook_up, WHITE), GameState::DrawByTimeout));
+
+    //a lone knight can still mate with help from the flagged side's pawns
+    let knight_vs_pawn = engine.board("4k3/4p3/8/8/8/8/8/1N2K3 b - - 0 1");
+    assert!(matches!(GameState::flag_fall(&knight_vs_pawn, BLACK), GameState::Timeout(BLACK)));
+    let knight_vs_king = engine.board("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1");
+    assert!(matches!(GameState::flag_fall(&knight_vs_king, BLACK), GameState::DrawByTimeout));
+
+    let timeout = GameState::Timeout(WHITE);
+    assert_eq!(timeout.pgn_result(), "0-1");
+    assert!(timeout.is_over() && !timeout.is_draw());
+    assert_eq!(timeout.to_string(), "White ran out of time, Black wins");
+    assert_eq!(GameState::DrawByTimeout.pgn_result(), "1/2-1/2");
+}
+
+#[test]
+fn game_ends_when_the_mover_flags() {
+    let mut game = Game::default();
+    game.set_time_control(Some(TimeControl::sudden_death(60_000)));
+    assert!(game.try_make_move(12, 28, None).is_ok()); //e2e4
+    assert!(!game.check_flag());
+    assert!(game.clock.as_ref().unwrap().remaining(WHITE, Instant::now()) > 59_000);
+
+    game.set_time_control(Some(TimeControl::sudden_death(0)));
+    assert!(game.check_flag());
+    assert!(matches!(game.game_state, GameState::Timeout(BLACK)));
+    assert!(game.try_make_move(52, 36, None).is_err()); //e7e5 is too late
+    assert!(game.to_pgn(&[], None).contains("1. e4 1-0"));
+
+    //importing a position restarts the same time control
+    let engine = TestEngine::new();
+    game.import_position(engine.position("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+    assert!(game.clock.as_ref().unwrap().is_running());
+    std::thread::sleep(Duration::from_millis(2));
+    assert!(game.try_make_move(0, 56, None).is_err());
+    assert!(matches!(game.game_state, GameState::DrawByTimeout));
+}
+
+#[test]
+fn cpu_moves_are_budgeted_from_the_clock() {
+    let mut game = Game::default();
+    game.searcher.search_config.log_uci_diagnostics = false;
+    game.set_time_control(Some(TimeControl::sudden_death(3000)));
+    let started = Instant::now();
+    game.play_cpu_move().unwrap();
+    assert!(started.elapsed() < Duration::from_millis(1000));
+    match game.searcher.search_config.search_mode {
+        SearchMode::StaticTime(t) => assert!(t <= 3000 / 30),
+        _ => panic!("clocked games search with a time budget"),
+    }
+    let clock = game.clock.as_ref().unwrap();
+    assert!(clock.remaining(WHITE, Instant::now()) < 3000);
+    assert!(clock.is_running());
+}
+
+#[test]
+fn a_side_that_never_moves_still_loses_on_time() {
+    let mut game = Game::default();
+    game.set_time_control(Some(TimeControl::sudden_death(20)));
+    assert!(!game.check_flag());
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(game.check_flag());
+    assert!(matches!(game.game_state, GameState::Timeout(WHITE)));
+    assert!(!game.clock.as_ref().unwrap().is_running());
+    assert!(game.to_pgn(&[], None).contains("0-1"));
+}
+
+#[test]
+fn the_gui_timer_ticks_until_dropped() {
+    let mut ticks = Box::pin(timer::clock_ticks());
+    let started = Instant::now();
+    assert_eq!(block_on(ticks.next()), Some(Message::ClockTick));
+    assert_eq!(block_on(ticks.next()), Some(Message::ClockTick));
+    assert!(started.elapsed() >= timer::TICK_INTERVAL * 2);
+}
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use rusty_engine::{
-    repr::{board::Board, move_gen::MoveGen, position::Position},
+    repr::{_move, board::Board, move_gen::MoveGen, perft, position::Position},
     utils::{
         fen_tool::{self, DEFAULT_FEN},
         zobrist::Zobrist,
@@ -48,8 +48,8 @@ impl TestEngine {
         pos.unmake_move(mov, &self.zobrist);
     }
 
-    pub fn perft(&self, target_depth: usize, pos: &mut Position) -> u32 {
-        go_perft(target_depth, pos, &self.move_gen, &self.zobrist)
+    pub fn perft(&self, target_depth: usize, pos: &mut Position) -> u64 {
+        perft::perft(target_depth, pos, &self.move_gen, &self.zobrist)
     }
 }
 
@@ -59,12 +59,21 @@ impl Default for TestEngine {
     }
 }
 
+///The legal move of **pos** written as **uci**, panics if there is none
+pub fn find_uci(pos: &Position, uci: &str) -> u32 {
+    pos.legal_moves()
+        .iter()
+        .copied()
+        .find(|mov| _move::to_uci(*mov, &pos.board) == uci)
+        .unwrap_or_else(|| panic!("{uci} not legal"))
+}
+
 #[derive(Clone, Copy)]
 pub struct PerftCase {
     pub name: &'static str,
     pub fen: &'static str,
     pub depth: usize,
-    pub expected: u32,
+    pub expected: u64,
 }
 
 pub const PERFT_CASES: [PerftCase; 6] = [
@@ -105,31 +114,3 @@ pub const PERFT_CASES: [PerftCase; 6] = [
         expected: 164_075_551,
     },
 ];
-
-pub fn go_perft(
-    target_depth: usize,
-    pos: &mut Position,
-    move_gen: &MoveGen,
-    zobrist: &Zobrist,
-) -> u32 {
-    assert!(target_depth > 1);
-
-    fn inner(d_left: usize, pos: &mut Position, move_gen: &MoveGen, zobrist: &Zobrist) -> usize {
-        if d_left == 1 {
-            return pos.legal_search_moves().len();
-        }
-
-        let mut from_here = 0;
-        let (s, e) = pos.search_move_bounds();
-        for i in s..e {
-            let mov = pos.move_arr[i];
-            pos.make_move(mov, true, true, false, move_gen, zobrist);
-            from_here += inner(d_left - 1, pos, move_gen, zobrist);
-            pos.unmake_move(mov, zobrist);
-        }
-
-        from_here
-    }
-
-    inner(target_depth, pos, move_gen, zobrist) as u32
-}
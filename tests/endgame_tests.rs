@@ -0,0 +1,152 @@
+mod common;
+
+use common::{TestEngine, MULTITHREADED};
+use rusty_engine::{
+    repr::{
+        _move,
+        types::{BLACK, WHITE},
+    },
+    search::{
+        endgame::{self, EndgameKind, KNOWN_WIN},
+        eval::{Evaluator, MATE_BOUND},
+        search_config::SearchMode,
+        searcher::Searcher,
+    },
+};
+
+fn endgame_eval(engine: &TestEngine, fen: &str) -> i16 {
+    let board = engine.board(fen);
+    Evaluator::default().eval(board.pieces, board.turn, board.late_game_phase)
+}
+
+fn kpk_win(engine: &TestEngine, fen: &str) -> bool {
+    let board = engine.board(fen);
+    let (kind, strong_side) = endgame::classify(&board.pieces).expect("KPK signature");
+    assert_eq!(kind, EndgameKind::KPK);
+    let (pawn_u, strong_king_u, weak_king_u) = if strong_side == WHITE { (0, 5, 11) } else { (6, 11, 5) };
+    endgame::kpk_bitbase().probe(
+        strong_side,
+        board.pieces[strong_king_u].trailing_zeros(),
+        board.pieces[pawn_u].trailing_zeros(),
+        board.pieces[weak_king_u].trailing_zeros(),
+        board.turn,
+    )
+}
+
+#[test]
+fn classifies_material_signatures_for_both_sides() {
+    let engine = TestEngine::new();
+    let classify = |fen: &str| endgame::classify(&engine.board(fen).pieces);
+
+    assert_eq!(classify("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"), Some((EndgameKind::KQK, WHITE)));
+    assert_eq!(classify("4k2r/8/8/8/8/8/8/4K3 w - - 0 1"), Some((EndgameKind::KRK, BLACK)));
+    assert_eq!(classify("4k3/8/8/8/8/8/8/2B1KN2 b - - 0 1"), Some((EndgameKind::KBNK, WHITE)));
+    assert_eq!(classify("4k3/4p3/8/8/8/8/8/4K3 w - - 0 1"), Some((EndgameKind::KPK, BLACK)));
+
+    assert_eq!(classify("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), None);
+    assert_eq!(classify("4k3/7p/8/8/8/8/8/3QK3 w - - 0 1"), None);
+    assert_eq!(classify("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1"), None);
+    assert_eq!(classify("4k3/8/8/8/8/8/4PP2/4K3 w - - 0 1"), None);
+}
+
+#[test]
+fn kpk_bitbase_knows_basic_wins_and_draws() {
+    let engine = TestEngine::new();
+
+    // King on the sixth rank in front of its pawn wins regardless of the side to move.
+    assert!(kpk_win(&engine, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"));
+    assert!(kpk_win(&engine, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"));
+    // Defending king outside the square of the pawn.
+    assert!(kpk_win(&engine, "7k/8/8/8/P7/8/8/K7 w - - 0 1"));
+
+    // Rook pawn with the defending king in the corner.
+    assert!(!kpk_win(&engine, "k7/8/8/8/8/8/P7/K7 w - - 0 1"));
+    // Stalemate.
+    assert!(!kpk_win(&engine, "4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"));
+    // Undefended pawn is captured.
+    assert!(!kpk_win(&engine, "8/8/8/8/8/4k3/4P3/K7 b - - 0 1"));
+}
+
+#[test]
+fn kpk_bitbase_is_color_and_file_symmetric() {
+    let engine = TestEngine::new();
+
+    for (white_fen, black_fen, flipped_fen) in [
+        (
+            "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1",
+            "8/8/8/8/4p3/4k3/8/4K3 w - - 0 1",
+            "3k4/8/3K4/3P4/8/8/8/8 b - - 0 1",
+        ),
+        (
+            "k7/8/8/8/8/8/P7/K7 w - - 0 1",
+            "k7/p7/8/8/8/8/8/K7 b - - 0 1",
+            "7k/8/8/8/8/8/7P/7K w - - 0 1",
+        ),
+        (
+            "8/8/8/3k4/8/8/2P5/2K5 w - - 0 1",
+            "2k5/2p5/8/8/3K4/8/8/8 b - - 0 1",
+            "8/8/8/4k3/8/8/5P2/5K2 w - - 0 1",
+        ),
+    ] {
+        let expected = kpk_win(&engine, white_fen);
+        assert_eq!(kpk_win(&engine, black_fen), expected, "{black_fen}");
+        assert_eq!(kpk_win(&engine, flipped_fen), expected, "{flipped_fen}");
+    }
+}
+
+#[test]
+fn evaluator_defers_to_endgame_scores() {
+    let engine = TestEngine::new();
+
+    let won = endgame_eval(&engine, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1");
+    assert!(won > KNOWN_WIN && won < MATE_BOUND);
+    assert_eq!(endgame_eval(&engine, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), -won);
+    assert_eq!(endgame_eval(&engine, "k7/8/8/8/8/8/P7/K7 w - - 0 1"), 0);
+
+    let krk = endgame_eval(&engine, "8/8/8/3k4/8/8/8/R3K3 b - - 0 1");
+    assert!(krk < -KNOWN_WIN, "bare king to move should see a lost position, got {krk}");
+}
+
+#[test]
+fn mop_up_drives_bare_king_to_the_edge() {
+    let engine = TestEngine::new();
+
+    let centralised = endgame_eval(&engine, "8/8/8/3k4/8/3K4/8/R7 w - - 0 1");
+    let on_edge = endgame_eval(&engine, "3k4/8/3K4/8/8/8/8/R7 w - - 0 1");
+    let kings_apart = endgame_eval(&engine, "3k4/8/8/8/8/8/8/R3K3 w - - 0 1");
+    assert!(on_edge > centralised);
+    assert!(on_edge > kings_apart);
+
+    let queen_far = endgame_eval(&engine, "8/8/8/4K3/8/8/8/k6q b - - 0 1");
+    let queen_close = endgame_eval(&engine, "8/8/8/8/8/8/2K5/k6q b - - 0 1");
+    assert!(queen_close > queen_far);
+}
+
+#[test]
+fn kbnk_prefers_the_bishop_colored_corner() {
+    let engine = TestEngine::new();
+
+    // Dark squared bishops mate in a1 or h8.
+    let right_corner = endgame_eval(&engine, "8/8/8/8/8/2K5/8/k1B2N2 w - - 0 1");
+    let wrong_corner = endgame_eval(&engine, "k2B1N2/8/2K5/8/8/8/8/8 w - - 0 1");
+    assert!(right_corner > wrong_corner);
+
+    // Light squared bishop on d1 mates in a8 or h1.
+    let right_corner = endgame_eval(&engine, "k7/8/2K5/8/8/8/8/3B1N2 w - - 0 1");
+    let wrong_corner = endgame_eval(&engine, "8/8/8/8/8/2K5/8/k2B1N2 w - - 0 1");
+    assert!(right_corner > wrong_corner);
+}
+
+#[test]
+fn search_still_prefers_mate_in_recognised_endgame() {
+    let engine = TestEngine::new();
+    let pos = engine.position("k7/8/1K6/8/8/8/7Q/8 w - - 0 1");
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(3);
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+
+    let best = searcher.collect_best_move().expect("a legal move exists");
+    assert_eq!(_move::to_string(best, true), "h2h8");
+    let tte = searcher.tt.probe(pos.board.zhash).expect("root stored");
+    assert!(tte.score >= MATE_BOUND);
+}
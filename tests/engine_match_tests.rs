@@ -0,0 +1,268 @@
+mod common;
+
+use common::TestEngine;
+use rusty_engine::{
+    game::{
+        engine_match::{self, MatchPlayer, MatchScore, SearcherPlayer, Sprt, SprtDecision},
+        game_state::GameState,
+    },
+    repr::{
+        _move,
+        types::{BLACK, WHITE},
+    },
+    search::search_config::SearchMode,
+    utils::fen_tool::DEFAULT_FEN,
+};
+
+//answers with a fixed list of moves, then resigns by erring
+struct ScriptedPlayer {
+    moves: Vec<&'static str>,
+}
+
+impl MatchPlayer for ScriptedPlayer {
+    fn name(&self) -> &str {
+        "Scripted"
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn best_move(&mut self, _start_fen: &str, _moves: &[String], _search_mode: SearchMode) -> Result<String, String> {
+        if self.moves.is_empty() {
+            return Err("Out of moves".to_string());
+        }
+        Ok(self.moves.remove(0).to_string())
+    }
+}
+
+fn scripted(moves: &[&'static str]) -> ScriptedPlayer {
+    ScriptedPlayer { moves: moves.to_vec() }
+}
+
+#[test]
+fn elo_follows_the_logistic_curve() {
+    let score = MatchScore { wins: 60, draws: 20, losses: 20 };
+    assert_eq!(score.games(), 100);
+    assert!((score.score() - 0.7).abs() < 1e-9);
+    assert!((score.variance() - 0.16).abs() < 1e-9);
+    assert!((score.elo() - 147.19).abs() < 0.01);
+    assert!(score.elo_error() > 0.0 && score.elo_error() < 100.0);
+
+    let mirrored = MatchScore { wins: 20, draws: 20, losses: 60 };
+    assert!((mirrored.elo() + score.elo()).abs() < 1e-9);
+    for elo in [-300.0, -5.0, 0.0, 42.0] {
+        assert!((engine_match::score_to_elo(engine_match::elo_to_score(elo)) - elo).abs() < 1e-9);
+    }
+
+    let even = MatchScore { wins: 10, draws: 80, losses: 10 };
+    assert_eq!(even.elo(), 0.0);
+    //more games narrow the error bar
+    let longer = MatchScore { wins: 40, draws: 320, losses: 40 };
+    assert!(longer.elo_error() < even.elo_error());
+
+    let perfect = MatchScore { wins: 3, draws: 0, losses: 0 };
+    assert_eq!(perfect.elo(), f64::INFINITY);
+    assert_eq!(perfect.elo_error(), f64::INFINITY);
+    assert_eq!(MatchScore::default().score(), 0.5);
+}
+
+#[test]
+fn sprt_decides_on_clear_results_only() {
+    let sprt = Sprt::default();
+    let (lower, upper) = sprt.bounds();
+    assert!((upper - (0.95f64 / 0.05).ln()).abs() < 1e-9);
+    assert!((lower + upper).abs() < 1e-9);
+
+    let mut score = MatchScore::default();
+    assert_eq!(score.llr(sprt.elo0, sprt.elo1), 0.0);
+    for result in [1.0, 0.5, 0.0, 1.0] {
+        score.add(result);
+    }
+    assert_eq!(score, MatchScore { wins: 2, draws: 1, losses: 1 });
+    assert_eq!(sprt.decision(&score), SprtDecision::Continue);
+
+    //the llr grows with the number of games at the same score
+    let ahead = MatchScore { wins: 60, draws: 20, losses: 20 };
+    assert!((ahead.llr(0.0, 5.0) - 0.8832).abs() < 1e-3);
+    assert_eq!(sprt.decision(&ahead), SprtDecision::Continue);
+    let far_ahead = MatchScore { wins: 600, draws: 200, losses: 200 };
+    assert_eq!(sprt.decision(&far_ahead), SprtDecision::AcceptH1);
+    let level = MatchScore { wins: 6000, draws: 8000, losses: 6000 };
+    assert!(level.llr(0.0, 5.0) < 0.0);
+    assert_eq!(sprt.decision(&level), SprtDecision::AcceptH0);
+}
+
+#[test]
+fn games_are_adjudicated_with_game_state() {
+    let engine = TestEngine::new();
+    let fools_mate = engine_match::play_game(
+        &mut scripted(&["f2f3", "g2g4"]),
+        &mut scripted(&["e7e5", "d8h4"]),
+        DEFAULT_FEN,
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(matches!(fools_mate.state, GameState::Checkmate(WHITE)));
+    assert_eq!(fools_mate.pgn_result(), "0-1");
+    assert_eq!(fools_mate.white_score(), 0.0);
+    assert_eq!(fools_mate.moves.len(), 4);
+    let pgn = fools_mate.to_pgn(&[("White", "a"), ("Black", "b")], &engine.move_gen, &engine.zobrist);
+    assert!(pgn.contains("[Termination \"Checkmate, Black wins\"]"));
+    assert!(pgn.contains("2. g4 Qh4# 0-1"));
+
+    let shuffle = ["g1f3", "f3g1", "g1f3", "f3g1"];
+    let repetition = engine_match::play_game(
+        &mut scripted(&shuffle),
+        &mut scripted(&["g8f6", "f6g8", "g8f6", "f6g8"]),
+        DEFAULT_FEN,
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(matches!(repetition.state, GameState::DrawByRepetition));
+    assert_eq!(repetition.moves.len(), 8);
+    assert_eq!(repetition.white_score(), 0.5);
+
+    let stalemate = engine_match::play_game(
+        &mut scripted(&[]),
+        &mut scripted(&[]),
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(matches!(stalemate.state, GameState::Stalemate));
+    assert!(stalemate.moves.is_empty());
+}
+
+#[test]
+fn illegal_and_missing_moves_forfeit() {
+    let engine = TestEngine::new();
+    let illegal = engine_match::play_game(
+        &mut scripted(&["e2e4"]),
+        &mut scripted(&["e7e4"]),
+        DEFAULT_FEN,
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    let (loser, reason) = illegal.forfeit.as_ref().unwrap();
+    assert_eq!(*loser, BLACK);
+    assert!(reason.contains("Illegal move e7e4"));
+    assert_eq!(illegal.pgn_result(), "1-0");
+    assert_eq!(illegal.moves.len(), 1);
+
+    let silent = engine_match::play_game(
+        &mut scripted(&[]),
+        &mut scripted(&[]),
+        DEFAULT_FEN,
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert_eq!(silent.forfeit.as_ref().unwrap().0, WHITE);
+    assert_eq!(silent.white_score(), 0.0);
+    assert!(silent.termination().starts_with("Forfeit"));
+
+    assert!(engine_match::play_game(
+        &mut scripted(&[]),
+        &mut scripted(&[]),
+        "not a fen",
+        SearchMode::StaticDepth(1),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .is_err());
+}
+
+#[test]
+fn searcher_players_finish_a_game() {
+    let engine = TestEngine::new();
+    let mut strong = SearcherPlayer::new("depth 3");
+    let mut weak = SearcherPlayer::new("no quiescence");
+    weak.cpu_game.searcher.search_config.quiescence = false;
+
+    //queen up, the searcher has to convert before the fifty move rule
+    let record = engine_match::play_game(
+        &mut strong,
+        &mut weak,
+        "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+        SearchMode::StaticDepth(3),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(record.forfeit.is_none());
+    assert!(!matches!(record.state, GameState::InProgress));
+    assert!(record.moves.len() < 200);
+}
+
+#[test]
+fn chess960_castling_is_written_king_takes_rook() {
+    let engine = TestEngine::new();
+    //long castling, king b1 to c1 and rook a1 to d1, mates
+    let epd = "8/8/8/B7/5Q2/3k4/8/RK4N1 w A -\n8/8/8/B7/8/3k4/8/RK4N1 w A -\n";
+    let openings = engine_match::openings_from_epd(epd, &engine.move_gen, &engine.zobrist).unwrap();
+    assert!(engine_match::needs_chess960(&openings, &engine.move_gen, &engine.zobrist));
+    assert!(!engine_match::needs_chess960(&[DEFAULT_FEN.to_string()], &engine.move_gen, &engine.zobrist));
+
+    let mate = engine_match::play_game(
+        &mut SearcherPlayer::new("castler"),
+        &mut scripted(&[]),
+        &openings[0],
+        SearchMode::StaticDepth(2),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(mate.forfeit.is_none(), "{}", mate.termination());
+    assert!(matches!(mate.state, GameState::Checkmate(BLACK)));
+    assert!(_move::is_castle(mate.moves[0]));
+
+    //the searcher reads the castling move back from the move list
+    let reply = engine_match::play_game(
+        &mut scripted(&["b1a1"]),
+        &mut SearcherPlayer::new("replier"),
+        &openings[1],
+        SearchMode::StaticDepth(2),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert_eq!(reply.moves.len(), 2);
+    assert!(_move::is_castle(reply.moves[0]));
+    assert_eq!(reply.forfeit.as_ref().unwrap(), &(WHITE, "Out of moves".to_string()));
+}
+
+#[test]
+fn openings_come_from_epd_and_pgn() {
+    let engine = TestEngine::new();
+    let epd = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id \"e4\";\n\n# comment\n4k3/8/8/8/8/8/8/4K3 w - -\n";
+    assert_eq!(
+        engine_match::openings_from_epd(epd, &engine.move_gen, &engine.zobrist).unwrap(),
+        [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        ]
+    );
+
+    let pgn = "[Event \"a\"]\n\n1. e4 c5 2. Nf3 *\n\n[Event \"b\"]\n\n1. d4 (1. c4) 1... d5 *\n";
+    let openings = engine_match::openings_from_pgn(pgn, &engine.move_gen, &engine.zobrist).unwrap();
+    assert_eq!(openings.len(), 2);
+    assert_eq!(
+        engine.board(&openings[0]).zhash,
+        engine.board("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").zhash
+    );
+    assert_eq!(
+        engine.board(&openings[1]).zhash,
+        engine.board("rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq d6 0 2").zhash
+    );
+    assert!(engine_match::openings_from_pgn("1. e5 *", &engine.move_gen, &engine.zobrist).is_err());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::TestEngine;
+use rusty_engine::{
+    repr::_move,
+    search::search_config::SearchMode,
+    utils::epd::{self, EpdRecord},
+};
+
+const SUITE: &str = r#"
+# a tiny regression suite
+7k/8/5KQ1/8/8/8/8/8 w - - bm Qg7#; id "mate.1"; c0 "mate in one";
+r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; dm 1; id "mate.2";
+6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - hmvc 3; fmvn 20; bm d1d8; dm 1; id "back.rank";
+4k3/8/8/8/8/8/1q6/R3K3 w - - am Rb1 a1c1; id "avoid"; c0 "save the rook; but not on b1";
+"#;
+
+fn uci(moves: &[u32]) -> Vec<String> {
+    moves.iter().map(|mov| _move::to_string(*mov, true)).collect()
+}
+
+#[test]
+fn parses_fields_and_known_opcodes() {
+    let engine = TestEngine::new();
+    let records = epd::parse_epd(SUITE, &engine.move_gen, &engine.zobrist).unwrap();
+    assert_eq!(records.len(), 4);
+
+    assert_eq!(records[0].fen, "7k/8/5KQ1/8/8/8/8/8 w - - 0 1");
+    assert_eq!(records[0].id.as_deref(), Some("mate.1"));
+    assert_eq!(records[0].comment.as_deref(), Some("mate in one"));
+    assert_eq!(uci(&records[0].best_moves), ["g6g7"]);
+    assert_eq!(records[0].board.zhash, engine.board("7k/8/5KQ1/8/8/8/8/8 w - - 0 1").zhash);
+
+    assert_eq!(records[1].direct_mate, Some(1));
+    assert_eq!(uci(&records[1].best_moves), ["f3f7"]);
+
+    assert_eq!(records[2].fen, "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 3 20");
+    assert_eq!(uci(&records[2].best_moves), ["d1d8"]);
+
+    assert!(records[3].best_moves.is_empty());
+    assert_eq!(uci(&records[3].avoid_moves), ["a1b1", "a1c1"]);
+    assert_eq!(records[3].comment.as_deref(), Some("save the rook; but not on b1"));
+    assert_eq!(records[3].operations.len(), 3);
+}
+
+#[test]
+fn rejects_malformed_records() {
+    let engine = TestEngine::new();
+    let parse = |line: &str| EpdRecord::parse(line, &engine.move_gen, &engine.zobrist);
+
+    assert!(parse("7k/8/5KQ1/8/8/8/8/8 w -").is_err());
+    assert!(parse("7k/8/5KQ1/8/8/8/8/9 w - - bm Qg7;").is_err());
+    assert!(parse("7k/8/5KQ1/8/8/8/8/8 w - - bm Qg7").is_err());
+    assert!(parse("7k/8/5KQ1/8/8/8/8/8 w - - id \"open;").is_err());
+    assert!(parse("7k/8/5KQ1/8/8/8/8/8 w - - bm Qa1b2;").is_err());
+    assert!(parse("7k/8/5KQ1/8/8/8/8/8 w - - dm x;").is_err());
+    assert!(epd::parse_epd("\n\n7k/8/5KQ1/8/8/8/8/8 w - - bm Kh8;", &engine.move_gen, &engine.zobrist)
+        .err()
+        .unwrap()
+        .starts_with("Line 3"));
+}
+
+#[test]
+fn runner_solves_mates_and_checks_avoid_moves() {
+    let engine = TestEngine::new();
+    let records = epd::parse_epd(SUITE, &engine.move_gen, &engine.zobrist).unwrap();
+
+    for record in records.iter().take(3) {
+        let outcome = epd::run_record(record, SearchMode::StaticDepth(3), &engine.move_gen, &engine.zobrist);
+        assert!(outcome.solved, "{:?}", record.id);
+        assert_eq!(outcome.depth, 3);
+        assert!(outcome.time_to_solution.unwrap() <= outcome.time_ms);
+        assert!(outcome.nodes > 0);
+    }
+
+    let avoid = &records[3];
+    let outcome = epd::run_record(avoid, SearchMode::StaticDepth(2), &engine.move_gen, &engine.zobrist);
+    assert_eq!(outcome.solved, !avoid.avoid_moves.contains(&outcome.best_move.unwrap()));
+    assert!(!avoid.is_solved_by(avoid.avoid_moves[0], 0));
+    assert!(!records[1].is_solved_by(records[1].best_moves[0], 0), "dm needs a mate score");
+}
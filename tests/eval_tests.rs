@@ -7,10 +7,13 @@ use rusty_engine::{
     repr::{
         _move,
         position::Position,
-        types::{BLACK, B_KING_U, B_KNIGHT_U, B_PAWN_U, WHITE, W_KING_U, W_PAWN_U, W_QUEEN},
+        types::{
+            BLACK, B_KING_U, B_KNIGHT_U, B_PAWN_U, B_QUEEN_U, WHITE, W_BISHOP_U, W_KING_U,
+            W_KNIGHT_U, W_PAWN_U, W_QUEEN, W_QUEEN_U, W_ROOK_U,
+        },
     },
     search::{
-        eval::{Evaluator, MAX_LATE_GAME_PHASE, PIECE_MATERIAL_VALUE},
+        eval::{EvalParams, Evaluator, MAX_LATE_GAME_PHASE, PIECE_MATERIAL_VALUE},
         table_loader::read_table_value_file,
     },
 };
@@ -19,8 +22,10 @@ const EARLY_GAME_PHASE: usize = 0;
 const MIDDLE_GAME_PHASE: usize = 12;
 const LATE_GAME_PHASE: usize = MAX_LATE_GAME_PHASE;
 
-fn tapered_value(early: i16, late: i16, coefficients: (f32, f32)) -> i16 {
-    (coefficients.0 * f32::from(early) + coefficients.1 * f32::from(late)) as i16
+fn tapered_value(early: i32, late: i32, phase: usize) -> i16 {
+    let late_weight = phase as i32;
+    let early_weight = MAX_LATE_GAME_PHASE as i32 - late_weight;
+    ((early * early_weight + late * late_weight) / MAX_LATE_GAME_PHASE as i32) as i16
 }
 
 fn repo_root() -> PathBuf {
@@ -90,20 +95,18 @@ fn eval_tapers_pawn_and_king_tables_across_given_phase() {
     let pawn_end = load_table("pawn_l.txt");
     let king_end = load_table("king_l.txt");
 
-    let material = PIECE_MATERIAL_VALUE[W_PAWN_U] + PIECE_MATERIAL_VALUE[W_KING_U];
-    let phases = [
-        (EARLY_GAME_PHASE, (1.0, 0.0)),
-        (MIDDLE_GAME_PHASE, (0.52, 0.48)),
-        (LATE_GAME_PHASE, (0.04, 0.96)),
-    ];
-
-    for (phase, coefficients) in phases {
-        let expected = material
-            + tapered_value(pawn_open[17], pawn_end[17], coefficients)
-            + tapered_value(king_open[20], king_end[20], coefficients);
+    let material = i32::from(PIECE_MATERIAL_VALUE[W_PAWN_U] + PIECE_MATERIAL_VALUE[W_KING_U]);
+    let early = material + i32::from(pawn_open[17] + king_open[20]);
+    let late = material + i32::from(pawn_end[17] + king_end[20]);
 
-        assert_eq!(evaluator.eval(pieces, WHITE, phase), expected);
+    for phase in [EARLY_GAME_PHASE, MIDDLE_GAME_PHASE, LATE_GAME_PHASE] {
+        assert_eq!(
+            evaluator.eval(pieces, WHITE, phase),
+            tapered_value(early, late, phase)
+        );
     }
+    assert_eq!(evaluator.eval(pieces, WHITE, EARLY_GAME_PHASE), early as i16);
+    assert_eq!(evaluator.eval(pieces, WHITE, LATE_GAME_PHASE), late as i16);
 }
 
 #[test]
@@ -122,17 +125,16 @@ fn eval_with_both_sides_pieces_is_consistent_for_each_mover() {
     let pawn_late = load_table("pawn_l.txt");
     let king_early = load_table("king_e.txt");
     let king_late = load_table("king_l.txt");
-    let coefficients = (0.52, 0.48);
 
-    let white_sum = PIECE_MATERIAL_VALUE[W_PAWN_U]
-        + tapered_value(pawn_early[18], pawn_late[18], coefficients)
-        + PIECE_MATERIAL_VALUE[W_KING_U]
-        + tapered_value(king_early[4], king_late[4], coefficients);
-    let black_sum = PIECE_MATERIAL_VALUE[B_PAWN_U]
-        + tapered_value(pawn_early[43 ^ 56], pawn_late[43 ^ 56], coefficients)
-        + PIECE_MATERIAL_VALUE[B_KING_U]
-        + tapered_value(king_early[60 ^ 56], king_late[60 ^ 56], coefficients);
-    let expected_white = white_sum - black_sum;
+    let white_early = i32::from(pawn_early[18] + king_early[4]);
+    let white_late = i32::from(pawn_late[18] + king_late[4]);
+    let black_early = i32::from(pawn_early[43 ^ 56] + king_early[60 ^ 56]);
+    let black_late = i32::from(pawn_late[43 ^ 56] + king_late[60 ^ 56]);
+    let expected_white = tapered_value(
+        white_early - black_early,
+        white_late - black_late,
+        MIDDLE_GAME_PHASE,
+    );
     let expected_black = -expected_white;
 
     let eval_from_white = evaluator.eval(pieces, WHITE, MIDDLE_GAME_PHASE);
@@ -143,6 +145,56 @@ fn eval_with_both_sides_pieces_is_consistent_for_each_mover() {
     assert_eq!(eval_from_white, -eval_from_black);
 }
 
+#[test]
+fn eval_tapers_every_piece_with_custom_params() {
+    let mut params = EvalParams::default();
+    params.material_eg[W_KNIGHT_U] = 280;
+    params.pst_eg[W_KNIGHT_U] = [-10; 64];
+    params.pst_mg[W_QUEEN_U] = [0; 64];
+    params.pst_eg[W_QUEEN_U] = [30; 64];
+    let evaluator = Evaluator::with_params(params.clone());
+
+    // White knight on d4 (27), black queen on d8 (59), kings on e1 and e8 cancel out.
+    let mut pieces = [0u64; 12];
+    pieces[W_KNIGHT_U] = 1u64 << 27;
+    pieces[B_QUEEN_U] = 1u64 << 59;
+    pieces[W_KING_U] = 1u64 << 4;
+    pieces[B_KING_U] = 1u64 << 60;
+
+    let early = i32::from(params.material_mg[W_KNIGHT_U] + params.pst_mg[W_KNIGHT_U][27])
+        - i32::from(params.material_mg[W_QUEEN_U]);
+    let late = i32::from(params.material_eg[W_KNIGHT_U] - 10)
+        - i32::from(params.material_eg[W_QUEEN_U] + 30);
+
+    for phase in 0..=MAX_LATE_GAME_PHASE {
+        let expected = tapered_value(early, late, phase);
+        assert_eq!(evaluator.eval(pieces, WHITE, phase), expected);
+        assert_eq!(evaluator.eval(pieces, BLACK, phase), -expected);
+    }
+}
+
+#[test]
+fn default_params_match_embedded_tables() {
+    let params = EvalParams::default();
+
+    for (piece, file_name) in [
+        (W_KNIGHT_U, "knight.txt"),
+        (W_BISHOP_U, "bishop.txt"),
+        (W_ROOK_U, "rook.txt"),
+        (W_QUEEN_U, "queen.txt"),
+    ] {
+        let table = load_table(file_name);
+        assert_eq!(params.pst_mg[piece].as_slice(), table.as_slice());
+        assert_eq!(params.pst_eg[piece].as_slice(), table.as_slice());
+    }
+    assert_eq!(params.pst_mg[W_PAWN_U].as_slice(), load_table("pawn_e.txt").as_slice());
+    assert_eq!(params.pst_eg[W_PAWN_U].as_slice(), load_table("pawn_l.txt").as_slice());
+    assert_eq!(params.pst_mg[W_KING_U].as_slice(), load_table("king_e.txt").as_slice());
+    assert_eq!(params.pst_eg[W_KING_U].as_slice(), load_table("king_l.txt").as_slice());
+    assert_eq!(params.material_mg, params.material_eg);
+    assert_eq!(params.material_mg.as_slice(), &PIECE_MATERIAL_VALUE[..6]);
+}
+
 #[test]
 fn board_initializes_late_game_phase_from_material() {
     let engine = TestEngine::new();
@@ -230,6 +282,56 @@ fn late_game_phase_round_trip_survives_promotion_past_phase_zero() {
     assert_eq!(pos.board.late_game_phase, 2);
 }
 
+#[test]
+fn trace_terms_add_up_to_eval() {
+    let engine = TestEngine::new();
+    //tuned terms show up in both
+    let mut params = EvalParams::default();
+    params.material_eg[W_KNIGHT_U] += 50;
+    params.pst_mg[W_PAWN_U][28] += 30;
+    params.pst_eg[W_KING_U][4] -= 20;
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        "4kb2/pp6/8/8/8/8/PPP5/2B1K3 w - - 0 1",
+        "8/8/8/3k4/8/8/8/R3K3 b - - 0 1",
+        "4k3/8/8/8/4P3/8/8/1N2K3 w - - 0 1",
+    ];
+
+    let evaluators = [Evaluator::default(), Evaluator::with_params(params)];
+
+    for (evaluator, fen) in evaluators.iter().flat_map(|evaluator| fens.map(|fen| (evaluator, fen))) {
+        let board = engine.board(fen);
+        let trace = evaluator.trace(board.pieces, board.turn, board.late_game_phase);
+        let eval = evaluator.eval(board.pieces, board.turn, board.late_game_phase);
+        let white_eval = if board.turn == WHITE { eval } else { -eval };
+
+        assert_eq!(trace.score, white_eval, "{fen}");
+        assert_eq!(trace.terms.len(), 7, "material and one PST per piece kind");
+        assert_eq!(trace.mg, trace.terms.iter().map(|term| term.mg_total()).sum::<i32>());
+        assert_eq!(trace.eg, trace.terms.iter().map(|term| term.eg_total()).sum::<i32>());
+        assert_eq!(trace.late_game_phase, board.late_game_phase);
+    }
+}
+
+#[test]
+fn trace_reports_material_per_side_and_prints_table() {
+    let engine = TestEngine::new();
+    let board = engine.board("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+    let trace = Evaluator::default().trace(board.pieces, board.turn, board.late_game_phase);
+
+    let material = &trace.terms[0];
+    let kings = PIECE_MATERIAL_VALUE[W_KING_U] as i32;
+    assert_eq!(material.mg, [kings + PIECE_MATERIAL_VALUE[W_ROOK_U] as i32, kings]);
+    assert_eq!(material.eg_total(), PIECE_MATERIAL_VALUE[W_ROOK_U] as i32);
+    assert!(trace.endgame.is_some());
+
+    let table = trace.to_string();
+    for expected in ["Material", "Rook PST", "King PST", "Total", "Final evaluation"] {
+        assert!(table.contains(expected), "missing {expected} in\n{table}");
+    }
+}
+
 fn legal_move_matching<F>(pos: &Position, matches: F) -> u32
 where
     F: Fn(u32) -> bool,
@@ -0,0 +1,147 @@
+use std::time::Instant;
+
+use rusty_engine::{
+    game::{clock::TimeControl, game::Game, game_state::GameState},
+    repr::{position::Position, types::WHITE},
+    utils::fen_tool::{board_to_fen, DEFAULT_FEN},
+};
+
+#[test]
+fn undo_and_redo_restore_the_position() {
+    let mut game = Game::default();
+    assert!(!game.can_undo() && game.undo().is_none());
+    play(&mut game, &[("e2", "e4"), ("d7", "d5")]);
+    let before_capture: String = board_to_fen(&game.position.board);
+    play(&mut game, &[("e4", "d5")]);
+    let after_capture: String = board_to_fen(&game.position.board);
+
+    let taken_back = game.undo().unwrap();
+    assert_eq!(board_to_fen(&game.position.board), before_capture);
+    assert_eq!(game.position.board.zhash, game.board_history.last().unwrap().zhash);
+    assert_eq!(game.ply(), 2);
+    assert!(game.can_redo());
+    assert!(game.position.legal_moves().contains(&taken_back));
+
+    assert_eq!(game.redo(), Some(taken_back));
+    assert_eq!(board_to_fen(&game.position.board), after_capture);
+    assert!(!game.can_redo() && game.redo().is_none());
+
+    assert_eq!(game.goto_ply(0), 0);
+    assert_eq!(board_to_fen(&game.position.board), DEFAULT_FEN);
+    assert!(game.position.played_moves_stack.is_empty());
+    assert_eq!(game.goto_ply(usize::MAX), 3);
+    assert_eq!(board_to_fen(&game.position.board), after_capture);
+    assert!(game.to_pgn(&[], None).contains("1. e4 d5 2. exd5 *"));
+}
+
+#[test]
+fn new_moves_replace_the_redo_line_unless_they_repeat_it() {
+    let mut game = Game::default();
+    play(&mut game, &[("e2", "e4"), ("e7", "e5"), ("g1", "f3")]);
+    game.goto_ply(1);
+    play(&mut game, &[("e7", "e5")]);
+    assert!(game.can_redo(), "replaying the next move keeps the rest of the line");
+    assert_eq!(game.goto_ply(3), 3);
+
+    game.goto_ply(1);
+    play(&mut game, &[("c7", "c5")]);
+    assert!(!game.can_redo());
+    assert_eq!(game.goto_ply(3), 2);
+}
+
+#[test]
+fn the_line_includes_taken_back_moves() {
+    let mut game = Game::default();
+    play(&mut game, &[("g1", "f3"), ("d7", "d5"), ("f3", "e5"), ("d5", "d4"), ("c2", "c4")]);
+    game.goto_ply(2);
+    assert_eq!(game.played_moves().len(), 2);
+    assert_eq!(game.line().len(), 5);
+    assert_eq!(game.line_san(), ["Nf3", "d5", "Ne5", "d4", "c4"]);
+    assert!(game.to_pgn(&[], None).contains("1. Nf3 d5 2. Ne5 d4 3. c4 *"));
+
+    //the start position's side to move is kept
+    let black_to_move: Position =
+        Position::from("4k3/8/8/8/8/8/4p3/4K3 b - - 0 1", &game.move_gen, &game.zobrist).unwrap();
+    game.import_position(black_to_move);
+    assert!(game.line_san().is_empty());
+    play(&mut game, &[("e8", "d7"), ("e1", "e2")]);
+    assert_eq!(game.line_san(), ["Kd7", "Kxe2"]);
+}
+
+#[test]
+fn game_state_and_repetition_history_follow_navigation() {
+    let mut game = Game::default();
+    let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+    play(&mut game, &shuffle);
+    play(&mut game, &shuffle);
+    assert!(matches!(game.game_state, GameState::DrawByRepetition));
+
+    game.undo();
+    assert!(matches!(game.game_state, GameState::InProgress));
+    assert!(!game.searcher.search_data[0].in_three_fold(&game.position));
+    game.redo();
+    assert!(matches!(game.game_state, GameState::DrawByRepetition));
+    assert!(game.searcher.search_data[0].in_three_fold(&game.position));
+
+    //the search only remembers positions since the last irreversible move
+    let mut game = Game::default();
+    play(&mut game, &[("g1", "f3"), ("e7", "e5"), ("f3", "g1"), ("e8", "e7")]);
+    game.undo();
+    assert_eq!(game.searcher.search_data[0].board_hash_history.len(), 2);
+    assert_eq!(game.searcher.search_data[0].board_hash_history[1], game.position.board.zhash);
+    game.goto_ply(1);
+    assert_eq!(game.searcher.search_data[0].board_hash_history.len(), 2);
+
+    let mate: Position = Position::from("7k/8/5KQ1/8/8/8/8/8 w - - 0 1", &game.move_gen, &game.zobrist).unwrap();
+    game.import_position(mate);
+    assert!(!game.can_undo());
+    play(&mut game, &[("g6", "g7")]);
+    assert!(game.is_over());
+    game.undo();
+    assert!(!game.is_over());
+    //the copied game is the whole line with its result
+    assert!(game.to_pgn(&[], None).contains("1. Qg7# 1-0"));
+    play(&mut game, &[("g6", "g8")]);
+    assert!(game.to_pgn(&[], None).contains("1. Qg8+ *"));
+}
+
+#[test]
+fn navigation_resyncs_the_searcher() {
+    let mut game = Game::default();
+    game.searcher.search_config = rusty_engine::search::search_config::SearchConfig::with_d(2);
+    game.searcher.search_config.log_uci_diagnostics = false;
+    play(&mut game, &[("e2", "e4"), ("e7", "e5")]);
+    game.undo();
+    assert_eq!(game.searcher.positions[0].board.zhash, game.position.board.zhash);
+    let reply = game.play_cpu_move().unwrap();
+    assert!(game.position.played_moves_stack.ends_with(&[reply]));
+    assert_eq!(game.ply(), 2);
+}
+
+#[test]
+fn undo_keeps_the_clock_for_the_side_to_move() {
+    let mut game = Game::default();
+    game.set_time_control(Some(TimeControl::sudden_death(60_000)));
+    play(&mut game, &[("e2", "e4")]);
+    game.undo();
+    let clock = game.clock.as_ref().unwrap();
+    assert!(clock.is_running());
+    assert!(clock.remaining(WHITE, Instant::now()) <= 60_000);
+
+    game.set_time_control(Some(TimeControl::sudden_death(0)));
+    assert!(game.check_flag());
+    game.redo();
+    assert!(matches!(game.game_state, GameState::Timeout(_)), "a fallen flag stays down");
+}
+
+fn play(game: &mut Game, moves: &[(&str, &str)]) {
+    for (from, to) in moves {
+        game.try_make_move(square(from), square(to), None)
+            .unwrap_or_else(|_| panic!("expected legal move {from}{to}"));
+    }
+}
+
+fn square(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    (bytes[0] - b'a') as u32 + 8 * (bytes[1] - b'1') as u32
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{TestEngine, MULTITHREADED};
+use rusty_engine::{
+    game::{game::Game, game_state::GameState},
+    repr::position::Position,
+    search::{
+        eval::{endgame_scale, Evaluator, SCALE_NORMAL},
+        search_config::SearchMode,
+        searcher::Searcher,
+    },
+    repr::types::{BLACK, WHITE},
+};
+
+#[test]
+fn detects_insufficient_material_signatures() {
+    let engine = TestEngine::new();
+
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KB2 b - - 0 1",
+        "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+        // Bishops on c1 and f8 are both on dark squares.
+        "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/B7/1B2K3 w - - 0 1",
+    ] {
+        assert!(
+            engine.board(fen).is_insufficient_material(),
+            "{fen} should be insufficient material"
+        );
+    }
+}
+
+#[test]
+fn mating_material_is_not_insufficient() {
+    let engine = TestEngine::new();
+
+    for fen in [
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KR2 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KNN1 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+        "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1",
+        // Bishops on c1 (dark) and c8 (light) can still cooperate with a mate.
+        "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+    ] {
+        assert!(
+            !engine.board(fen).is_insufficient_material(),
+            "{fen} should have mating material"
+        );
+    }
+}
+
+#[test]
+fn game_reports_draw_by_insufficient_material_after_capture() {
+    let mut game = Game::default();
+    let position = Position::from("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1", &game.move_gen, &game.zobrist)
+        .expect("valid FEN position");
+    game.import_position(position);
+    assert!(matches!(game.game_state, GameState::InProgress));
+
+    // Kxd2 leaves kings only.
+    game.try_make_move(4, 11, None).expect("legal capture");
+
+    assert!(
+        matches!(game.game_state, GameState::DrawByInsufficientMaterial),
+        "expected draw by insufficient material, got {}",
+        game.game_state.to_string()
+    );
+    assert!(game.is_over());
+    assert!(game.game_state.is_draw());
+}
+
+#[test]
+fn search_scores_minor_piece_endgame_as_draw() {
+    let engine = TestEngine::new();
+    let pos = engine.position("4k3/8/8/8/8/8/8/3BK3 w - - 0 1");
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(3);
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+
+    assert!(searcher.collect_best_move().is_some());
+    let tte = searcher
+        .tt
+        .probe(pos.board.zhash)
+        .expect("root should be stored in the transposition table");
+    assert_eq!(tte.score, 0);
+}
+
+#[test]
+fn endgame_scale_pulls_drawish_configurations_toward_zero() {
+    let engine = TestEngine::new();
+    let scale = |fen: &str, strong_side: u32| endgame_scale(&engine.board(fen).pieces, strong_side);
+
+    // Rook versus minor without pawns.
+    assert!(scale("4k3/8/8/8/8/8/8/3nKR2 w - - 0 1", WHITE) < SCALE_NORMAL / 8);
+    // Opposite-coloured bishops with pawns.
+    assert!(scale("2b1k3/pp6/8/8/8/8/PPP5/2B1K3 w - - 0 1", WHITE) < SCALE_NORMAL / 2);
+    // Same-coloured bishops with extra pawn play normally.
+    assert_eq!(scale("4kb2/pp6/8/8/8/8/PPP5/2B1K3 w - - 0 1", WHITE), SCALE_NORMAL);
+    // Lone minor piece cannot win.
+    assert_eq!(scale("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", WHITE), 0);
+    // Rook up with pawns is a normal win.
+    assert_eq!(scale("4k3/pp6/8/8/8/8/PPP5/4KR2 w - - 0 1", WHITE), SCALE_NORMAL);
+    assert_eq!(scale("4k1r1/8/8/8/8/8/8/4K3 b - - 0 1", BLACK), SCALE_NORMAL);
+}
+
+#[test]
+fn eval_scales_late_game_score_in_rook_versus_minor() {
+    let engine = TestEngine::new();
+    let evaluator = Evaluator::default();
+    let board = engine.board("4k3/8/8/8/8/8/8/3nKR2 w - - 0 1");
+
+    let eval = evaluator.eval(board.pieces, WHITE, board.late_game_phase);
+    assert!(eval.abs() < 100, "rook versus knight should be near zero, got {eval}");
+    assert_eq!(evaluator.eval(board.pieces, BLACK, board.late_game_phase), -eval);
+}
@@ -21,7 +21,7 @@ fn move_gen_benchmark() {
     );
 }
 
-fn perft_benchmark<F: FnOnce() -> u32>(f: F) -> f32 {
+fn perft_benchmark<F: FnOnce() -> u64>(f: F) -> f32 {
     let start = Instant::now();
     let perft = f();
     let time_took: Duration = start.elapsed();
@@ -1,22 +1,26 @@
 mod common;
 
-use common::{TestEngine};
+use common::{TestEngine, PERFT_CASES};
 use rusty_engine::repr::move_gen::*;
 use rusty_engine::repr::*;
+use rusty_engine::utils::fen_tool;
 
-fn generate_legal(engine: &TestEngine, fen: &str, noisy_only: bool) -> Vec<u32> {
-    let board = engine.board(fen);
+fn generate_legal(engine: &TestEngine, fen: &str, gen_type: GenType) -> Vec<u32> {
+    generate_legal_on(engine, &engine.board(fen), gen_type)
+}
+
+fn generate_legal_on(engine: &TestEngine, board: &board::Board, gen_type: GenType) -> Vec<u32> {
     let mut legal_moves = [_move::NULL_MOVE; position::MOVE_ARR_SIZE];
     let mut pseudolegal_moves = [_move::NULL_MOVE; types::MAX_PSEUDO_MOVES_IN_POS];
     let generated = engine.move_gen.generate_legal(
-        &board,
+        board,
         board.turn,
         &mut legal_moves,
         &mut pseudolegal_moves,
         0,
         false,
         false,
-        noisy_only,
+        gen_type,
     );
 
     legal_moves[..generated].to_vec()
@@ -42,8 +46,8 @@ fn noisy_only_matches_captures_from_full_generation() {
     let engine = TestEngine::new();
     let fen = "4k3/8/3p1n2/4P3/2bQ4/8/8/4K3 w - - 0 1";
 
-    let all_moves = generate_legal(&engine, fen, false);
-    let captures = generate_legal(&engine, fen, true);
+    let all_moves = generate_legal(&engine, fen, GenType::All);
+    let captures = generate_legal(&engine, fen, GenType::Noisy);
     let captures_from_all: Vec<u32> = all_moves
         .iter()
         .copied()
@@ -65,7 +69,7 @@ fn noisy_only_includes_en_passant() {
     let captures = generate_legal(
         &engine,
         "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
-        true,
+        GenType::Noisy,
     );
 
     assert_eq!(sorted_uci(&captures), ["e5d6"]);
@@ -116,7 +120,7 @@ fn en_passant_king_safety_edge_cases() {
     ];
 
     for (name, fen, expected_move, expected_legal) in cases {
-        let moves = sorted_uci(&generate_legal(&engine, fen, false));
+        let moves = sorted_uci(&generate_legal(&engine, fen, GenType::All));
         assert_eq!(
             moves.iter().any(|mov| mov == expected_move),
             expected_legal,
@@ -131,7 +135,7 @@ fn noisy_only_includes_capture_and_quiet_promotions() {
     let noisy_moves = generate_legal(
         &engine,
         "4k2r/6P1/8/8/8/8/8/4K3 w - - 0 1",
-        true,
+        GenType::Noisy,
     );
 
     assert!(noisy_moves.iter().all(|mov| _move::is_promotion(*mov)));
@@ -143,6 +147,215 @@ fn noisy_only_includes_capture_and_quiet_promotions() {
     );
 }
 
+#[test]
+fn noisy_and_quiet_generation_partition_all_moves() {
+    let engine = TestEngine::new();
+    let fens = PERFT_CASES.iter().map(|case| case.fen).chain([
+        "4k2r/6P1/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+    ]);
+
+    for fen in fens {
+        let noisy = generate_legal(&engine, fen, GenType::Noisy);
+        let quiet = generate_legal(&engine, fen, GenType::Quiet);
+        assert!(noisy.iter().all(|mov| _move::is_noisy(*mov)), "{fen}");
+        assert!(!quiet.iter().any(|mov| _move::is_noisy(*mov)), "{fen}");
+
+        let combined: Vec<u32> = noisy.iter().chain(quiet.iter()).copied().collect();
+        assert_eq!(
+            sorted_encoded(&combined),
+            sorted_encoded(&generate_legal(&engine, fen, GenType::All)),
+            "{fen}"
+        );
+    }
+}
+
+fn is_valid(engine: &TestEngine, board: &board::Board, mov: u32) -> bool {
+    engine.move_gen.is_pseudolegal(board, mov) && engine.move_gen.pseudolegal_is_legal(mov, board)
+}
+
+#[test]
+fn pseudolegal_validation_agrees_with_generation() {
+    let engine = TestEngine::new();
+    let fens: Vec<&str> = PERFT_CASES
+        .iter()
+        .map(|case| case.fen)
+        .chain([
+            "4k2r/6P1/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b - - 0 1",
+            "r3k2r/p6p/8/8/8/8/P6P/R3K2R w KQkq - 0 1",
+            "7k/8/8/KPp4r/8/8/8/8 w - c6 0 1",
+        ])
+        .collect();
+    //moves legal somewhere, tried in every position as TT moves from a colliding key would be
+    let mut candidates: Vec<u32> = fens
+        .iter()
+        .flat_map(|fen| generate_legal(&engine, fen, GenType::All))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    for fen in &fens {
+        let board = engine.board(fen);
+        let legal = generate_legal(&engine, fen, GenType::All);
+        for mov in &candidates {
+            assert_eq!(
+                is_valid(&engine, &board, *mov),
+                legal.contains(mov),
+                "{} in {fen}",
+                _move::to_string(*mov, true)
+            );
+        }
+        let see_flagged = legal.iter().map(|mov| _move::with_negative_see(*mov));
+        assert!(see_flagged.clone().all(|mov| is_valid(&engine, &board, mov)), "{fen}");
+    }
+}
+
+#[test]
+fn pseudolegal_validation_rejects_malformed_moves() {
+    let engine = TestEngine::new();
+    let board = engine.board("r3k2r/6P1/8/3pP3/8/8/1P6/R3K2R w KQkq d6 0 1");
+    let valid = |mov: u32| is_valid(&engine, &board, mov);
+
+    assert!(!valid(_move::NULL_MOVE));
+    //pushes
+    assert!(valid(_move::create_double_push(9, 25, types::WHITE, types::W_PAWN)));
+    assert!(!valid(_move::create(9, 25, false, types::WHITE, types::W_PAWN)), "double push without its flag");
+    assert!(!valid(_move::create(9, 33, false, types::WHITE, types::W_PAWN)));
+    //en passant only onto the ep square
+    assert!(valid(_move::with_eaten_piece(_move::create_en_passant(36, 43, types::WHITE, types::W_PAWN), types::B_PAWN)));
+    assert!(!valid(_move::with_eaten_piece(_move::create_en_passant(36, 45, types::WHITE, types::W_PAWN), types::B_PAWN)));
+    //promotions need the flag on the last rank and a piece of the mover
+    assert!(!valid(_move::create(54, 62, false, types::WHITE, types::W_PAWN)));
+    assert!(valid(_move::create_promotion(54, 62, false, types::W_ROOK, types::WHITE, types::W_PAWN)));
+    assert!(!valid(_move::create_promotion(54, 62, false, types::B_QUEEN, types::WHITE, types::W_PAWN)));
+    assert!(!valid(_move::create_promotion(54, 62, false, types::W_KING, types::WHITE, types::W_PAWN)));
+    //wrong side, missing piece, blocked slide, wrong eaten piece
+    assert!(!valid(_move::create(60, 52, false, types::BLACK, types::B_KING)));
+    assert!(!valid(_move::create(2, 10, false, types::WHITE, types::W_BISHOP)));
+    assert!(valid(_move::create(0, 3, false, types::WHITE, types::W_ROOK)));
+    assert!(!valid(_move::create(0, 5, false, types::WHITE, types::W_ROOK)));
+    assert!(valid(_move::with_eaten_piece(_move::create(7, 63, true, types::WHITE, types::W_ROOK), types::B_ROOK)));
+    assert!(!valid(_move::with_eaten_piece(_move::create(7, 63, true, types::WHITE, types::W_ROOK), types::B_QUEEN)));
+    //castling needs the right and a free path
+    assert!(valid(_move::create_castling(types::WHITE, true)));
+    assert!(valid(_move::create_castling(types::WHITE, false)));
+    let no_rights = engine.board("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1");
+    assert!(!is_valid(&engine, &no_rights, _move::create_castling(types::WHITE, true)));
+    let blocked = engine.board("r3k2r/8/8/8/8/8/8/R3KB1R w KQkq - 0 1");
+    assert!(!is_valid(&engine, &blocked, _move::create_castling(types::WHITE, true)));
+}
+
+///Positions up to **depth** plies from **pos**, including it
+fn positions_within(engine: &TestEngine, pos: &mut position::Position, depth: usize, out: &mut Vec<board::Board>) {
+    out.push(pos.board.clone());
+    if depth == 0 {
+        return;
+    }
+    for mov in pos.legal_search_moves().to_vec() {
+        engine.make_search_move(pos, mov);
+        positions_within(engine, pos, depth - 1, out);
+        engine.unmake_move(pos, mov);
+    }
+}
+
+fn gives_check(engine: &TestEngine, board: &board::Board, mov: u32) -> bool {
+    let mut pos = engine.position(&fen_tool::board_to_fen(board));
+    engine.make_search_move(&mut pos, mov);
+    pos.board.nof_checkers > 0
+}
+
+#[test]
+fn evasion_and_quiet_check_generation_match_filtered_full_generation() {
+    let engine = TestEngine::new();
+    let extra = [
+        //discovered checks by a knight, a pawn push and the king, and a rook giving check by castling
+        "4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1",
+        "1k6/8/8/8/8/8/1P6/1R4K1 w - - 0 1",
+        "7k/8/8/8/8/8/1K6/B7 w - - 0 1",
+        "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        "2k5/8/8/8/8/8/8/R3K3 w Q - 0 1",
+        //single and double checks, evasions by en passant
+        "4k3/8/8/8/8/8/3n4/R3K2R w KQ - 0 1",
+        "4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1",
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+    ];
+    let mut boards: Vec<board::Board> = vec![];
+    for fen in PERFT_CASES.iter().map(|case| case.fen).chain(extra) {
+        positions_within(&engine, &mut engine.position(fen), 2, &mut boards);
+    }
+
+    let mut in_check: usize = 0;
+    let mut checks: usize = 0;
+    for board in &boards {
+        let fen = fen_tool::board_to_fen(board);
+        let all = generate_legal_on(&engine, board, GenType::All);
+        if board.nof_checkers > 0 {
+            in_check += 1;
+            assert_eq!(sorted_encoded(&generate_legal_on(&engine, board, GenType::Evasions)), sorted_encoded(&all), "{fen}");
+        }
+        let expected: Vec<u32> = all
+            .iter()
+            .copied()
+            .filter(|mov| !_move::is_noisy(*mov) && gives_check(&engine, board, *mov))
+            .collect();
+        let quiet_checks = generate_legal_on(&engine, board, GenType::QuietChecks);
+        assert_eq!(sorted_uci(&quiet_checks), sorted_uci(&expected), "{fen}");
+        checks += quiet_checks.len();
+    }
+    assert!(in_check > 100 && checks > 1000, "{in_check} positions in check, {checks} quiet checks");
+}
+
+#[test]
+fn attackers_to_matches_attack_maps_and_checkers() {
+    let engine = TestEngine::new();
+    let mut boards: Vec<board::Board> = vec![];
+    for case in PERFT_CASES {
+        positions_within(&engine, &mut engine.position(case.fen), 2, &mut boards);
+    }
+
+    for board in &boards {
+        let fen = fen_tool::board_to_fen(board);
+        let occupied = board.total_occupation();
+        //only the side that just moved has its attack map computed
+        let (attacks, attacker_occupation) = if board.turn == types::WHITE {
+            (board.black_attacks, board.black_occupation)
+        } else {
+            (board.white_attacks, board.white_occupation)
+        };
+        let attacked: u64 = (0..64)
+            .filter(|sqr| engine.move_gen.attackers_to(board, *sqr, occupied) & attacker_occupation != 0)
+            .fold(0, |attacked, sqr| attacked | (1 << sqr));
+        assert_eq!(attacked, attacks, "{fen}");
+
+        let king = board.get_king_sqr_idx(board.turn) as usize;
+        let checkers = engine.move_gen.attackers_to(board, king, occupied) & attacker_occupation;
+        assert_eq!(checkers.count_ones(), board.nof_checkers, "{fen}");
+    }
+}
+
+#[test]
+fn attackers_to_sees_through_removed_pieces_and_xrays() {
+    let engine = TestEngine::new();
+    let board = engine.board("4k3/q7/1b6/8/8/8/3Q4/3R3K w - - 0 1");
+    let occupied = board.total_occupation();
+    let squares = |bb: u64| -> Vec<String> {
+        (0..64).filter(|sqr| bb & (1 << sqr) != 0).map(board::square_to_string).collect()
+    };
+    let d4: usize = 27;
+
+    assert_eq!(squares(engine.move_gen.attackers_to(&board, d4, occupied)), ["d2", "b6"]);
+    assert_eq!(squares(engine.move_gen.xray_attackers_to(&board, d4, occupied)), ["d1", "a7"]);
+    //once the queen has taken on d4 the rook behind it attacks
+    let after_queen = occupied & !(1 << 11);
+    assert_eq!(squares(engine.move_gen.attackers_to(&board, d4, after_queen)), ["d1", "b6"]);
+    assert_eq!(squares(engine.move_gen.xray_attackers_to(&board, d4, after_queen)), ["a7"]);
+}
+
 #[test]
 fn naive_slide_gen_works() {
     let blockers: u64 = 18141975937152;
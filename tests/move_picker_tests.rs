@@ -0,0 +1,99 @@
+mod common;
+
+use common::{find_uci, TestEngine, PERFT_CASES};
+use rusty_engine::{
+    repr::{_move::{self, NULL_MOVE}, position::Position, types::{WHITE, W_ROOK}},
+    search::{move_picker::MovePicker, search_data::SearchData},
+};
+
+fn pick_all(
+    engine: &TestEngine,
+    pos: &mut Position,
+    priority: [u32; 2],
+    killers: [u32; 2],
+    noisy_only: bool,
+    quiet_checks: bool,
+) -> Vec<u32> {
+    let mut search_data = SearchData::new(pos);
+    let mut picker = MovePicker::new(pos, priority, killers, noisy_only, quiet_checks);
+    let mut picked = vec![];
+    while let Some(mov) = picker.next(pos, &engine.move_gen, &mut search_data) {
+        picked.push(mov);
+    }
+    picked
+}
+
+fn sorted(moves: &[u32]) -> Vec<u32> {
+    let mut moves = moves.to_vec();
+    moves.sort_unstable();
+    moves
+}
+
+#[test]
+fn lazy_picker_yields_every_search_move_once() {
+    let engine = TestEngine::new();
+    for case in PERFT_CASES {
+        let mut pos = engine.position(case.fen);
+        let root_moves = pos.legal_moves().to_vec();
+        for mov in root_moves {
+            engine.make_search_move(&mut pos, mov);
+            let expected = sorted(pos.legal_search_moves());
+            engine.unmake_move(&mut pos, mov);
+
+            pos.make_move_lazy(mov, true, &engine.move_gen, &engine.zobrist);
+            let picked = pick_all(&engine, &mut pos, [NULL_MOVE; 2], [NULL_MOVE; 2], false, false);
+            assert_eq!(sorted(&picked), expected, "{} after {}", case.name, _move::to_uci(mov, &pos.board));
+            engine.unmake_move(&mut pos, mov);
+        }
+    }
+}
+
+#[test]
+fn root_picker_orders_generated_moves_in_place() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position(PERFT_CASES[1].fen);
+    let expected = sorted(pos.legal_moves());
+    let picked = pick_all(&engine, &mut pos, [NULL_MOVE; 2], [NULL_MOVE; 2], false, false);
+    assert_eq!(sorted(&picked), expected);
+    assert_eq!(sorted(pos.legal_moves()), expected);
+}
+
+#[test]
+fn priority_and_killers_are_picked_once_in_stage_order() {
+    let engine = TestEngine::new();
+    //Qxh4 and Qxd6 win material, Qxe5+ loses the queen to dxe5
+    let mut pos = engine.position("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1");
+    let tt_move = find_uci(&pos, "e1d1");
+    let killer = find_uci(&pos, "d4a1");
+    let illegal_killer = _move::create(0, 9, false, WHITE, W_ROOK); //no rook on a1
+
+    let picked = pick_all(&engine, &mut pos, [tt_move, NULL_MOVE], [killer, illegal_killer], false, false);
+    let uci: Vec<String> = picked.iter().map(|mov| _move::to_uci(*mov, &pos.board)).collect();
+
+    assert_eq!(sorted(&picked), sorted(pos.legal_moves()));
+    assert_eq!(&uci[..4], ["e1d1", "d4h4", "d4d6", "d4a1"]);
+    assert_eq!(uci.last().map(String::as_str), Some("d4e5"));
+}
+
+#[test]
+fn noisy_only_picker_skips_quiets_and_killers() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1");
+    let killer = find_uci(&pos, "d4a1");
+    let picked = pick_all(&engine, &mut pos, [NULL_MOVE; 2], [killer, NULL_MOVE], true, false);
+    let uci: Vec<String> = picked.iter().map(|mov| _move::to_uci(*mov, &pos.board)).collect();
+    assert_eq!(uci, ["d4h4", "d4d6", "d4e5"]);
+}
+
+#[test]
+fn first_quiescence_ply_adds_quiet_checks_before_bad_captures() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1");
+    let picked = pick_all(&engine, &mut pos, [NULL_MOVE; 2], [NULL_MOVE; 2], true, true);
+    let uci: Vec<String> = picked.iter().map(|mov| _move::to_uci(*mov, &pos.board)).collect();
+
+    assert_eq!(&uci[..2], ["d4h4", "d4d6"]);
+    assert_eq!(uci.last().map(String::as_str), Some("d4e5"));
+    //the d6 and e5 pawns block the other lines to the king
+    assert_eq!(&uci[2..uci.len() - 1], ["d4a4"]);
+}
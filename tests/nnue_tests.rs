@@ -0,0 +1,153 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{TestEngine, MULTITHREADED, PERFT_CASES};
+use rusty_engine::{
+    repr::{_move, position::Position},
+    search::{
+        nnue::{simd, Network, NNUE_INPUTS, NNUE_MAGIC, NNUE_VERSION},
+        search_config::{EvalKind, SearchMode},
+        searcher::Searcher,
+    },
+};
+
+const BUNDLED_BYTES: &[u8] = include_bytes!("../assets/nnue/tiny.nnue");
+
+/// Deterministic pseudo random network so that every weight takes part
+fn random_network(hidden: usize) -> Network {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) % 101) as i16 - 50
+    };
+    let mut bytes: Vec<u8> = NNUE_MAGIC.to_vec();
+    bytes.extend(NNUE_VERSION.to_le_bytes());
+    bytes.extend((hidden as u32).to_le_bytes());
+    for _ in 0..(NNUE_INPUTS * hidden + 3 * hidden) {
+        bytes.extend(next().to_le_bytes());
+    }
+    bytes.extend(1234i32.to_le_bytes());
+    Network::from_bytes(&bytes).expect("valid network")
+}
+
+fn assert_incremental_matches_scratch(d_left: usize, pos: &mut Position, engine: &TestEngine) {
+    let nnue = pos.nnue.as_ref().expect("network attached");
+    assert_eq!(
+        nnue.eval(pos.board.turn),
+        nnue.network().evaluate(&pos.board.pieces, pos.board.turn)
+    );
+    if d_left == 0 {
+        return;
+    }
+    let (s, e) = pos.search_move_bounds();
+    for i in s..e {
+        let mov = pos.move_arr[i];
+        engine.make_search_move(pos, mov);
+        assert_incremental_matches_scratch(d_left - 1, pos, engine);
+        engine.unmake_move(pos, mov);
+    }
+}
+
+#[test]
+fn bundled_network_round_trips_through_the_file_format() {
+    let network = Network::bundled();
+
+    assert_eq!(network.hidden_size(), 16);
+    assert_eq!(network.to_bytes(), BUNDLED_BYTES);
+}
+
+#[test]
+fn rejects_malformed_network_files() {
+    let mut bad_magic = BUNDLED_BYTES.to_vec();
+    bad_magic[0] = b'X';
+    let mut bad_version = BUNDLED_BYTES.to_vec();
+    bad_version[4] = 99;
+    let truncated = &BUNDLED_BYTES[..BUNDLED_BYTES.len() - 1];
+
+    assert!(Network::from_bytes(&bad_magic).is_err());
+    assert!(Network::from_bytes(&bad_version).is_err());
+    assert!(Network::from_bytes(truncated).is_err());
+    assert!(Network::from_bytes(&[]).is_err());
+    assert!(Network::load("does/not/exist.nnue").is_err());
+}
+
+#[test]
+fn bundled_network_counts_material() {
+    let engine = TestEngine::new();
+    let network = Network::bundled();
+    let eval = |fen: &str| {
+        let board = engine.board(fen);
+        network.evaluate(&board.pieces, board.turn)
+    };
+
+    assert_eq!(eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+    let pawn_up = eval("rnbqkbnr/ppp1pppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert!((95..=105).contains(&pawn_up), "pawn up evaluated {pawn_up}");
+    assert_eq!(eval("rnbqkbnr/ppp1pppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"), -pawn_up);
+}
+
+#[test]
+fn incremental_accumulator_matches_full_refresh() {
+    let engine = TestEngine::new();
+    let network = Arc::new(random_network(40));
+
+    for case in PERFT_CASES.iter() {
+        let mut pos = engine.position(case.fen);
+        pos.set_network(Some(network.clone()));
+        assert_incremental_matches_scratch(3, &mut pos, &engine);
+    }
+}
+
+#[test]
+fn accumulator_follows_game_moves_and_root_unmake() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    pos.set_network(Some(Arc::new(random_network(24))));
+
+    // White castles short as a game move, shifting the root.
+    let castle = pos.legal_moves().iter().copied().find(|mov| _move::is_castle(*mov)).unwrap();
+    pos.make_move(castle, false, false, false, &engine.move_gen, &engine.zobrist);
+    let nnue = pos.nnue.as_ref().unwrap();
+    assert_eq!(nnue.eval(pos.board.turn), nnue.network().evaluate(&pos.board.pieces, pos.board.turn));
+
+    engine.unmake_move(&mut pos, castle);
+    let nnue = pos.nnue.as_ref().unwrap();
+    assert_eq!(nnue.eval(pos.board.turn), nnue.network().evaluate(&pos.board.pieces, pos.board.turn));
+}
+
+#[test]
+fn simd_kernels_match_scalar_reference() {
+    let acc: Vec<i16> = (0..37).map(|i| (i * 37 % 400) as i16 - 100).collect();
+    let weights: Vec<i16> = (0..37).map(|i| (i * 53 % 200) as i16 - 100).collect();
+
+    assert_eq!(simd::crelu_dot(&acc, &weights), simd::scalar::crelu_dot(&acc, &weights));
+
+    let mut fast = acc.clone();
+    let mut reference = acc.clone();
+    simd::add_assign(&mut fast, &weights);
+    simd::scalar::add_assign(&mut reference, &weights);
+    assert_eq!(fast, reference);
+    simd::sub_assign(&mut fast, &weights);
+    simd::scalar::sub_assign(&mut reference, &weights);
+    assert_eq!(fast, reference);
+    assert_eq!(fast, acc);
+}
+
+#[test]
+fn search_uses_network_when_selected() {
+    let engine = TestEngine::new();
+    let pos = engine.position("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(3);
+    searcher.search_config.eval_kind = EvalKind::Nnue;
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+
+    assert!(searcher.network.is_some(), "bundled network should be loaded on demand");
+    assert!(searcher.positions[0].nnue.is_some());
+    assert_eq!(_move::to_string(searcher.collect_best_move().unwrap(), true), "d2d5");
+
+    searcher.search_config.eval_kind = EvalKind::Handcrafted;
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+    assert!(searcher.positions[0].nnue.is_none());
+}
@@ -1,6 +1,9 @@
 mod common;
 
+use std::sync::atomic::AtomicBool;
+
 use common::{PerftCase, TestEngine, PERFT_CASES};
+use rusty_engine::repr::{_move, perft};
 
 #[test]
 fn default_pos_perft_correct() {
@@ -32,6 +35,60 @@ fn edge_case_perft_6() {
     assert_perft_case(PERFT_CASES[5]);
 }
 
+#[test]
+fn shallow_perft_counts_and_bulk_counting_edges() {
+    let engine = TestEngine::new();
+    for (depth, expected) in [(0, 1), (1, 20), (2, 400), (3, 8_902), (4, 197_281)] {
+        let mut pos = engine.position(PERFT_CASES[0].fen);
+        assert_eq!(engine.perft(depth, &mut pos), expected, "depth {depth}");
+    }
+
+    let mut mated = engine.position("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+    assert_eq!(engine.perft(1, &mut mated), 0);
+    assert_eq!(engine.perft(3, &mut mated), 0);
+}
+
+#[test]
+fn divide_sums_to_perft_and_restores_the_position() {
+    let engine = TestEngine::new();
+    let case = PERFT_CASES[1];
+    let mut pos = engine.position(case.fen);
+    let hash = pos.board.zhash;
+
+    let divide = perft::perft_divide(3, &mut pos, &engine.move_gen, &engine.zobrist);
+    assert_eq!(divide.len(), 48);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 97_862);
+    assert_eq!(pos.board.zhash, hash);
+    assert_eq!(pos.legal_moves().len(), 48);
+
+    let count_of = |uci: &str| {
+        divide
+            .iter()
+            .find(|(mov, _)| _move::to_string(*mov, true) == uci)
+            .map(|(_, nodes)| *nodes)
+    };
+    assert_eq!(count_of("e1g1"), Some(2_059));
+    assert_eq!(count_of("e5f7"), Some(2_080));
+
+    let mut pos = engine.position(case.fen);
+    let ones = perft::perft_divide(1, &mut pos, &engine.move_gen, &engine.zobrist);
+    assert!(ones.iter().all(|(_, nodes)| *nodes == 1));
+}
+
+#[test]
+fn stopped_divide_gives_up_and_restores_the_position() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position(PERFT_CASES[1].fen);
+    let hash = pos.board.zhash;
+    let stopped = AtomicBool::new(true);
+    assert!(perft::perft_divide_until(6, &mut pos, &engine.move_gen, &engine.zobrist, &stopped).is_none());
+    assert_eq!(pos.board.zhash, hash);
+    assert_eq!(pos.legal_moves().len(), 48);
+    //nothing is left to check at depth 1 and 2
+    let twos = perft::perft_divide_until(2, &mut pos, &engine.move_gen, &engine.zobrist, &stopped).unwrap();
+    assert_eq!(twos.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+}
+
 fn assert_perft_case(case: PerftCase) {
     let engine = TestEngine::new();
     let mut pos = engine.position(case.fen);
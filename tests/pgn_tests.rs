@@ -0,0 +1,131 @@
+mod common;
+
+use common::TestEngine;
+use rusty_engine::{
+    game::{
+        cpu_game::CpuGame,
+        game::Game,
+        game_state::GameState,
+        pgn::{self, PgnGame},
+    },
+    search::eval::MATE_EVAL,
+};
+
+const TWO_GAMES: &str = r#"
+[Event "Casual \"blitz\""]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+% escape lines are ignored
+{Scholar's mate} 1. e4 e5 2. Bc4 Nc6 (2... Nf6 3. d3 {solid}) 3. Qh5 $6 Nf6?? ; greedy
+4. Qxf7# 1-0
+
+[Event "Endgame"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"]
+[Result "*"]
+
+40... Kd7 41.e4 Ke6 42. Ke2 (42. Kd2 Ke5 (42... Kf6) 43. Ke3) *
+"#;
+
+#[test]
+fn parses_tags_moves_comments_nags_and_variations() {
+    let engine = TestEngine::new();
+    let games = pgn::parse_pgn(TWO_GAMES, &engine.move_gen, &engine.zobrist).unwrap();
+    assert_eq!(games.len(), 2);
+
+    let mate = &games[0];
+    assert_eq!(mate.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(mate.tag("White"), Some("Alice"));
+    assert_eq!(mate.result, "1-0");
+    assert_eq!(mate.uci_moves(), ["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"]);
+    assert_eq!(mate.moves[6].san, "Qxf7#");
+    assert_eq!(mate.moves[0].leading_comments, ["Scholar's mate"]);
+    assert_eq!(mate.moves[4].nags, [6]);
+    assert_eq!(mate.moves[5].nags, [4]);
+    assert_eq!(mate.moves[5].comments, ["greedy"]);
+
+    let variation = &mate.moves[3].variations[0];
+    assert_eq!(variation.iter().map(|m| m.san.as_str()).collect::<Vec<_>>(), ["Nf6", "d3"]);
+    assert_eq!(variation[1].comments, ["solid"]);
+
+    let endgame = &games[1];
+    assert_eq!(endgame.start_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40");
+    assert_eq!(endgame.result, "*");
+    assert_eq!(endgame.uci_moves(), ["e8d7", "e2e4", "d7e6", "e1e2"]);
+    let kd2 = &endgame.moves[3].variations[0];
+    assert_eq!(kd2.len(), 3);
+    assert_eq!(kd2[1].variations[0][0].san, "Kf6");
+}
+
+#[test]
+fn rejects_illegal_moves_and_broken_movetext() {
+    let engine = TestEngine::new();
+    let parse = |text: &str| pgn::parse_pgn(text, &engine.move_gen, &engine.zobrist);
+
+    assert!(parse("1. e4 e5 2. Ke3 *").unwrap_err().contains("Illegal SAN move"));
+    assert!(parse("1. e4 (1. d4 *").is_err());
+    assert!(parse("1. e4 ) *").is_err());
+    assert!(parse("(1. e4) *").is_err());
+    assert!(parse("1. e4 {unterminated").is_err());
+    assert!(parse("[Event \"x\" 1. e4 *").is_err());
+    assert!(parse("[Event \"?\"]\n[Result \"*\"]\n\n1. e4 e5 2. Nxe5 *")
+        .unwrap_err()
+        .starts_with("Game 1"));
+}
+
+#[test]
+fn export_round_trips_through_the_parser() {
+    let engine = TestEngine::new();
+    let games = pgn::parse_pgn(TWO_GAMES, &engine.move_gen, &engine.zobrist).unwrap();
+
+    for game in games.iter() {
+        let exported = game.to_string();
+        let reparsed = pgn::parse_pgn(&exported, &engine.move_gen, &engine.zobrist).unwrap();
+        assert_eq!(reparsed, vec![game.clone()], "{exported}");
+    }
+    assert!(games[1].to_string().contains("40... Kd7 41. e4 Ke6 42. Ke2 (42. Kd2 Ke5 (42... Kf6) 43. Ke3) *"));
+}
+
+#[test]
+fn exports_game_with_seven_tag_roster_result_and_evals() {
+    let mut game = Game::default();
+    for (from, to) in [(13, 21), (52, 36), (14, 30), (59, 31)] {
+        game.try_make_move(from, to, None).unwrap();
+    }
+    assert!(matches!(game.game_state, GameState::Checkmate(_)));
+
+    let evals: [i16; 4] = [-40, 150, -300, -(MATE_EVAL - 1)];
+    let pgn = game.to_pgn(&[("White", "rusty"), ("Black", "human"), ("TimeControl", "60+1")], Some(&evals));
+    let header = "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                  [White \"rusty\"]\n[Black \"human\"]\n[Result \"0-1\"]\n[TimeControl \"60+1\"]\n\n";
+    assert!(pgn.starts_with(header), "{pgn}");
+    assert!(pgn.ends_with("1. f3 {-0.40} 1... e5 {+1.50} 2. g4 {-3.00} 2... Qh4# {-M1} 0-1\n"), "{pgn}");
+    assert!(!pgn.contains("[FEN"));
+}
+
+#[test]
+fn exports_cpu_game_from_its_imported_position() {
+    let engine = TestEngine::new();
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let mut cpu_game = CpuGame::default();
+    cpu_game
+        .import_position(fen, vec!["e2e4".to_string(), "e8d7".to_string()])
+        .unwrap();
+
+    let pgn = cpu_game.to_pgn(&[], None, &GameState::InProgress);
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n"));
+    assert!(pgn.ends_with("1. e4 Kd7 *\n"));
+
+    let parsed = pgn::parse_pgn(&pgn, &engine.move_gen, &engine.zobrist).unwrap();
+    let mut reimported = CpuGame::default();
+    reimported.import_position(parsed[0].start_fen(), parsed[0].uci_moves()).unwrap();
+    assert_eq!(reimported.position.board.zhash, cpu_game.position.board.zhash);
+
+    let drawn = PgnGame::from_moves(&[], fen, &[], None, GameState::Stalemate.pgn_result(), &engine.move_gen, &engine.zobrist);
+    assert_eq!(drawn.unwrap().result, "1/2-1/2");
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{find_uci, TestEngine};
+use rand::{rngs::StdRng, SeedableRng};
+use rusty_engine::{
+    game::cpu_game::CpuGame,
+    repr::_move,
+    utils::{
+        fen_tool::DEFAULT_FEN,
+        polyglot::{self, BookEntry, BookSelection, PolyglotBook},
+    },
+};
+
+//positions and keys from the Polyglot book format description
+const REFERENCE_KEYS: [(&str, u64); 9] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c),
+    ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c9b50fd114196),
+    ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0),
+    ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1),
+    ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9),
+    ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+    ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560),
+];
+
+fn book_bytes(mut entries: Vec<BookEntry>) -> Vec<u8> {
+    entries.sort_by_key(|entry| entry.key);
+    entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+}
+
+///Start position book: e2e4 weight 3, d2d4 weight 1, g1f3 weight 0, with entries of other positions around it
+fn start_position_book(engine: &TestEngine) -> PolyglotBook {
+    let pos = engine.position(DEFAULT_FEN);
+    let key = polyglot::polyglot_key(&pos.board);
+    let entry = |key: u64, uci: &str, weight: u16| BookEntry {
+        key,
+        raw_move: polyglot::encode_move(find_uci(&pos, uci), &pos.board),
+        weight,
+        learn: 0,
+    };
+    let bytes = book_bytes(vec![
+        entry(key - 1, "a2a3", 100),
+        entry(key, "e2e4", 3),
+        entry(key, "d2d4", 1),
+        entry(key, "g1f3", 0),
+        entry(key + 1, "h2h3", 100),
+    ]);
+    PolyglotBook::from_bytes(&bytes).expect("valid book")
+}
+
+#[test]
+fn keys_match_the_polyglot_reference_positions() {
+    let engine = TestEngine::new();
+    for (fen, key) in REFERENCE_KEYS {
+        assert_eq!(polyglot::polyglot_key(&engine.board(fen)), key, "{fen}");
+    }
+}
+
+#[test]
+fn played_moves_reach_the_reference_keys() {
+    let engine = TestEngine::new();
+    let mut pos = engine.position(DEFAULT_FEN);
+    for (uci, (_, key)) in ["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"].iter().zip(&REFERENCE_KEYS[1..7]) {
+        let mov = find_uci(&pos, uci);
+        pos.make_move(mov, false, false, false, &engine.move_gen, &engine.zobrist);
+        assert_eq!(polyglot::polyglot_key(&pos.board), *key, "after {uci}");
+    }
+}
+
+#[test]
+fn castling_is_encoded_as_king_takes_rook() {
+    let engine = TestEngine::new();
+    let pos = engine.position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let short = find_uci(&pos, "e1g1");
+    let long = find_uci(&pos, "e1c1");
+    let e1h1: u16 = (4 << 6) | 7;
+    let e1a1: u16 = 4 << 6;
+
+    assert_eq!(polyglot::encode_move(short, &pos.board), e1h1);
+    assert_eq!(polyglot::encode_move(long, &pos.board), e1a1);
+    assert_eq!(polyglot::decode_move(e1h1, &pos), Some(short));
+    assert_eq!(polyglot::decode_move(e1a1, &pos), Some(long));
+    //king step to g1 is not a castling encoding
+    assert_eq!(polyglot::decode_move((4 << 6) | 6, &pos), None);
+}
+
+#[test]
+fn promotions_round_trip() {
+    let engine = TestEngine::new();
+    let pos = engine.position("4k3/8/8/8/8/8/1p6/4K3 b - - 0 1");
+    for (uci, promotion) in [("b2b1n", 1u16), ("b2b1b", 2), ("b2b1r", 3), ("b2b1q", 4)] {
+        let mov = find_uci(&pos, uci);
+        let raw: u16 = (promotion << 12) | (9 << 6) | 1;
+        assert_eq!(polyglot::encode_move(mov, &pos.board), raw, "{uci}");
+        assert_eq!(polyglot::decode_move(raw, &pos), Some(mov), "{uci}");
+    }
+    assert_eq!(polyglot::decode_move((9 << 6) | 1, &pos), None);
+}
+
+#[test]
+fn binary_search_finds_only_the_positions_entries() {
+    let engine = TestEngine::new();
+    let book = start_position_book(&engine);
+    let pos = engine.position(DEFAULT_FEN);
+    let key = polyglot::polyglot_key(&pos.board);
+
+    assert_eq!(book.len(), 5);
+    assert_eq!(book.entries_for(key).len(), 3);
+    assert!(book.entries_for(key).iter().all(|entry| entry.key == key));
+    assert!(book.entries_for(key ^ 0xFF00).is_empty());
+    let moves: Vec<String> = book.moves(&pos).iter().map(|(mov, _)| _move::to_uci(*mov, &pos.board)).collect();
+    assert_eq!(moves, ["e2e4", "d2d4", "g1f3"]);
+}
+
+#[test]
+fn selection_by_weight() {
+    let engine = TestEngine::new();
+    let mut book = start_position_book(&engine);
+    let pos = engine.position(DEFAULT_FEN);
+    let e2e4 = find_uci(&pos, "e2e4");
+    let d2d4 = find_uci(&pos, "d2d4");
+    let mut rng = StdRng::seed_from_u64(7);
+
+    book.selection = BookSelection::Best;
+    assert_eq!(book.pick(&pos, &mut rng), Some(e2e4));
+
+    book.selection = BookSelection::WeightedRandom;
+    let picks: Vec<u32> = (0..4000).map(|_| book.pick(&pos, &mut rng).unwrap()).collect();
+    let nof_e2e4 = picks.iter().filter(|mov| **mov == e2e4).count();
+    let nof_d2d4 = picks.iter().filter(|mov| **mov == d2d4).count();
+    assert_eq!(nof_e2e4 + nof_d2d4, picks.len(), "zero weight moves are never picked");
+    assert!((2800..3200).contains(&nof_e2e4), "{nof_e2e4} of 4000 picks were e2e4");
+
+    let out_of_book = engine.position("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(book.pick(&out_of_book, &mut rng), None);
+}
+
+#[test]
+fn rejects_truncated_and_unsorted_books() {
+    let entry = |key: u64| BookEntry { key, raw_move: 0, weight: 1, learn: 0 };
+    let sorted: Vec<u8> = [entry(1), entry(2)].iter().flat_map(|entry| entry.to_bytes()).collect();
+    let unsorted: Vec<u8> = [entry(2), entry(1)].iter().flat_map(|entry| entry.to_bytes()).collect();
+
+    assert!(PolyglotBook::from_bytes(&sorted).is_ok());
+    assert!(PolyglotBook::from_bytes(&sorted[..sorted.len() - 1]).is_err());
+    assert!(PolyglotBook::from_bytes(&unsorted).is_err());
+    assert!(PolyglotBook::load("no/such/book.bin").is_err());
+}
+
+#[test]
+fn cpu_game_plays_from_book_only_with_own_book() {
+    let engine = TestEngine::new();
+    let mut game = CpuGame { book: Some(start_position_book(&engine)), ..Default::default() };
+    assert_eq!(game.book_move(), None);
+
+    game.own_book = true;
+    let book_move = game.book_move().expect("start position is in the book");
+    assert!(["e2e4", "d2d4"].contains(&_move::to_uci(book_move, &game.position.board).as_str()));
+
+    game.import_position(DEFAULT_FEN, vec!["e2e4".to_string()]).unwrap();
+    assert_eq!(game.book_move(), None);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{find_uci, TestEngine, PERFT_CASES};
+use rusty_engine::repr::_move;
+
+fn san(engine: &TestEngine, fen: &str, uci: &str) -> String {
+    let pos = engine.position(fen);
+    pos.move_to_san(find_uci(&pos, uci), &engine.move_gen, &engine.zobrist)
+}
+
+fn parse(engine: &TestEngine, fen: &str, san: &str) -> Result<String, &'static str> {
+    engine
+        .position(fen)
+        .parse_san(san)
+        .map(|mov| _move::to_string(mov, true))
+}
+
+#[test]
+fn formats_piece_pawn_and_castling_moves() {
+    let engine = TestEngine::new();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    assert_eq!(san(&engine, start, "e2e4"), "e4");
+    assert_eq!(san(&engine, start, "g1f3"), "Nf3");
+    assert_eq!(san(&engine, kiwipete, "e1g1"), "O-O");
+    assert_eq!(san(&engine, kiwipete, "e1c1"), "O-O-O");
+    assert_eq!(san(&engine, kiwipete, "d5e6"), "dxe6");
+    assert_eq!(san(&engine, kiwipete, "f3f6"), "Qxf6");
+    assert_eq!(san(&engine, "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+}
+
+#[test]
+fn formats_promotions_checks_and_mates() {
+    let engine = TestEngine::new();
+
+    assert_eq!(san(&engine, "8/4P3/8/8/8/8/8/k3K3 w - - 0 1", "e7e8q"), "e8=Q");
+    assert_eq!(san(&engine, "5r2/4P3/8/8/8/8/8/k3K3 w - - 0 1", "e7f8n"), "exf8=N");
+    assert_eq!(san(&engine, "4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+    assert_eq!(
+        san(&engine, "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", "h5f7"),
+        "Qxf7#"
+    );
+}
+
+#[test]
+fn disambiguates_by_file_rank_or_both() {
+    let engine = TestEngine::new();
+
+    assert_eq!(san(&engine, "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1d2"), "Nbd2");
+    assert_eq!(san(&engine, "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "f3d2"), "Nfd2");
+    assert_eq!(san(&engine, "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+    assert_eq!(san(&engine, "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a5a3"), "R5a3");
+    assert_eq!(san(&engine, "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1", "a1b2"), "Qa1b2");
+    // A pinned knight is no rival.
+    assert_eq!(san(&engine, "4k3/4r3/8/8/8/2N5/4N3/4K3 w - - 0 1", "c3d5"), "Nd5");
+}
+
+#[test]
+fn parses_standard_and_lenient_input() {
+    let engine = TestEngine::new();
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    assert_eq!(parse(&engine, kiwipete, "O-O"), Ok("e1g1".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "0-0-0"), Ok("e1c1".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "Nxd7"), Ok("e5d7".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "Nxd7+!?"), Ok("e5d7".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "Ne5-d7"), Ok("e5d7".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "gxh3"), Ok("g2h3".to_string()));
+    assert_eq!(parse(&engine, kiwipete, "a3"), Ok("a2a3".to_string()));
+
+    let promotion = "5r2/4P3/8/8/8/8/8/k3K3 w - - 0 1";
+    assert_eq!(parse(&engine, promotion, "e8=Q"), Ok("e7e8q".to_string()));
+    assert_eq!(parse(&engine, promotion, "e8Q"), Ok("e7e8q".to_string()));
+    assert_eq!(parse(&engine, promotion, "exf8n"), Ok("e7f8n".to_string()));
+
+    let two_knights = "1r2k3/3P4/8/8/8/5N2/8/1N2K3 w - - 0 1";
+    assert_eq!(parse(&engine, two_knights, "Nbd2"), Ok("b1d2".to_string()));
+    assert_eq!(parse(&engine, two_knights, "N1d2"), Ok("b1d2".to_string()));
+}
+
+#[test]
+fn rejects_illegal_ambiguous_and_malformed_san() {
+    let engine = TestEngine::new();
+    let two_knights = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+
+    assert_eq!(parse(&engine, two_knights, "Nd2"), Err("Ambiguous SAN move"));
+    assert_eq!(parse(&engine, two_knights, "Nd4"), Ok("f3d4".to_string()));
+    assert_eq!(parse(&engine, two_knights, "Qd2"), Err("Illegal SAN move"));
+    assert_eq!(parse(&engine, two_knights, "O-O"), Err("Illegal SAN move"));
+    assert_eq!(parse(&engine, "8/4P3/8/8/8/8/8/k3K3 w - - 0 1", "e8"), Err("Missing SAN promotion piece"));
+    for malformed in ["", "N", "Nz9", "e9", "Nbb1d2"] {
+        assert!(parse(&engine, two_knights, malformed).is_err(), "{malformed} should be rejected");
+    }
+}
+
+#[test]
+fn san_round_trips_for_every_legal_move() {
+    let engine = TestEngine::new();
+
+    for case in PERFT_CASES.iter() {
+        let pos = engine.position(case.fen);
+        for mov in pos.legal_moves().iter().copied() {
+            let san = pos.move_to_san(mov, &engine.move_gen, &engine.zobrist);
+            assert_eq!(pos.parse_san(&san), Ok(mov), "{} in {}", san, case.name);
+        }
+    }
+}
@@ -7,14 +7,14 @@ use rusty_engine::{
     repr::position::Position,
     search::{
         search_config::SearchMode,
-        search_data::{get_triang_pv_ply_idx_table, TRIANG_PV_TABLE_SIZE},
+        search_data::{get_triang_pv_ply_idx_table, IterationInfo, TRIANG_PV_TABLE_SIZE},
         searcher::{Searcher, MAX_SEARCH_DEPTH},
     },
     utils::fen_tool::DEFAULT_FEN,
 };
 use std::sync::{
     atomic::{AtomicBool, Ordering::Relaxed},
-    Arc,
+    Arc, Mutex,
 };
 
 const MATE_IN_ONE_FEN: &str = "7k/8/5KQ1/8/8/8/8/8 w - - 0 1";
@@ -412,3 +412,26 @@ fn static_depth_zero_preserves_existing_pv() {
 
     assert_eq!(root_pv(&searcher), completed);
 }
+
+#[test]
+fn every_completed_depth_is_reported_while_searching() {
+    let engine = TestEngine::new();
+    let pos = engine.position(DEFAULT_FEN);
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(4);
+    searcher.search_config.log_uci_diagnostics = false;
+    let reported: Arc<Mutex<Vec<IterationInfo>>> = Arc::new(Mutex::new(vec![]));
+    let sink = reported.clone();
+    searcher.on_iteration = Some(Box::new(move |info: &IterationInfo| sink.lock().unwrap().push(info.clone())));
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+
+    let reported = reported.lock().unwrap();
+    assert_eq!(*reported, searcher.search_data[0].iterations);
+    assert_eq!(reported.iter().map(|info| info.depth).collect::<Vec<usize>>(), [1, 2, 3, 4]);
+    for info in reported.iter() {
+        assert_eq!(info.pv[0], info.best_move);
+        assert!(info.pv.len() <= info.depth && info.sel_depth >= info.pv.len());
+        assert_legal_pv(&engine, &pos, &info.pv);
+    }
+    assert_eq!(reported.last().unwrap().pv, root_pv(&searcher));
+}
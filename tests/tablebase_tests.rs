@@ -0,0 +1,217 @@
+mod common;
+
+use std::{
+    sync::{Arc, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{TestEngine, MULTITHREADED};
+use rusty_engine::{
+    repr::{
+        _move,
+        board::Board,
+        types::{BLACK, B_KING_U, B_PAWN_U, WHITE, W_KING_U, W_PAWN_U},
+    },
+    search::{
+        endgame,
+        search_config::SearchMode,
+        searcher::Searcher,
+        tablebase::{self, LocalTablebase, Tablebase, Wdl, TB_WIN},
+    },
+};
+
+///KPvK and the tables it converts to, KQvK, KRvK, KBvK and KNvK
+fn tablebase() -> &'static LocalTablebase {
+    static TABLEBASE: OnceLock<LocalTablebase> = OnceLock::new();
+    TABLEBASE.get_or_init(|| {
+        let engine = TestEngine::new();
+        let mut tablebase = LocalTablebase::default();
+        tablebase.generate("KPvK", &engine.move_gen, &engine.zobrist).expect("valid material");
+        tablebase
+    })
+}
+
+fn probe(engine: &TestEngine, fen: &str) -> Option<(Wdl, u32)> {
+    tablebase().probe_dtz(&engine.board(fen), &engine.move_gen)
+}
+
+#[test]
+fn generates_the_tables_a_material_converts_to() {
+    assert_eq!(tablebase().names(), ["KBvK", "KNvK", "KPvK", "KQvK", "KRvK"]);
+    assert_eq!(tablebase().max_pieces(), 3);
+    let mut tablebase = LocalTablebase::default();
+    let engine = TestEngine::new();
+    assert!(tablebase.generate("KQRvKR", &engine.move_gen, &engine.zobrist).is_err());
+    assert!(tablebase.generate("KQ", &engine.move_gen, &engine.zobrist).is_err());
+}
+
+#[test]
+fn distances_count_plies_to_mate_or_zeroing() {
+    let engine = TestEngine::new();
+    assert_eq!(probe(&engine, "k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), Some((Wdl::Loss, 0)));
+    assert_eq!(probe(&engine, "k7/8/1K6/8/8/8/8/2Q5 w - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(probe(&engine, "k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some((Wdl::Win, 1)));
+    //promoting is the zeroing move
+    assert_eq!(probe(&engine, "8/4P3/8/8/8/8/k7/4K3 w - - 0 1"), Some((Wdl::Win, 1)));
+    //stalemate and the hanging queen
+    assert_eq!(probe(&engine, "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some((Wdl::Draw, 0)));
+    assert_eq!(probe(&engine, "8/8/8/8/8/8/1Q6/k3K3 b - - 0 1").map(|(wdl, _)| wdl), Some(Wdl::Draw));
+    assert_eq!(probe(&engine, "8/8/8/3k4/8/8/8/3BK3 w - - 0 1"), Some((Wdl::Draw, 0)));
+}
+
+#[test]
+fn probes_either_color_as_the_stronger_side() {
+    let engine = TestEngine::new();
+    assert_eq!(probe(&engine, "8/8/8/8/8/1k6/2q5/K7 b - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(
+        probe(&engine, "8/8/4k3/8/8/8/4P3/4K3 w - - 0 1"),
+        probe(&engine, "4k3/4p3/8/8/8/4K3/8/8 b - - 0 1")
+    );
+    assert_eq!(
+        probe(&engine, "8/8/4k3/8/8/8/4P3/4K3 w - - 0 1"),
+        probe(&engine, "8/8/3k4/8/8/8/3P4/3K4 w - - 0 1")
+    );
+    //no table for four pieces or positions with castling rights
+    assert_eq!(probe(&engine, "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), None);
+    assert_eq!(probe(&engine, "4k3/8/8/8/8/8/8/4K2R w K - 0 1"), None);
+}
+
+#[test]
+fn pawn_results_agree_with_the_kpk_bitbase() {
+    let engine = TestEngine::new();
+    let bitbase = endgame::kpk_bitbase();
+    for (strong_side, pawn_u) in [(WHITE, W_PAWN_U), (BLACK, B_PAWN_U)] {
+        for pawn in 8..56u32 {
+            for strong_king in (0..64u32).step_by(3) {
+                for weak_king in 0..64u32 {
+                    for turn in [WHITE, BLACK] {
+                        if pawn == strong_king || pawn == weak_king || strong_king == weak_king {
+                            continue;
+                        }
+                        let mut pieces = [0u64; 12];
+                        let (strong_king_u, weak_king_u) = if strong_side == WHITE { (W_KING_U, B_KING_U) } else { (B_KING_U, W_KING_U) };
+                        pieces[pawn_u] = 1 << pawn;
+                        pieces[strong_king_u] = 1 << strong_king;
+                        pieces[weak_king_u] = 1 << weak_king;
+                        let board = board_with(&engine, pieces, turn);
+                        if in_check(&engine, &board, turn ^ 1) {
+                            continue; //illegal
+                        }
+                        let (wdl, _) = tablebase().probe_dtz(&board, &engine.move_gen).expect("KPvK probe");
+                        let strong_wins = if turn == strong_side { wdl == Wdl::Win } else { wdl == Wdl::Loss };
+                        assert_eq!(
+                            strong_wins,
+                            bitbase.probe(strong_side, strong_king, pawn, weak_king, turn),
+                            "pawn {pawn} kings {strong_king} {weak_king} turn {turn}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn tables_survive_saving_and_loading() {
+    let engine = TestEngine::new();
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let dir = std::env::temp_dir().join(format!("rusty_tablebase_{nanos}"));
+    let dir = dir.to_str().unwrap();
+    tablebase().save(dir).expect("writable temp dir");
+
+    let loaded = LocalTablebase::load(dir).expect("saved tables");
+    assert_eq!(loaded.names(), tablebase().names());
+    for fen in ["8/8/4k3/8/8/8/4P3/4K3 w - - 0 1", "k7/8/1K6/8/8/8/8/2Q5 w - - 0 1", "8/8/8/8/8/1k6/2q5/K7 b - - 0 1"] {
+        let board = engine.board(fen);
+        assert_eq!(loaded.probe_dtz(&board, &engine.move_gen), tablebase().probe_dtz(&board, &engine.move_gen));
+    }
+
+    std::fs::write(format!("{dir}/KQvK.rtb"), b"RTB1\x04KQvK").unwrap();
+    assert!(LocalTablebase::load(dir).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn root_filtering_keeps_wins_inside_the_fifty_move_rule() {
+    let engine = TestEngine::new();
+    //with a fresh counter every queen move that doesn't hang or stalemate wins
+    let mut pos = engine.position("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1");
+    let nof_moves = pos.legal_moves().len();
+    assert_eq!(tablebase::filter_root_moves(tablebase(), &mut pos, &engine.move_gen, &engine.zobrist), Some(Wdl::Win));
+    assert!(pos.legal_moves().len() > 1 && pos.legal_moves().len() < nof_moves);
+
+    //one ply before the fifty move draw only the mate is left
+    let mut pos = engine.position("k7/8/1K6/8/8/8/8/2Q5 w - - 99 100");
+    assert_eq!(tablebase::filter_root_moves(tablebase(), &mut pos, &engine.move_gen, &engine.zobrist), Some(Wdl::Win));
+    let uci: Vec<String> = pos.legal_moves().iter().map(|mov| _move::to_uci(*mov, &pos.board)).collect();
+    assert_eq!(uci, ["c1c8"]);
+
+    //too late for the rook to mate before the counter runs out
+    let mut pos = engine.position("8/8/8/3k4/8/8/8/R3K3 w - - 90 100");
+    assert_eq!(tablebase::filter_root_moves(tablebase(), &mut pos, &engine.move_gen, &engine.zobrist), Some(Wdl::CursedWin));
+}
+
+#[test]
+fn search_plays_only_moves_that_keep_the_win() {
+    let engine = TestEngine::new();
+    //only Ke3 wins, the other moves let the black king in front of the pawn
+    let fen = "8/8/8/2k5/8/8/3PK3/8 w - - 0 1";
+    let pos = engine.position(fen);
+    let winning: Vec<u32> = pos
+        .legal_moves()
+        .iter()
+        .copied()
+        .filter(|mov| {
+            let mut child = pos.clone();
+            child.make_move(*mov, false, false, false, &engine.move_gen, &engine.zobrist);
+            tablebase().probe_wdl(&child.board, &engine.move_gen) == Some(Wdl::Loss)
+        })
+        .collect();
+    assert_eq!(winning.len(), 1);
+
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(4);
+    searcher.search_config.log_uci_diagnostics = false;
+    searcher.tablebase = Some(Arc::new(LocalTablebase::default())); //nothing to probe
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+    assert!(searcher.search_data[0].iterations.iter().all(|iteration| iteration.eval.abs() < TB_WIN - 100));
+
+    let tables: &'static LocalTablebase = tablebase();
+    let mut searcher = Searcher::from(&pos, MULTITHREADED);
+    searcher.search_config.search_mode = SearchMode::StaticDepth(4);
+    searcher.search_config.log_uci_diagnostics = false;
+    searcher.tablebase = Some(Arc::new(StaticTablebase(tables)));
+    searcher.start_search(&engine.move_gen, &engine.zobrist, None);
+    let best_move = searcher.collect_best_move().expect("a move");
+    assert!(winning.contains(&best_move), "{}", _move::to_uci(best_move, &pos.board));
+    assert!(searcher.search_data[0].iterations.last().unwrap().eval > 0);
+}
+
+///Shares the generated tables between tests, the searcher owns its tablebase
+struct StaticTablebase(&'static LocalTablebase);
+
+impl Tablebase for StaticTablebase {
+    fn max_pieces(&self) -> u32 {
+        self.0.max_pieces()
+    }
+
+    fn probe_wdl(&self, board: &Board, move_gen: &rusty_engine::repr::move_gen::MoveGen) -> Option<Wdl> {
+        self.0.probe_wdl(board, move_gen)
+    }
+
+    fn probe_dtz(&self, board: &Board, move_gen: &rusty_engine::repr::move_gen::MoveGen) -> Option<(Wdl, u32)> {
+        self.0.probe_dtz(board, move_gen)
+    }
+}
+
+fn board_with(engine: &TestEngine, pieces: [u64; 12], turn: u32) -> Board {
+    let white_occupation = pieces[..6].iter().fold(0, |occ, bb| occ | bb);
+    let black_occupation = pieces[6..].iter().fold(0, |occ, bb| occ | bb);
+    Board::board_with(pieces, white_occupation, black_occupation, turn, 1, 1, 1, 1, None, 0, &engine.move_gen, &engine.zobrist, 0)
+}
+
+fn in_check(engine: &TestEngine, board: &Board, side: u32) -> bool {
+    let (king, own) = if side == WHITE { (W_KING_U, board.white_occupation) } else { (B_KING_U, board.black_occupation) };
+    let attackers = engine.move_gen.attackers_to(board, board.pieces[king].trailing_zeros() as usize, board.total_occupation());
+    attackers & !own != 0
+}
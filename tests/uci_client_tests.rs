@@ -0,0 +1,134 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::TestEngine;
+use rusty_engine::{
+    game::{
+        engine_match::{self, SearcherPlayer, UciPlayer},
+        game_state::GameState,
+    },
+    repr::_move,
+    search::search_config::SearchMode,
+    uci::{
+        client::UciClient,
+        uci_command::{EngineCommand, GoCommand, PositionCommand, Score},
+    },
+};
+
+const ENGINE: &str = env!("CARGO_BIN_EXE_rusty_engine");
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[test]
+fn handshake_reads_id_and_options() {
+    let mut client = UciClient::launch(ENGINE, &[]).unwrap();
+    assert_eq!(client.name, "Rusty");
+    let depth = client.options.iter().find(|option| option.name == "SyzygyProbeDepth").unwrap();
+    assert_eq!(depth.option_type, "spin");
+    assert_eq!((depth.min, depth.max), (Some(1), Some(50)));
+    let book = client.options.iter().find(|option| option.name == "BookFile").unwrap();
+    assert_eq!(book.default.as_deref(), Some(""));
+
+    client.set_option("SyzygyProbeDepth", "3").unwrap();
+    client.new_game().unwrap();
+    client.is_ready().unwrap();
+}
+
+#[test]
+fn searches_report_info_and_a_legal_best_move() {
+    let engine = TestEngine::new();
+    let mut client = UciClient::launch(ENGINE, &[]).unwrap();
+    let position = PositionCommand::new(None, vec!["e2e4".into(), "e7e5".into()]);
+    let reply = client.search(&position, &GoCommand::new_depth_tc(false, 4), TIMEOUT).unwrap();
+
+    let depths: Vec<usize> = reply.info.iter().filter_map(|info| info.depth).collect();
+    assert_eq!(depths, [1, 2, 3, 4]);
+    assert!(reply.info.iter().all(|info| !info.pv.is_empty() && info.nodes.is_some()));
+    assert!(matches!(reply.score(), Some(Score::Cp(_))));
+    let pos = engine.position("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+    assert!(pos.legal_moves().iter().any(|mov| _move::to_string(*mov, true) == reply.best_move));
+    assert_eq!(reply.info.last().unwrap().pv[0], reply.best_move);
+
+    //timed searches answer too
+    let mate = PositionCommand::new(Some("7k/8/5KQ1/8/8/8/8/8 w - - 0 1".into()), vec![]);
+    let reply = client.search(&mate, &GoCommand::new_movetime_tc(false, 200), TIMEOUT).unwrap();
+    assert_eq!(reply.best_move, "g6g7");
+}
+
+#[test]
+fn perft_runs_until_stopped() {
+    let mut client = UciClient::launch(ENGINE, &[]).unwrap();
+    client.position(&PositionCommand::new(None, vec![])).unwrap();
+    client.go(&GoCommand::new_perft(9)).unwrap();
+    client.is_ready().unwrap();
+    client.stop().unwrap();
+    //the next search waits for the stopped count
+    let reply = client.search(&PositionCommand::new(None, vec![]), &GoCommand::new_depth_tc(false, 1), TIMEOUT).unwrap();
+    assert!(!reply.best_move.is_empty());
+}
+
+/// Free text info lines the engine prints before it answers isready
+fn info_strings_until_ready(client: &mut UciClient) -> Vec<String> {
+    client.send("isready").unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    let mut strings: Vec<String> = vec![];
+    loop {
+        match client.read(deadline).unwrap() {
+            Some(EngineCommand::ReadyOk) => return strings,
+            Some(EngineCommand::Info(info)) => strings.extend(info.string),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn eval_notes_that_the_trace_is_handcrafted_under_nnue() {
+    let mut client = UciClient::launch(ENGINE, &[]).unwrap();
+    client.send("eval").unwrap();
+    assert!(info_strings_until_ready(&mut client).is_empty());
+
+    client.set_option("UseNNUE", "true").unwrap();
+    client.send("eval").unwrap();
+    let strings = info_strings_until_ready(&mut client);
+    assert!(strings.iter().any(|string| string.contains("handcrafted")), "{strings:?}");
+}
+
+#[test]
+fn plays_a_match_game_against_the_searcher() {
+    let engine = TestEngine::new();
+    let mut external = UciPlayer::launch(ENGINE, &[], &[("SyzygyProbeDepth".into(), "2".into())], Some("external"), false).unwrap();
+    let mut internal = SearcherPlayer::new("internal");
+
+    let record = engine_match::play_game(
+        &mut external,
+        &mut internal,
+        "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+        SearchMode::StaticDepth(3),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(record.forfeit.is_none(), "{}", record.termination());
+    assert!(!matches!(record.state, GameState::InProgress));
+}
+
+#[test]
+fn external_engines_castle_in_chess960_openings() {
+    let engine = TestEngine::new();
+    let mut external = UciPlayer::launch(ENGINE, &[], &[], Some("external"), true).unwrap();
+    let mut internal = SearcherPlayer::new("internal");
+
+    //long castling, king b1 to c1 and rook a1 to d1, mates
+    let record = engine_match::play_game(
+        &mut external,
+        &mut internal,
+        "8/8/8/B7/5Q2/3k4/8/RK4N1 w A - 0 1",
+        SearchMode::StaticDepth(2),
+        &engine.move_gen,
+        &engine.zobrist,
+    )
+    .unwrap();
+    assert!(record.forfeit.is_none(), "{}", record.termination());
+    assert!(matches!(record.state, GameState::Checkmate(_)));
+    assert!(_move::is_castle(record.moves[0]));
+}
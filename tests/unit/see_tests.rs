@@ -1,5 +1,23 @@
 use super::*;
-use crate::utils::{fen_tool, zobrist::Zobrist};
+use crate::{repr::{position::Position, types::*}, utils::{fen_tool, zobrist::Zobrist}};
+
+///Asserts the exact exchange value of the uci move in the position and that see_ge agrees with it at the boundary
+fn assert_see(fen: &str, uci: &str, expected: i32) {
+    let move_gen = MoveGen::init();
+    let zobrist = Zobrist::default();
+    let pos = Position::from(fen, &move_gen, &zobrist).expect("valid see test position");
+    let mov: u32 = pos
+        .legal_moves()
+        .iter()
+        .copied()
+        .find(|mov| _move::to_uci(*mov, &pos.board) == uci)
+        .unwrap_or_else(|| panic!("{uci} not legal in {fen}"));
+
+    let mut worker = SeeWorker::default();
+    assert_eq!(worker.see(mov, &pos.board, &move_gen), expected, "{uci} in {fen}");
+    assert!(worker.see_ge(mov, expected, &pos.board, &move_gen), "{uci} in {fen}");
+    assert!(!worker.see_ge(mov, expected + 1, &pos.board, &move_gen), "{uci} in {fen}");
+}
 
 #[test]
 fn promoting_recaptures_use_promoted_value_and_gain() {
@@ -26,35 +44,50 @@ fn promoting_recaptures_use_promoted_value_and_gain() {
         let board = fen_tool::fen_to_board(fen.to_owned(), &move_gen, &zobrist)
             .expect("valid promotion-recapture position");
 
-        assert!(!SeeWorker::default().see_positive(initiating_move, &board, &move_gen,));
+        let mut worker = SeeWorker::default();
+        //wins the queen, loses the rook and the pawn becomes a queen
+        assert_eq!(worker.see(initiating_move, &board, &move_gen), -400);
+        assert!(!worker.see_ge(initiating_move, 0, &board, &move_gen));
     }
 }
 
 #[test]
-fn black_discovered_attackers_preserve_value_groups_and_lvp() {
-    let mut worker = SeeWorker::default();
-    worker.piece_s_indices_black = [NO_ENTRIES_IDX; NOF_PIECE_TYPES_U];
-
-    worker.add_discovered_attacker(16, B_QUEEN_U);
-    worker.add_discovered_attacker(8, B_PAWN_U);
-    worker.add_discovered_attacker(24, B_ROOK_U);
-    worker.add_discovered_attacker(32, B_PAWN_U);
-
-    assert_eq!(worker.attackers_black, vec![16, 24, 8, 32]);
-    assert_eq!(worker.lvp_black, Some(B_PAWN_U));
-    assert_eq!(worker.piece_s_indices_black[W_QUEEN_U], 0);
-    assert_eq!(worker.piece_s_indices_black[W_ROOK_U], 1);
-    assert_eq!(worker.piece_s_indices_black[W_PAWN_U], 2);
-    assert_eq!(worker.piece_s_indices_black[W_KNIGHT_U], NO_ENTRIES_IDX);
-    assert_eq!(worker.piece_s_indices_black[W_BISHOP_U], NO_ENTRIES_IDX);
-
-    assert_eq!(worker.pop_lvp(BLACK), (32, W_PAWN_U));
-    assert_eq!(worker.lvp_black, Some(B_PAWN_U));
-    assert_eq!(worker.pop_lvp(BLACK), (8, W_PAWN_U));
-    assert_eq!(worker.lvp_black, Some(B_ROOK_U));
-    assert_eq!(worker.pop_lvp(BLACK), (24, W_ROOK_U));
-    assert_eq!(worker.lvp_black, Some(B_QUEEN_U));
-    assert_eq!(worker.pop_lvp(BLACK), (16, W_QUEEN_U));
-    assert_eq!(worker.lvp_black, None);
-    assert_eq!(worker.total_attackers, 0);
+fn exchange_values_of_simple_captures() {
+    //Qxh4 hangs nothing, Qxd6 wins a pawn, Qxe5 loses the queen to dxe5
+    assert_see("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1", "d4h4", 320);
+    assert_see("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1", "d4d6", 100);
+    assert_see("4k3/8/3p4/4p3/3Q3n/8/8/4K3 w - - 0 1", "d4e5", -800);
+    //quiet move to an attacked square
+    assert_see("4k3/8/3p4/8/3Q4/8/8/4K3 w - - 0 1", "d4c5", -900);
+}
+
+#[test]
+fn xrays_join_the_exchange_behind_takers() {
+    //rook and queen on the e-file, queen behind the bishop on the long diagonal
+    assert_see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5", -220);
+    //doubled rooks win the defended pawn
+    assert_see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5", 100);
+    assert_see("1k1r4/1pp1r2p/p7/4p3/8/P5P1/1PP1R2P/2K1R3 w - - 0 1", "e2e5", 100);
+}
+
+#[test]
+fn en_passant_removes_the_victim_from_its_own_square() {
+    assert_see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100);
+    assert_see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 0);
+    //the d1 rook sees d6 through the captured pawn's square
+    assert_see("3rk3/8/8/3pP3/8/8/8/3RK3 w - d6 0 1", "e5d6", 100);
+}
+
+#[test]
+fn promotions_count_the_promoted_piece() {
+    assert_see("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", 800);
+    assert_see("r7/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", -100);
+    assert_see("r7/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8n", -100);
+    assert_see("3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7d8q", 1300);
+}
+
+#[test]
+fn king_only_takes_undefended_pieces() {
+    assert_see("8/8/4k3/3p4/8/8/8/3R3K w - - 0 1", "d1d5", -400);
+    assert_see("8/8/4k3/3p4/8/1B6/8/3R3K w - - 0 1", "d1d5", 100);
 }
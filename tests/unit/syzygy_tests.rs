@@ -0,0 +1,414 @@
+use super::*;
+use crate::{
+    repr::move_gen::MoveGen,
+    search::tablebase::LocalTablebase,
+    utils::fen_tool,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BLOCK_BITS: u8 = 10;
+const SPAN_BITS: u8 = 6;
+const MATERIALS: [&str; 5] = ["KQvK", "KRvK", "KBvK", "KNvK", "KPvK"];
+
+///Syzygy files written from the local tables of KPvK and the materials it converts to
+struct Fixture {
+    dir: PathBuf,
+    local: LocalTablebase,
+    syzygy: SyzygyTablebase,
+    move_gen: MoveGen,
+    zobrist: Zobrist,
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let move_gen = MoveGen::init();
+        let zobrist = Zobrist::default();
+        let mut local = LocalTablebase::default();
+        local.generate("KPvK", &move_gen, &zobrist).expect("valid material");
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("rusty_syzygy_{nanos}"));
+        fs::create_dir_all(&dir).unwrap();
+        for name in MATERIALS {
+            for (dtz, suffix) in [(false, WDL_SUFFIX), (true, DTZ_SUFFIX)] {
+                let bytes = syzygy_file(name, dtz, &local, &move_gen, &zobrist);
+                fs::write(dir.join(format!("{name}.{suffix}")), bytes).unwrap();
+            }
+        }
+        let syzygy = SyzygyTablebase::open(dir.to_str().unwrap()).expect("written tables");
+        Fixture { dir, local, syzygy, move_gen, zobrist }
+    })
+}
+
+fn board(fen: &str) -> Board {
+    let fixture = fixture();
+    fen_tool::fen_to_board(fen.to_owned(), &fixture.move_gen, &fixture.zobrist).expect("valid FEN")
+}
+
+#[test]
+fn encoding_tables_have_the_syzygy_sizes() {
+    let encoding = encoding();
+    let kings: Vec<u64> = encoding.map_kk.iter().flatten().copied().filter(|code| *code > 0).collect();
+    assert_eq!(kings.len() + 1, 462);
+    assert_eq!(kings.iter().max(), Some(&461));
+    assert_eq!(encoding.unique_idx(27, 36, 45), 31_311); //all on the diagonal, as high as possible
+    assert_eq!((encoding.map_pawns[8], encoding.map_pawns[15], encoding.map_pawns[52]), (47, 46, 0));
+    assert_eq!(encoding.lead_pawns_size[1], [6; 4]);
+    assert_eq!(encoding.lead_pawns_size[2][0], 47 + 45 + 43 + 41 + 39 + 37);
+    assert_eq!(encoding.binomial[2][4], 6);
+    assert_eq!((encoding.map_a1d1d4[1], encoding.map_a1d1d4[0], encoding.map_a1d1d4[27]), (0, 6, 9));
+}
+
+#[test]
+fn finds_tables_and_rejects_foreign_files() {
+    assert_eq!(fixture().syzygy.names(), ["KBvK", "KNvK", "KPvK", "KQvK", "KRvK"]);
+    assert_eq!(fixture().syzygy.max_pieces(), 3);
+    assert!(SyzygyTablebase::open("/nonexistent/syzygy").is_err());
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let dir = std::env::temp_dir().join(format!("rusty_syzygy_foreign_{nanos}"));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("KQvK.rtbw"), b"RTB1\x04KQvK").unwrap();
+    assert!(SyzygyTablebase::open(dir.to_str().unwrap()).is_err());
+    //a table that fails to parse is a failed probe
+    let wdl: Vec<u8> = fs::read(fixture().dir.join("KQvK.rtbw")).unwrap();
+    fs::write(dir.join("KQvK.rtbw"), &wdl[..wdl.len() / 2]).unwrap();
+    let truncated = SyzygyTablebase::open(dir.to_str().unwrap()).expect("magic is intact");
+    assert_eq!(truncated.probe_wdl(&board("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1"), &fixture().move_gen), None);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn probes_agree_with_the_local_tables() {
+    let fixture = fixture();
+    let move_gen = &fixture.move_gen;
+    let mut probed: usize = 0;
+    for (strong, offset) in [(W_QUEEN_U, 0), (W_ROOK_U, 0), (W_PAWN_U, 0), (W_QUEEN_U, NOF_PIECE_TYPES_U), (W_PAWN_U, NOF_PIECE_TYPES_U)] {
+        for (i, (strong_king, weak_king, piece)) in placements().enumerate() {
+            if i % 211 != 0 || (strong == W_PAWN_U && !(8..56).contains(&piece)) {
+                continue;
+            }
+            let mut pieces: [u64; 12] = [0; 12];
+            pieces[W_KING_U + offset] = 1 << strong_king;
+            pieces[B_KING_U - offset] = 1 << weak_king;
+            pieces[strong + offset] = 1 << piece;
+            for turn in [WHITE, BLACK] {
+                let board = tablebase::board_from_pieces(pieces, turn, None, move_gen, &fixture.zobrist);
+                let opponent = tablebase::board_from_pieces(pieces, turn ^ 1, None, move_gen, &fixture.zobrist);
+                if opponent.nof_checkers > 0 {
+                    continue; //the side not to move is in check
+                }
+                let local = fixture.local.probe_dtz(&board, move_gen);
+                assert_eq!(fixture.syzygy.probe_dtz(&board, move_gen), local, "{}", fen_tool::board_to_fen(&board));
+                assert_eq!(fixture.syzygy.probe_wdl(&board, move_gen), local.map(|(wdl, _)| wdl), "{}", fen_tool::board_to_fen(&board));
+                probed += 1;
+            }
+        }
+    }
+    assert!(probed > 1_000);
+}
+
+/// Tables of the reference generator aren't checked in, point SYZYGY_PATH at a directory holding
+/// the KQvK, KRvK and KPvK files to compare them with the local tables
+#[test]
+#[ignore = "needs Syzygy tables"]
+fn generator_tables_agree_with_the_local_tables() {
+    let fixture = fixture();
+    let move_gen = &fixture.move_gen;
+    let path: String = std::env::var("SYZYGY_PATH").expect("SYZYGY_PATH is set");
+    let syzygy = SyzygyTablebase::open(&path).expect("readable tables");
+    for (strong, offset) in [(W_QUEEN_U, 0), (W_ROOK_U, 0), (W_PAWN_U, 0), (W_ROOK_U, NOF_PIECE_TYPES_U), (W_PAWN_U, NOF_PIECE_TYPES_U)] {
+        for (i, (strong_king, weak_king, piece)) in placements().enumerate() {
+            if i % 97 != 0 || (strong == W_PAWN_U && !(8..56).contains(&piece)) {
+                continue;
+            }
+            let mut pieces: [u64; 12] = [0; 12];
+            pieces[W_KING_U + offset] = 1 << strong_king;
+            pieces[B_KING_U - offset] = 1 << weak_king;
+            pieces[strong + offset] = 1 << piece;
+            for turn in [WHITE, BLACK] {
+                let board = tablebase::board_from_pieces(pieces, turn, None, move_gen, &fixture.zobrist);
+                let opponent = tablebase::board_from_pieces(pieces, turn ^ 1, None, move_gen, &fixture.zobrist);
+                if opponent.nof_checkers > 0 {
+                    continue;
+                }
+                let fen: String = fen_tool::board_to_fen(&board);
+                let (wdl, plies) = fixture.local.probe_dtz(&board, move_gen).expect("local tables");
+                assert_eq!(syzygy.probe_wdl(&board, move_gen), Some(wdl), "{fen}");
+                //the generator may store a win or loss one ply further from zeroing
+                let (probed_wdl, probed_plies) = syzygy.probe_dtz(&board, move_gen).expect("generator tables");
+                assert_eq!(probed_wdl, wdl, "{fen}");
+                assert!(probed_plies.abs_diff(plies) <= 1, "{fen}: {probed_plies} for {plies}");
+            }
+        }
+    }
+}
+
+#[test]
+fn probes_mate_stalemate_and_zeroing_moves() {
+    let fixture = fixture();
+    let probe = |fen: &str| fixture.syzygy.probe_dtz(&board(fen), &fixture.move_gen);
+    assert_eq!(probe("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), Some((Wdl::Loss, 0)));
+    assert_eq!(probe("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some((Wdl::Draw, 0)));
+    //promoting is the zeroing move, the hanging queen is taken
+    assert_eq!(probe("8/4P3/8/8/8/8/k7/4K3 w - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(probe("8/8/8/8/8/8/1Q6/k3K3 b - - 0 1").map(|(wdl, _)| wdl), Some(Wdl::Draw));
+    //black as the stronger side and single value tables
+    assert_eq!(probe("8/8/8/8/8/1k6/2q5/K7 b - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(probe("8/8/8/3k4/8/8/8/3BK3 w - - 0 1"), Some((Wdl::Draw, 0)));
+    assert!(fs::metadata(fixture.dir.join("KBvK.rtbw")).unwrap().len() <= 64);
+    //not covered
+    assert_eq!(probe("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), None);
+    assert_eq!(probe("4k3/8/8/8/8/8/8/4K2R w K - 0 1"), None);
+}
+
+/// Symbols of the hand-built sub-table: code, what the tree stores and what it expands to
+/// - 3 `1`   pair(2, 4) -> 0 1 2 2
+/// - 2 `01`  pair(0, 1) -> 0 1
+/// - 0 `000` value 0, 1 `001` value 1
+/// - 4 (no code) pair(5, 5) -> 2 2, 5 (no code) value 2
+const PAIRS_HEADER: [u8; 36] = [
+    0, 5, 3, 0, //flags, 32 byte blocks, span 8, no padding
+    2, 0, 0, 0, //two blocks
+    3, 1, //codes of one to three bits
+    3, 0, 2, 0, 0, 0, //lowest symbol of lengths 1, 2 and 3
+    6, 0, //symbols
+    0x00, 0xF0, 0xFF, //0: value 0
+    0x01, 0xF0, 0xFF, //1: value 1
+    0x00, 0x10, 0x00, //2: 0, 1
+    0x02, 0x40, 0x00, //3: 2, 4
+    0x05, 0x50, 0x00, //4: 5, 5
+    0x02, 0xF0, 0xFF, //5: value 2
+];
+/// Indices 4 and 12 are found in block 0 at offset 4 and in block 1 at offset 0
+const PAIRS_SPARSE_INDEX: [u8; 12] = [0, 0, 0, 0, 4, 0, 1, 0, 0, 0, 0, 0];
+const PAIRS_BLOCK_LENGTHS: [u8; 4] = [11, 0, 3, 0]; //values minus one
+const PAIRS_BLOCKS: [(usize, [u8; 2]); 2] = [
+    (0, [0b1010_0000, 0b1100_0000]), //1 01 000 001 1: 0 1 2 2, 0 1, 0, 1, 0 1 2 2
+    (32, [0b0101_0000, 0]),          //01 01: 0 1, 0 1
+];
+
+#[test]
+fn decompresses_symbol_pairs_of_hand_built_bytes() {
+    let mut bytes: Vec<u8> = PAIRS_HEADER.to_vec();
+    let mut d = Pairs::default();
+    d.group_idx[0] = 16; //the table size
+    assert_eq!(d.set_sizes(&bytes, 0), Some(PAIRS_HEADER.len()));
+    assert_eq!(d.symlen, [0, 0, 1, 3, 1, 0]);
+    assert_eq!(d.base64, [1 << 63, 1 << 62, 0]);
+
+    d.sparse_index = bytes.len();
+    bytes.extend(PAIRS_SPARSE_INDEX);
+    d.block_length = bytes.len();
+    bytes.extend(PAIRS_BLOCK_LENGTHS);
+    d.data = 64;
+    bytes.resize(128, 0);
+    for (start, block) in PAIRS_BLOCKS {
+        bytes[64 + start..64 + start + 2].copy_from_slice(&block);
+    }
+    let values: Vec<Option<u16>> = (0..16).map(|idx| d.decompress(&bytes, idx)).collect();
+    let expected: [u16; 16] = [0, 1, 2, 2, 0, 1, 0, 1, 0, 1, 2, 2, 0, 1, 0, 1];
+    assert_eq!(values, expected.map(Some));
+
+    //a symbol that points out of the tree is corrupt data
+    bytes[PAIRS_HEADER.len() - 6] = 0x09; //the left child of 4
+    assert_eq!(Pairs { group_idx: d.group_idx, ..Pairs::default() }.set_sizes(&bytes, 0), None);
+}
+
+/// KQvK DTZ file with one stored value and maps of wins, losses, cursed wins and blessed losses
+fn mapped_dtz_file(wide: bool) -> Vec<u8> {
+    let mut bytes: Vec<u8> = DTZ_MAGIC.to_vec();
+    bytes.extend([SPLIT, 0, 0x66, 0xEE, 0x55, 0]); //white king, black king and queen, word aligned
+    bytes.extend([SINGLE_VALUE | MAPPED | WIN_PLIES | if wide { WIDE } else { 0 }, 1]);
+    if wide {
+        bytes.extend([3, 0, 44, 1, 45, 1, 232, 3]); //300 301 1000
+        bytes.extend([2, 0, 0, 1, 255, 1]); //256 511
+        bytes.extend([1, 0, 144, 1]); //400
+        bytes.extend([1, 0, 244, 1]); //500
+    } else {
+        bytes.extend([3, 5, 9, 14]);
+        bytes.extend([2, 4, 8]);
+        bytes.extend([1, 60]);
+        bytes.extend([1, 70]);
+    }
+    bytes.resize(64, 0);
+    bytes
+}
+
+#[test]
+fn dtz_values_go_through_the_hand_built_maps() {
+    let material = Material::new("KQvK", PathBuf::new(), None).unwrap();
+    let table = Table::parse(mapped_dtz_file(false), &material, true).expect("valid file");
+    assert_eq!(table.map, 12);
+    assert_eq!(table.pairs[0][0].map_idx, [1, 5, 8, 10]);
+    assert_eq!(table.pairs[0][0].decompress(&table.bytes, 31_000), Some(1));
+    //wins are stored in plies, the other results in moves
+    assert_eq!(table.map_score(0, 1, WIN), Some(9 + 1));
+    assert_eq!(table.map_score(0, 2, WIN), Some(14 + 1));
+    assert_eq!(table.map_score(0, 0, LOSS), Some(2 * 4 + 1));
+    assert_eq!(table.map_score(0, 0, CURSED_WIN), Some(2 * 60 + 1));
+    assert_eq!(table.map_score(0, 0, BLESSED_LOSS), Some(2 * 70 + 1));
+
+    let table = Table::parse(mapped_dtz_file(true), &material, true).expect("valid file");
+    assert_eq!(table.pairs[0][0].map_idx, [1, 5, 8, 10]);
+    assert_eq!(table.map_score(0, 0, WIN), Some(300 + 1));
+    assert_eq!(table.map_score(0, 2, WIN), Some(1000 + 1));
+    assert_eq!(table.map_score(0, 1, LOSS), Some(2 * 511 + 1));
+    assert_eq!(table.map_score(0, 0, CURSED_WIN), Some(2 * 400 + 1));
+    assert_eq!(table.map_score(0, 0, BLESSED_LOSS), Some(2 * 500 + 1));
+}
+
+/// Squares of the strong king, the weak king and the piece, kings apart
+fn placements() -> impl Iterator<Item = (usize, usize, usize)> {
+    (0..64).flat_map(|strong_king: usize| {
+        (0..64).flat_map(move |weak_king: usize| {
+            (0..64)
+                .filter(move |piece| *piece != strong_king && *piece != weak_king)
+                .filter(move |_| (strong_king & 7).abs_diff(weak_king & 7).max((strong_king >> 3).abs_diff(weak_king >> 3)) > 1)
+                .map(move |piece| (strong_king, weak_king, piece))
+        })
+    })
+}
+
+/// Index of the white pieces on **squares**, in the order of the file, written out from the reference layout
+/// independently of the reader: the file of a leading pawn and the position within its sub-table
+fn reference_idx(has_pawns: bool, squares: [usize; 3]) -> (usize, usize) {
+    let rank = |sqr: usize| sqr >> 3;
+    let file = |sqr: usize| sqr & 7;
+    let mut s: [usize; 3] = squares;
+    if file(s[0]) > 3 {
+        s = s.map(|sqr| sqr ^ 7);
+    }
+    if has_pawns {
+        //pawn ranks 2 to 7, then each king on the squares not taken before it
+        let king = |k: usize| s[k] - s[..k].iter().filter(|sqr| **sqr < s[k]).count();
+        return (file(s[0]), rank(s[0]) - 1 + 6 * king(1) + 6 * 63 * king(2));
+    }
+    if rank(s[0]) > 3 {
+        s = s.map(|sqr| sqr ^ 56);
+    }
+    //the first piece off the a1-h8 diagonal goes below it
+    if let Some(i) = (0..3).find(|i| rank(s[*i]) != file(s[*i])) {
+        if rank(s[i]) > file(s[i]) {
+            s[i..].iter_mut().for_each(|sqr| *sqr = file(*sqr) << 3 | rank(*sqr));
+        }
+    }
+    let below = |sqr: usize| (0..sqr).filter(|other| file(*other) > rank(*other)).count();
+    let triangle: [usize; 6] = [1, 2, 3, 10, 11, 19];
+    let adjust1: usize = (s[1] > s[0]) as usize;
+    let adjust2: usize = (s[2] > s[0]) as usize + (s[2] > s[1]) as usize;
+    let on_diagonal = |sqr: usize| rank(sqr) == file(sqr);
+    let idx: usize = if !on_diagonal(s[0]) {
+        (triangle.iter().position(|sqr| *sqr == s[0]).unwrap() * 63 + s[1] - adjust1) * 62 + s[2] - adjust2
+    } else if !on_diagonal(s[1]) {
+        (6 * 63 + rank(s[0]) * 28 + below(s[1])) * 62 + s[2] - adjust2
+    } else if !on_diagonal(s[2]) {
+        6 * 63 * 62 + 4 * 28 * 62 + rank(s[0]) * 7 * 28 + (rank(s[1]) - adjust1) * 28 + below(s[2])
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(s[0]) * 7 * 6 + (rank(s[1]) - adjust1) * 6 + rank(s[2]) - adjust2
+    };
+    (0, idx)
+}
+
+/// Syzygy file of a three piece **name** with the values of the local tables.
+/// Every value is a fixed length symbol that isn't a pair, tables of one value are stored as such
+fn syzygy_file(name: &str, dtz: bool, local: &LocalTablebase, move_gen: &MoveGen, zobrist: &Zobrist) -> Vec<u8> {
+    let counts: [u32; 12] = tablebase::parse_counts(name).unwrap();
+    let piece: usize = (W_PAWN_U..W_KING_U).find(|kind| counts[*kind] == 1).unwrap();
+    let has_pawns: bool = piece == W_PAWN_U;
+    //the leading pawn or the three unique pieces first
+    let sequence: [usize; 3] = if has_pawns { [piece, W_KING_U, B_KING_U] } else { [W_KING_U, B_KING_U, piece] };
+    let files: usize = if has_pawns { 4 } else { 1 };
+    let sides: usize = if dtz { 1 } else { 2 };
+    let size: usize = if has_pawns { 6 * 63 * 62 } else { 31_332 };
+    let mut values: Vec<Vec<Vec<u16>>> = vec![vec![vec![if dtz { 0 } else { 2 }; size]; sides]; files];
+    for (strong_king, weak_king, first) in placements() {
+        let squares: [usize; 3] = if has_pawns { [first, strong_king, weak_king] } else { [strong_king, weak_king, first] };
+        if has_pawns && !(8..56).contains(&first) {
+            continue;
+        }
+        let mut pieces: [u64; 12] = [0; 12];
+        for (p, sqr) in sequence.iter().zip(squares) {
+            pieces[*p] |= 1 << sqr;
+        }
+        let (tb_file, idx) = reference_idx(has_pawns, squares);
+        for turn in if dtz { vec![WHITE] } else { vec![WHITE, BLACK] } {
+            let board = tablebase::board_from_pieces(pieces, turn, None, move_gen, zobrist);
+            let (wdl, plies) = local.probe_dtz(&board, move_gen).unwrap_or((Wdl::Draw, 0));
+            values[tb_file][turn as usize][idx] = match (dtz, wdl) {
+                (false, wdl) => wdl as u16,
+                (true, Wdl::Win | Wdl::Loss) => plies.max(1) as u16 - 1,
+                (true, _) => 0,
+            };
+        }
+    }
+
+    let flags: u8 = if dtz { WIN_PLIES | LOSS_PLIES } else { 0 };
+    let compressed: Vec<[Vec<u8>; 4]> = values.iter().flatten().map(|values| compress(values, flags)).collect();
+    let mut bytes: Vec<u8> = if dtz { DTZ_MAGIC.to_vec() } else { WDL_MAGIC.to_vec() };
+    bytes.push(SPLIT | if has_pawns { HAS_PAWNS } else { 0 });
+    for _ in 0..files {
+        bytes.push(0);
+        bytes.extend(sequence.iter().map(|p| piece_code(*p) | piece_code(*p) << 4));
+    }
+    for section in 0..4 {
+        //word aligned after the piece sequences and, in DTZ files, after the value maps
+        if (section == 0 || (section == 1 && dtz)) && bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        for parts in compressed.iter() {
+            if section == 3 {
+                bytes.resize(bytes.len().next_multiple_of(64), 0);
+            }
+            bytes.extend(&parts[section]);
+        }
+    }
+    bytes
+}
+
+/// Header, sparse index, block lengths and blocks of one sub-table
+fn compress(values: &[u16], flags: u8) -> [Vec<u8>; 4] {
+    if values.iter().all(|value| *value == values[0]) {
+        return [vec![flags | SINGLE_VALUE, values[0] as u8], vec![], vec![], vec![]];
+    }
+    let symbols: usize = *values.iter().max().unwrap() as usize + 1;
+    let bits: usize = (usize::BITS - (symbols - 1).leading_zeros()) as usize;
+    let block_size: usize = 1 << BLOCK_BITS;
+    let per_block: usize = (block_size * 8 - 64) / bits;
+    let num_blocks: usize = values.len().div_ceil(per_block);
+
+    let mut header: Vec<u8> = vec![flags, BLOCK_BITS, SPAN_BITS, 0];
+    header.extend((num_blocks as u32).to_le_bytes());
+    header.extend([bits as u8, bits as u8, 0, 0]);
+    header.extend((symbols as u16).to_le_bytes());
+    for value in 0..symbols {
+        header.extend([value as u8, (value >> 8) as u8 | ((LEAF & 0xF) << 4) as u8, (LEAF >> 4) as u8]);
+    }
+    if symbols % 2 == 1 {
+        header.push(0);
+    }
+
+    let span: usize = 1 << SPAN_BITS;
+    let mut sparse_index: Vec<u8> = vec![];
+    for k in 0..values.len().div_ceil(span) {
+        let idx: usize = k * span + span / 2;
+        let block: usize = (idx / per_block).min(num_blocks - 1);
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend(((idx - block * per_block) as u16).to_le_bytes());
+    }
+    let mut block_lengths: Vec<u8> = vec![];
+    let mut blocks: Vec<u8> = vec![0; num_blocks * block_size];
+    for (block, chunk) in values.chunks(per_block).enumerate() {
+        block_lengths.extend((chunk.len() as u16 - 1).to_le_bytes());
+        for (i, value) in chunk.iter().enumerate() {
+            for bit in 0..bits {
+                if value >> (bits - 1 - bit) & 1 == 1 {
+                    let pos: usize = block * block_size * 8 + i * bits + bit;
+                    blocks[pos / 8] |= 0x80 >> (pos % 8);
+                }
+            }
+        }
+    }
+    [header, sparse_index, block_lengths, blocks]
+}
@@ -0,0 +1,55 @@
+use super::*;
+
+#[test]
+fn scores_survive_the_byte_encoding() {
+    for score in [0, win(1), win(100), win(101), loss(0), loss(1), loss(100), loss(101)] {
+        assert_eq!(decode(encode(score)), score);
+    }
+    //cursed distances are capped but stay cursed
+    assert_eq!(decode(encode(win(300))), win(MAX_STORED_DTZ));
+    assert_eq!(decode(encode(loss(300))), loss(MAX_STORED_DTZ));
+}
+
+#[test]
+fn zeroing_moves_restart_the_distance() {
+    assert_eq!(after_zeroing(loss(0)), win(1));
+    assert_eq!(after_zeroing(loss(100)), win(1));
+    assert_eq!(after_zeroing(loss(101)), win(101));
+    assert_eq!(after_zeroing(0), 0);
+    assert_eq!(after_zeroing(win(5)), loss(1));
+    assert_eq!(after_zeroing(win(120)), loss(101));
+}
+
+#[test]
+fn materials_are_named_with_the_stronger_side_white() {
+    let counts: [u32; 12] = parse_material("KRvKQ").unwrap();
+    assert_eq!(material_name(&canonical_counts(&counts)), "KQvKR");
+    let counts: [u32; 12] = parse_material("KNvKB").unwrap();
+    assert_eq!(material_name(&canonical_counts(&counts)), "KBvKN");
+    assert_eq!(material_name(&parse_material("KRPvK").unwrap()), "KRPvK");
+    assert!(parse_material("KQRvKR").is_none());
+    assert!(parse_material("QvK").is_none());
+    assert!(parse_material("KXvK").is_none());
+}
+
+#[test]
+fn index_round_trips_and_mirrors_the_key_piece() {
+    for name in ["KQvKR", "KPvKP", "KRPvK"] {
+        let table: LocalTable = LocalTable::new(&parse_material(name).unwrap());
+        for idx in (0..table.data.len()).step_by(997) {
+            let (squares, turn) = table.decode_index(idx);
+            assert_eq!(table.index(&squares[..table.slots.len()], turn), idx, "{name}");
+            let mirrored: Vec<u32> = squares[..table.slots.len()].iter().map(|sqr| sqr ^ 7).collect();
+            assert_eq!(table.index(&mirrored, turn), idx, "{name}");
+        }
+    }
+}
+
+#[test]
+fn conversions_cover_captures_and_promotions() {
+    let names: Vec<String> = conversions(&parse_material("KPvKR").unwrap()).iter().map(material_name).collect();
+    assert_eq!(names, ["KRvK", "KRvKN", "KRvKB", "KRvKR", "KQvKR", "KPvK"]);
+    let all: Vec<String> = all_materials(4);
+    assert_eq!(all.len(), 35);
+    assert_eq!(&all[..5], ["KNvK", "KBvK", "KRvK", "KQvK", "KPvK"]);
+}